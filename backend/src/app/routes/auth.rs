@@ -1,4 +1,7 @@
-use axum::{Json, extract::State};
+use axum::{
+    Json,
+    extract::{Query, State},
+};
 use serde::Deserialize;
 
 use crate::app::cache::keys::CacheKey;
@@ -7,6 +10,7 @@ use crate::app::extractors::auth::AuthUser;
 use crate::app::routes::ok_status;
 use crate::app::services;
 use crate::app::services::auth::parse_server;
+use crate::app::services::replays::RefreshParams;
 use crate::app::state::AppState;
 
 #[derive(Deserialize)]
@@ -101,8 +105,9 @@ pub async fn update_settings(
 pub async fn refresh(
     State(state): State<AppState>,
     auth: AuthUser,
+    Query(params): Query<RefreshParams>,
 ) -> Result<Json<serde_json::Value>, ApiError> {
     let server = parse_server(&auth.server)?;
-    let data = services::roster::refresh(&state, &auth.uid, server).await?;
+    let data = services::roster::refresh(&state, &auth.uid, server, params.replays).await?;
     Ok(Json(data))
 }
@@ -0,0 +1,26 @@
+use axum::Json;
+use axum::extract::{Path, Query, State};
+
+use crate::app::error::ApiError;
+use crate::app::services::changelog::{self, Changelog, DiffParams, ServerDiff};
+use crate::app::state::AppState;
+use crate::core::hypergryph::constants::Server;
+
+/// `GET /changelog/{server}` - what the server's last game data update changed.
+pub async fn changelog(
+    State(state): State<AppState>,
+    Path(server): Path<Server>,
+) -> Result<Json<Changelog>, ApiError> {
+    let body = changelog::changelog(&state, server).await?;
+    Ok(Json(body))
+}
+
+/// `GET /diff/{a}/{b}` - game data differences from server `a` to server `b`.
+pub async fn server_diff(
+    State(state): State<AppState>,
+    Path((a, b)): Path<(Server, Server)>,
+    Query(params): Query<DiffParams>,
+) -> Result<Json<ServerDiff>, ApiError> {
+    let body = changelog::server_diff(&state, a, b, &params).await?;
+    Ok(Json(body))
+}
@@ -1,13 +1,22 @@
 use axum::Json;
-use axum::extract::State;
+use axum::extract::{Query, State};
 use axum::http::HeaderMap;
 use axum::response::Response;
+use serde::Deserialize;
 
 use crate::app::error::ApiError;
+use crate::app::extractors::auth::MaybeAuthUser;
+use crate::app::routes::resolve_user_id;
 use crate::app::routes::static_data::json_response;
 use crate::app::services;
 use crate::app::state::AppState;
-use crate::dps::engine::{DpsResult, HpsResult};
+use crate::dps::engine::HpsResult;
+use crate::dps::timeline::TimelineResult;
+
+#[derive(Deserialize)]
+pub struct UidParams {
+    pub uid: Option<String>,
+}
 
 pub async fn operators(
     State(state): State<AppState>,
@@ -20,8 +29,54 @@ pub async fn operators(
 pub async fn calculate(
     State(state): State<AppState>,
     Json(body): Json<services::dps::CalculateRequest>,
-) -> Result<Json<DpsResult>, ApiError> {
-    let result = services::dps::calculate(&state, body)?;
+) -> Result<Json<services::dps::CalculateResponse>, ApiError> {
+    let result = services::dps::calculate(&state, body).await?;
+    Ok(Json(result))
+}
+
+pub async fn timeline(
+    State(state): State<AppState>,
+    Json(body): Json<services::dps::TimelineRequest>,
+) -> Result<Json<TimelineResult>, ApiError> {
+    let result = services::dps::timeline(&state, body).await?;
+    Ok(Json(result))
+}
+
+pub async fn squad(
+    State(state): State<AppState>,
+    Json(body): Json<services::dps::SquadRequest>,
+) -> Result<Json<services::dps::SquadResult>, ApiError> {
+    let result = services::dps::calculate_squad(&state, body).await?;
+    Ok(Json(result))
+}
+
+pub async fn curve(
+    State(state): State<AppState>,
+    Json(body): Json<services::dps::CurveRequest>,
+) -> Result<Json<services::dps::CurveResult>, ApiError> {
+    let result = services::dps::calculate_curve(&state, body).await?;
+    Ok(Json(result))
+}
+
+pub async fn roster_ranking(
+    State(state): State<AppState>,
+    auth: MaybeAuthUser,
+    Query(params): Query<UidParams>,
+    Json(body): Json<services::roster_dps::RosterDpsRequest>,
+) -> Result<Json<services::roster_dps::RosterDpsRanking>, ApiError> {
+    let user_id = resolve_user_id(&state, &auth, params.uid.as_deref()).await?;
+    let result = services::roster_dps::rank_roster(&state, user_id, body).await?;
+    Ok(Json(result))
+}
+
+pub async fn upgrade_values(
+    State(state): State<AppState>,
+    auth: MaybeAuthUser,
+    Query(params): Query<UidParams>,
+    Json(body): Json<services::roster_dps::UpgradeValueRequest>,
+) -> Result<Json<services::roster_dps::UpgradeValueResult>, ApiError> {
+    let user_id = resolve_user_id(&state, &auth, params.uid.as_deref()).await?;
+    let result = services::roster_dps::upgrade_values(&state, user_id, body).await?;
     Ok(Json(result))
 }
 
@@ -40,3 +95,11 @@ pub async fn calculate_hps(
     let result = services::dps::calculate_hps(&state, body)?;
     Ok(Json(result))
 }
+
+pub async fn heal_scenario(
+    State(state): State<AppState>,
+    Json(body): Json<services::dps::HealScenarioRequest>,
+) -> Result<Json<services::dps::HealScenarioResponse>, ApiError> {
+    let result = services::dps::calculate_heal_scenario(&state, body)?;
+    Ok(Json(result))
+}
@@ -1,6 +1,8 @@
 use axum::{
     Json,
     extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
 };
 use serde::Deserialize;
 use uuid::Uuid;
@@ -11,6 +13,7 @@ use crate::app::services::gacha::get_gacha_settings;
 use crate::app::services::gacha::get_global_stats;
 use crate::app::services::gacha::get_history_envelope;
 use crate::app::services::gacha::get_history_for_char;
+use crate::app::services::gacha::get_luck;
 use crate::app::services::gacha::get_per_banner_stats;
 use crate::app::services::gacha::get_stats;
 use crate::app::services::gacha::get_stored_records;
@@ -136,6 +139,84 @@ pub async fn stats(
     Ok(Json(stats))
 }
 
+#[derive(Deserialize)]
+pub struct LuckParams {
+    #[serde(alias = "pool_id")]
+    #[serde(rename = "poolId", default)]
+    pub pool_id: Option<String>,
+    #[serde(alias = "char_id")]
+    #[serde(rename = "charId", default)]
+    pub char_id: Option<String>,
+}
+
+pub async fn luck(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Query(params): Query<LuckParams>,
+) -> Result<Json<services::gacha::GachaLuck>, ApiError> {
+    let user_id: Uuid = auth.user_uuid()?;
+    let target = match (params.pool_id.as_deref(), params.char_id.as_deref()) {
+        (Some(pool_id), Some(char_id)) => Some((pool_id, char_id)),
+        (None, None) => None,
+        _ => {
+            return Err(ApiError::BadRequest("poolId and charId go together".into()));
+        }
+    };
+    let luck = get_luck(&state, user_id, target).await?;
+    Ok(Json(luck))
+}
+
+pub async fn plan(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(body): Json<services::pull_planner::PullPlanRequest>,
+) -> Result<Json<services::pull_planner::PullPlan>, ApiError> {
+    let user_id: Uuid = auth.user_uuid()?;
+    let plan = services::pull_planner::plan(&state, user_id, &body).await?;
+    Ok(Json(plan))
+}
+
+#[derive(Deserialize)]
+pub struct TransferParams {
+    /// `json`, `csv` or `hypergryph`; imports detect it when omitted.
+    #[serde(default)]
+    pub format: Option<services::gacha_transfer::TransferFormat>,
+}
+
+pub async fn export_history(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Query(params): Query<TransferParams>,
+) -> Result<Response, ApiError> {
+    let user_id: Uuid = auth.user_uuid()?;
+    let format = params
+        .format
+        .unwrap_or(services::gacha_transfer::TransferFormat::Json);
+    let file = services::gacha_transfer::export(&state, user_id, format).await?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, file.content_type.to_owned()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", file.filename),
+            ),
+        ],
+        file.body,
+    )
+        .into_response())
+}
+
+pub async fn import_history(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Query(params): Query<TransferParams>,
+    body: String,
+) -> Result<Json<services::gacha_transfer::ImportResult>, ApiError> {
+    let user_id: Uuid = auth.user_uuid()?;
+    let result = services::gacha_transfer::import(&state, user_id, params.format, &body).await?;
+    Ok(Json(result))
+}
+
 pub async fn get_settings(
     State(state): State<AppState>,
     auth: AuthUser,
@@ -4,8 +4,12 @@ use serde::Deserialize;
 
 use crate::app::error::ApiError;
 use crate::app::extractors::auth::MaybeAuthUser;
+use crate::app::routes::resolve_user_id;
+use crate::app::services::base_simulation::{BaseSimulationParams, simulate};
+use crate::app::services::base_what_if::{BaseWhatIfRequest, BaseWhatIfResponse, what_if};
 use crate::app::services::improvements::{ImprovementsResponse, get_improvements};
 use crate::app::state::AppState;
+use crate::core::grade::base::simulate::BaseSimulation;
 use crate::database::queries::users::find_by_id;
 use crate::database::queries::users::find_by_uid;
 
@@ -24,6 +28,31 @@ pub async fn get_user_improvements(
     Ok(Json(body))
 }
 
+/// Run the base optimizer over a hypothetical layout / roster (see
+/// `services::base_what_if`).
+pub async fn base_what_if(
+    State(state): State<AppState>,
+    auth: MaybeAuthUser,
+    Query(params): Query<ImprovementsParams>,
+    Json(body): Json<BaseWhatIfRequest>,
+) -> Result<Json<BaseWhatIfResponse>, ApiError> {
+    let user_id = resolve_user_id(&state, &auth, params.uid.as_deref()).await?;
+    let body = what_if(&state, user_id, body).await?;
+    Ok(Json(body))
+}
+
+/// Step the recommended shift rotation (or the user's presets) through 24-72
+/// hours of their synced base (see `services::base_simulation`).
+pub async fn base_simulation(
+    State(state): State<AppState>,
+    auth: MaybeAuthUser,
+    Query(params): Query<BaseSimulationParams>,
+) -> Result<Json<BaseSimulation>, ApiError> {
+    let user_id = resolve_user_id(&state, &auth, params.uid.as_deref()).await?;
+    let body = simulate(&state, user_id, &params).await?;
+    Ok(Json(body))
+}
+
 async fn resolve_uid(
     state: &AppState,
     auth: &MaybeAuthUser,
@@ -1,10 +1,13 @@
 use axum::Json;
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use axum::http::header;
 
-use crate::app::services::level::get_level;
+use serde::Deserialize;
+
+use crate::app::services::level::{get_level, get_wave_simulation};
 use crate::app::{error::ApiError, state::AppState};
 use crate::core::hypergryph::constants::Server;
+use crate::core::wave_simulation::{DEFAULT_STEP, MIN_STEP};
 
 /// `GET /level/{stage_id}` - raw Arknights level (camelCased keys, 2D map grid)
 /// for the default (EN) server. Powers the Stage Viewer map + pathing renderer.
@@ -42,3 +45,57 @@ pub async fn get_level_map_srv(
         Json(value),
     ))
 }
+
+#[derive(Deserialize)]
+pub struct SimulationParams {
+    /// Seconds between frames.
+    pub step: Option<f64>,
+}
+
+impl SimulationParams {
+    fn step(&self) -> f64 {
+        // Whole tenths keep the cache key space small.
+        let step = self.step.unwrap_or(DEFAULT_STEP).max(MIN_STEP);
+        (step * 10.0).round() / 10.0
+    }
+}
+
+/// `GET /level/{stage_id}/simulation` - undefended wave replay (per-step
+/// enemy positions, leak timeline, first arrival per tile) for the default
+/// server. Drives the Stage Viewer's wave animation.
+pub async fn get_simulation(
+    State(state): State<AppState>,
+    Path(stage_id): Path<String>,
+    Query(params): Query<SimulationParams>,
+) -> Result<
+    (
+        [(header::HeaderName, &'static str); 1],
+        Json<serde_json::Value>,
+    ),
+    ApiError,
+> {
+    let value = get_wave_simulation(&state, state.default_server, &stage_id, params.step()).await?;
+    Ok((
+        [(header::CACHE_CONTROL, "public, max-age=300")],
+        Json(value),
+    ))
+}
+
+/// `GET /{server}/level/{stage_id}/simulation` - per-server variant.
+pub async fn get_simulation_srv(
+    State(state): State<AppState>,
+    Path((server, stage_id)): Path<(Server, String)>,
+    Query(params): Query<SimulationParams>,
+) -> Result<
+    (
+        [(header::HeaderName, &'static str); 1],
+        Json<serde_json::Value>,
+    ),
+    ApiError,
+> {
+    let value = get_wave_simulation(&state, server, &stage_id, params.step()).await?;
+    Ok((
+        [(header::CACHE_CONTROL, "public, max-age=300")],
+        Json(value),
+    ))
+}
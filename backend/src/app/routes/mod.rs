@@ -1,5 +1,6 @@
 use axum::{
     Json, Router,
+    extract::DefaultBodyLimit,
     routing::{get, post, put},
 };
 use uuid::Uuid;
@@ -46,6 +47,7 @@ pub(crate) async fn resolve_user_id(
 
 pub mod assets;
 pub mod auth;
+pub mod changelog;
 pub mod chibis;
 pub mod dps;
 pub mod enemies;
@@ -58,6 +60,8 @@ pub mod level;
 pub mod operator_notes;
 pub mod operators;
 pub mod planner;
+pub mod recruitment;
+pub mod replays;
 pub mod roster;
 pub mod search;
 pub mod skins;
@@ -68,6 +72,9 @@ pub mod stats;
 pub mod tier_lists;
 pub mod user;
 
+/// Multi-year pull histories run to a few MiB, past axum's 2 MiB default.
+const GACHA_IMPORT_LIMIT: usize = 16 * 1024 * 1024;
+
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/health", get(health::health))
@@ -79,8 +86,10 @@ pub fn router() -> Router<AppState> {
         .route("/leaderboard/distribution", get(leaderboard::distribution))
         .route("/leaderboard/standing", get(leaderboard::standing))
         .route("/search", get(search::search))
+        .route("/gamedata/search", get(search::gamedata))
         .route("/static/{resource}", get(static_data::get_static))
         .route("/level/{stage_id}", get(level::get_level_map))
+        .route("/level/{stage_id}/simulation", get(level::get_simulation))
         .route("/avatar/{id}", get(assets::avatar))
         .route("/portrait/{id}", get(assets::portrait))
         .route("/skill-icon/{id}", get(assets::skill_icon))
@@ -100,12 +109,23 @@ pub fn router() -> Router<AppState> {
         .route("/gacha/history/{char_id}", get(gacha::history_by_char))
         .route("/gacha/stored-records", get(gacha::stored_records))
         .route("/gacha/stats", get(gacha::stats))
+        .route("/gacha/luck", get(gacha::luck))
+        .route("/gacha/plan", post(gacha::plan))
+        .route("/gacha/export", get(gacha::export_history))
+        .route(
+            "/gacha/import",
+            post(gacha::import_history).layer(DefaultBodyLimit::max(GACHA_IMPORT_LIMIT)),
+        )
         .route(
             "/gacha/settings",
             get(gacha::get_settings).post(gacha::update_settings),
         )
         .route("/refresh", post(auth::refresh))
         .route("/roster", get(roster::get_roster))
+        .route("/roster/history", get(roster::get_history))
+        .route("/roster/progress", get(roster::get_progress))
+        .route("/replays", get(replays::list))
+        .route("/replays/{stage_id}", get(replays::view))
         .route("/roster/{operator_id}", get(roster::get_operator))
         .route("/stage-clears", get(stages::get_stage_clears))
         .route(
@@ -120,6 +140,8 @@ pub fn router() -> Router<AppState> {
             "/user/improvements",
             get(improvements::get_user_improvements),
         )
+        .route("/base/what-if", post(improvements::base_what_if))
+        .route("/base/simulate", get(improvements::base_simulation))
         .route("/get-user-supports", get(roster::get_supports))
         .route("/inventory", get(inventory::get_inventory))
         .route("/user-skins", get(skins::get_owned_skins))
@@ -132,15 +154,27 @@ pub fn router() -> Router<AppState> {
         .route("/admin/stats", get(stats::admin_stats))
         .route("/operators/index", get(operators::index))
         .route("/operators/ownership", get(operators::ownership))
+        .route("/operators/query", post(operators::query))
+        .route("/recruitment/solve", get(recruitment::solve))
+        .route("/stages/difficulty", get(stages::difficulty_index))
+        .route("/changelog/{server}", get(changelog::changelog))
+        .route("/diff/{a}/{b}", get(changelog::server_diff))
         .route("/stages/{stage_id}/detail", get(stages::stage_detail))
+        .route("/stages/{stage_id}/enemy-stats", get(stages::enemy_stats))
         .route("/enemies/{id}", get(enemies::enemy_detail))
         .route("/enemies/{id}/stages", get(enemies::enemy_stages))
         .route("/chibis/{operator_id}", get(chibis::chibi_detail))
         .route("/skins/index", get(skins::skins_index))
         .route("/dps/operators", get(dps::operators))
         .route("/dps/calculate", post(dps::calculate))
+        .route("/dps/timeline", post(dps::timeline))
+        .route("/dps/squad", post(dps::squad))
+        .route("/dps/curve", post(dps::curve))
+        .route("/dps/roster", post(dps::roster_ranking))
+        .route("/dps/upgrades", post(dps::upgrade_values))
         .route("/hps/operators", get(dps::healers))
         .route("/hps/calculate", post(dps::calculate_hps))
+        .route("/hps/scenario", post(dps::heal_scenario))
         .route("/operator-notes", get(operator_notes::list))
         .route("/operator-notes/{operator_id}", get(operator_notes::get))
         .route("/operator-notes/{operator_id}", put(operator_notes::update))
@@ -156,6 +190,7 @@ pub fn router() -> Router<AppState> {
         .route("/players/search", get(social::search_players))
         .route("/plans", get(planner::list))
         .route("/plans/public", get(planner::list_public))
+        .route("/plans/farming", post(planner::farming))
         .route(
             "/plan/{operator_id}",
             post(planner::upsert).delete(planner::delete),
@@ -173,15 +208,24 @@ pub fn router() -> Router<AppState> {
         .route("/skins/{id}", get(operators::skins_detail))
         .route("/{server}/skins/{id}", get(operators::skins_detail_srv))
         .route("/{server}/operators/index", get(operators::index_srv))
+        .route("/{server}/operators/query", post(operators::query_srv))
         .route(
             "/{server}/operators/ownership",
             get(operators::ownership_srv),
         )
         .route("/{server}/operators/{id}", get(operators::detail_srv))
+        .route(
+            "/{server}/stages/difficulty",
+            get(stages::difficulty_index_srv),
+        )
         .route(
             "/{server}/stages/{stage_id}/detail",
             get(stages::stage_detail_srv),
         )
+        .route(
+            "/{server}/stages/{stage_id}/enemy-stats",
+            get(stages::enemy_stats_srv),
+        )
         .route("/{server}/enemies/{id}", get(enemies::enemy_detail_srv))
         .route(
             "/{server}/enemies/{id}/stages",
@@ -192,11 +236,16 @@ pub fn router() -> Router<AppState> {
             get(chibis::chibi_detail_srv),
         )
         .route("/{server}/skins/index", get(skins::skins_index_srv))
+        .route("/{server}/gamedata/search", get(search::gamedata_srv))
         .route(
             "/{server}/static/{resource}",
             get(static_data::get_static_srv),
         )
         .route("/{server}/level/{stage_id}", get(level::get_level_map_srv))
+        .route(
+            "/{server}/level/{stage_id}/simulation",
+            get(level::get_simulation_srv),
+        )
         .route("/{server}/avatar/{id}", get(assets::avatar_srv))
         .route("/{server}/portrait/{id}", get(assets::portrait_srv))
         .route("/{server}/skill-icon/{id}", get(assets::skill_icon_srv))
@@ -7,13 +7,15 @@ use axum::{
 
 use crate::app::routes::static_data::json_response;
 use crate::app::services::operators::{
-    OperatorIndexEntry, OperatorOwnershipResponse, get_index, get_operator_json,
-    get_operator_skins, get_operator_voices, get_ownership, get_upcoming, resolve_operator_json,
+    OperatorIndexEntry, OperatorOwnershipResponse, OperatorQueryHit, get_index, get_operator_json,
+    get_operator_skins, get_operator_voices, get_ownership, get_upcoming, query_operators,
+    resolve_operator_json,
 };
 use crate::app::{error::ApiError, state::AppState};
 use crate::core::gamedata::types::skin::SkinData;
 use crate::core::gamedata::types::voice::Voices;
 use crate::core::hypergryph::constants::Server;
+use crate::core::operator_query::OperatorQuery;
 
 /// `GET /operators/index` - default (EN) operator index.
 pub async fn index(
@@ -30,6 +32,24 @@ pub async fn index_srv(
     Ok(Json(get_index(&state, server).await?))
 }
 
+/// `POST /operators/query` - default-server operators matching structured
+/// predicates, optionally sorted by stats at a chosen elite/level.
+pub async fn query(
+    State(state): State<AppState>,
+    Json(body): Json<OperatorQuery>,
+) -> Result<Json<Vec<OperatorQueryHit>>, ApiError> {
+    Ok(Json(query_operators(&state, state.default_server, &body)?))
+}
+
+/// `POST /{server}/operators/query` - per-server operator query.
+pub async fn query_srv(
+    State(state): State<AppState>,
+    Path(server): Path<Server>,
+    Json(body): Json<OperatorQuery>,
+) -> Result<Json<Vec<OperatorQueryHit>>, ApiError> {
+    Ok(Json(query_operators(&state, server, &body)?))
+}
+
 /// `GET /operators/ownership` - default-server operator ownership rates.
 pub async fn ownership(
     State(state): State<AppState>,
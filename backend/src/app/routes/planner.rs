@@ -49,6 +49,18 @@ pub async fn list(
     Ok(Json(response))
 }
 
+/// Stage runs covering what the active plans still miss, from an imported
+/// drop matrix (see `services::farming`).
+pub async fn farming(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Json(body): Json<services::farming::FarmingRequest>,
+) -> Result<Json<services::farming::FarmingPlan>, ApiError> {
+    let user_id = auth.user_uuid()?;
+    let plan = services::farming::farming_plan(&state, user_id, body).await?;
+    Ok(Json(plan))
+}
+
 pub async fn upsert(
     State(state): State<AppState>,
     auth: AuthUser,
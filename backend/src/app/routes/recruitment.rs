@@ -0,0 +1,24 @@
+use axum::Json;
+use axum::extract::{Query, State};
+
+use crate::app::error::ApiError;
+use crate::app::extractors::auth::MaybeAuthUser;
+use crate::app::routes::resolve_user_id;
+use crate::app::services;
+use crate::app::state::AppState;
+
+/// Roster flags apply when a `uid` is given or the caller is signed in;
+/// anonymous calls (e.g. the Discord bot) get the plain solution.
+pub async fn solve(
+    State(state): State<AppState>,
+    auth: MaybeAuthUser,
+    Query(params): Query<services::recruitment::SolveParams>,
+) -> Result<Json<services::recruitment::RecruitSolution>, ApiError> {
+    let user_id = if params.uid.is_some() || auth.0.is_some() {
+        Some(resolve_user_id(&state, &auth, params.uid.as_deref()).await?)
+    } else {
+        None
+    };
+    let solution = services::recruitment::solve_tags(&state, user_id, &params).await?;
+    Ok(Json(solution))
+}
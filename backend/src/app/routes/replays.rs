@@ -0,0 +1,31 @@
+use axum::Json;
+use axum::extract::{Path, Query, State};
+
+use crate::app::error::ApiError;
+use crate::app::extractors::auth::MaybeAuthUser;
+use crate::app::routes::resolve_user_id;
+use crate::app::services::replays::{self, ReplayListEntry, ReplayParams, ReplayView};
+use crate::app::state::AppState;
+
+/// `GET /replays` - stages with an archived auto-deploy replay.
+pub async fn list(
+    State(state): State<AppState>,
+    auth: MaybeAuthUser,
+    Query(params): Query<ReplayParams>,
+) -> Result<Json<Vec<ReplayListEntry>>, ApiError> {
+    let user_id = resolve_user_id(&state, &auth, params.uid.as_deref()).await?;
+    let entries = replays::list(&state, user_id).await?;
+    Ok(Json(entries))
+}
+
+/// `GET /replays/{stageId}` - one replay's squad and action log.
+pub async fn view(
+    State(state): State<AppState>,
+    auth: MaybeAuthUser,
+    Path(stage_id): Path<String>,
+    Query(params): Query<ReplayParams>,
+) -> Result<Json<ReplayView>, ApiError> {
+    let user_id = resolve_user_id(&state, &auth, params.uid.as_deref()).await?;
+    let replay = replays::view(&state, user_id, &stage_id).await?;
+    Ok(Json(replay))
+}
@@ -5,6 +5,9 @@ use serde::Deserialize;
 use crate::app::error::ApiError;
 use crate::app::extractors::auth::MaybeAuthUser;
 use crate::app::routes::resolve_user_id;
+use crate::app::services::roster_history::{
+    self, RosterHistoryPage, RosterHistoryParams, RosterProgress, RosterProgressParams,
+};
 use crate::app::state::AppState;
 use crate::database::models::roster::{RosterEntry, SupportUnit};
 use crate::database::queries::roster;
@@ -46,3 +49,23 @@ pub async fn get_supports(
     let entries = roster::get_supports(&state.db, user_id).await?;
     Ok(Json(entries))
 }
+
+pub async fn get_history(
+    State(state): State<AppState>,
+    auth: MaybeAuthUser,
+    Query(params): Query<RosterHistoryParams>,
+) -> Result<Json<RosterHistoryPage>, ApiError> {
+    let user_id = resolve_user_id(&state, &auth, params.uid.as_deref()).await?;
+    let page = roster_history::history(&state, user_id, &params).await?;
+    Ok(Json(page))
+}
+
+pub async fn get_progress(
+    State(state): State<AppState>,
+    auth: MaybeAuthUser,
+    Query(params): Query<RosterProgressParams>,
+) -> Result<Json<RosterProgress>, ApiError> {
+    let user_id = resolve_user_id(&state, &auth, params.uid.as_deref()).await?;
+    let progress = roster_history::progress(&state, user_id, &params).await?;
+    Ok(Json(progress))
+}
@@ -1,14 +1,17 @@
 use axum::{
     Json,
-    extract::{Query, State},
+    extract::{Path, Query, State},
 };
 use serde::Deserialize;
 
-use crate::app::services::search::search_users;
+use crate::app::services::search::{
+    GameDataSearchParams, GameDataSearchResponse, search_game_data, search_users,
+};
 use crate::app::{
     error::ApiError, extractors::pagination::Pagination, services::search::SearchPage,
     state::AppState,
 };
+use crate::core::hypergryph::constants::Server;
 
 #[derive(Deserialize)]
 pub struct SearchParams {
@@ -31,3 +34,26 @@ pub async fn search(
     .await?;
     Ok(Json(page))
 }
+
+/// `GET /gamedata/search?q=` - operators, skills, talents, items, enemies,
+/// stages and skins on the default server.
+pub async fn gamedata(
+    State(state): State<AppState>,
+    Query(params): Query<GameDataSearchParams>,
+) -> Result<Json<GameDataSearchResponse>, ApiError> {
+    Ok(Json(search_game_data(
+        &state,
+        state.default_server,
+        &params,
+    )?))
+}
+
+/// `GET /{server}/gamedata/search?q=` - the same over a server's own data and
+/// language.
+pub async fn gamedata_srv(
+    State(state): State<AppState>,
+    Path(server): Path<Server>,
+    Query(params): Query<GameDataSearchParams>,
+) -> Result<Json<GameDataSearchResponse>, ApiError> {
+    Ok(Json(search_game_data(&state, server, &params)?))
+}
@@ -8,6 +8,10 @@ use crate::app::error::ApiError;
 use crate::app::extractors::auth::MaybeAuthUser;
 use crate::app::routes::resolve_user_id;
 use crate::app::routes::static_data::json_response;
+use crate::app::services::stage_metrics::{
+    StageIndexParams, StageMetricsPage, stage_metrics_index,
+};
+use crate::app::services::stage_modifiers::{EnemyStatsResponse, RiskParams, get_enemy_stats};
 use crate::app::services::static_data::get_stage_detail;
 use crate::app::state::AppState;
 use crate::core::hypergryph::constants::Server;
@@ -49,6 +53,47 @@ pub async fn stage_detail_srv(
     Ok(json_response(body, &headers))
 }
 
+/// `GET /stages/{stageId}/enemy-stats` - effective enemy stats with the
+/// stage's runes at `difficulty` plus the picked CC `runes` applied.
+pub async fn enemy_stats(
+    State(state): State<AppState>,
+    Path(stage_id): Path<String>,
+    Query(params): Query<RiskParams>,
+) -> Result<Json<EnemyStatsResponse>, ApiError> {
+    let body = get_enemy_stats(&state, state.default_server, &stage_id, &params).await?;
+    Ok(Json(body))
+}
+
+/// `GET /{server}/stages/{stageId}/enemy-stats` - per-server variant.
+pub async fn enemy_stats_srv(
+    State(state): State<AppState>,
+    Path((server, stage_id)): Path<(Server, String)>,
+    Query(params): Query<RiskParams>,
+) -> Result<Json<EnemyStatsResponse>, ApiError> {
+    let body = get_enemy_stats(&state, server, &stage_id, &params).await?;
+    Ok(Json(body))
+}
+
+/// `GET /stages/difficulty` - difficulty metrics for every stage with a level
+/// (default server), filterable by `zone` and sortable by any metric.
+pub async fn difficulty_index(
+    State(state): State<AppState>,
+    Query(params): Query<StageIndexParams>,
+) -> Result<Json<StageMetricsPage>, ApiError> {
+    let page = stage_metrics_index(&state, state.default_server, &params).await?;
+    Ok(Json(page))
+}
+
+/// `GET /{server}/stages/difficulty` - per-server variant.
+pub async fn difficulty_index_srv(
+    State(state): State<AppState>,
+    Path(server): Path<Server>,
+    Query(params): Query<StageIndexParams>,
+) -> Result<Json<StageMetricsPage>, ApiError> {
+    let page = stage_metrics_index(&state, server, &params).await?;
+    Ok(Json(page))
+}
+
 pub async fn get_stage_clears(
     State(state): State<AppState>,
     auth: MaybeAuthUser,
@@ -0,0 +1,85 @@
+//! Hour-by-hour base simulation: run the recommended shift rotation (or the
+//! player's own presets) on their synced base and report where it breaks down -
+//! operators running out of morale, rooms going idle, drones overflowing.
+
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::app::error::ApiError;
+use crate::app::services::improvements::{base_profiles, is_243_layout};
+use crate::app::state::AppState;
+use crate::core::grade::base::buff_registry::{build_name_to_char, build_registry};
+use crate::core::grade::base::shift_rotation::recommend_shift_rotation;
+use crate::core::grade::base::simulate::{
+    BaseSimulation, DroneTarget, MAX_SIMULATION_HOURS, MIN_SIMULATION_HOURS, simulate_rotation,
+};
+use crate::core::grade::base::types::UserBuilding;
+use crate::database::queries::building::get_building;
+use crate::database::queries::roster::get_roster;
+
+const DEFAULT_SIMULATION_HOURS: usize = 48;
+
+#[derive(Debug, Deserialize, Default)]
+pub struct BaseSimulationParams {
+    pub uid: Option<String>,
+    /// 24..=72, default 48.
+    pub hours: Option<usize>,
+    /// Simulate the player's saved presets instead of the recommended crews.
+    #[serde(default)]
+    pub presets: bool,
+    #[serde(default)]
+    pub drones: DroneTarget,
+}
+
+pub async fn simulate(
+    state: &AppState,
+    user_id: Uuid,
+    params: &BaseSimulationParams,
+) -> Result<BaseSimulation, ApiError> {
+    let hours = params.hours.unwrap_or(DEFAULT_SIMULATION_HOURS);
+    if !(MIN_SIMULATION_HOURS..=MAX_SIMULATION_HOURS).contains(&hours) {
+        return Err(ApiError::BadRequest(format!(
+            "hours must be {MIN_SIMULATION_HOURS}..={MAX_SIMULATION_HOURS}"
+        )));
+    }
+
+    let gd = state.default_game_data();
+    let (roster, building_json) = tokio::try_join!(
+        get_roster(&state.db, user_id),
+        get_building(&state.db, user_id),
+    )?;
+    let building = building_json
+        .as_ref()
+        .map(UserBuilding::from_json)
+        .filter(|b| !b.is_empty())
+        .ok_or(ApiError::NotFound)?;
+    // The shift rotation is only built for a 243 layout.
+    if !is_243_layout(&building) {
+        return Err(ApiError::BadRequest(
+            "base simulation needs a 2-4-3 layout".into(),
+        ));
+    }
+
+    let profiles = base_profiles(&roster, &gd);
+    let name_to_char = build_name_to_char(&gd.operators);
+    let (registry, morale_drains) = build_registry(&gd.building.buffs, &name_to_char);
+    let rotation = recommend_shift_rotation(
+        &profiles,
+        &building,
+        &gd.building,
+        &registry,
+        &morale_drains,
+    );
+
+    Ok(simulate_rotation(
+        &rotation,
+        params.presets,
+        &profiles,
+        &building,
+        &gd.building,
+        &registry,
+        &morale_drains,
+        hours,
+        params.drones,
+    ))
+}
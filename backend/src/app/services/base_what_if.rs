@@ -0,0 +1,254 @@
+//! What-if base (RIIC) optimization: run the same optimizers as the base
+//! section of `/user/improvements`, but over a layout, pins and roster the
+//! player describes rather than only the one they have synced - so a 2-4-3 vs
+//! 3-3-3 rebuild, a room upgrade or a planned pull can be weighed before
+//! spending anything on it.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::app::error::ApiError;
+use crate::app::services::improvements::{
+    BaseImprovements, base_profile, base_profiles, optimize_base,
+};
+use crate::app::state::AppState;
+use crate::core::gamedata::types::GameData;
+use crate::core::grade::base::types::{OperatorBaseProfile, UserBuilding, UserRoom};
+use crate::database::queries::building::get_building;
+use crate::database::queries::roster::get_roster;
+
+/// Upper bound on submitted rooms (the full base has well under this).
+const MAX_ROOMS: usize = 40;
+
+const FACTORY_FORMULAS: [&str; 3] = ["F_GOLD", "F_EXP", "F_DIAMOND"];
+
+#[derive(Debug, Deserialize)]
+pub struct PlannedRoom {
+    /// `MANUFACTURE`, `TRADING`, `POWER`, `CONTROL`, `DORMITORY`, ...
+    pub room_type: String,
+    pub level: i32,
+    /// Factory formula (`F_GOLD` / `F_EXP` / `F_DIAMOND`). Factories without
+    /// one are left to the optimizer's gold/EXP split.
+    pub formula: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PinnedOperator {
+    pub operator_id: String,
+    pub room_type: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PlannedOperator {
+    pub operator_id: String,
+    /// Defaults to the operator's highest promotion.
+    pub elite: Option<i16>,
+    /// Defaults to the max level of `elite`.
+    pub level: Option<i16>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct BaseWhatIfRequest {
+    /// The hypothetical layout. Omitted = the user's synced base.
+    pub rooms: Option<Vec<PlannedRoom>>,
+    /// Operators the peak assignment must station in a given room type.
+    #[serde(default)]
+    pub pins: Vec<PinnedOperator>,
+    /// Operators to add to (or re-level in) the roster for this run.
+    #[serde(default)]
+    pub planned_operators: Vec<PlannedOperator>,
+}
+
+/// Headline yield of an optimized base, for side-by-side comparison.
+#[derive(Debug, Clone, Serialize)]
+pub struct BaseYieldSummary {
+    pub yield_lmd_per_day: f64,
+    pub yield_exp_per_day: f64,
+    pub yield_total_value: f64,
+    pub sustained_efficiency: f64,
+}
+
+impl BaseYieldSummary {
+    fn of(base: &BaseImprovements) -> Option<Self> {
+        let optimal = base.optimal.as_ref()?;
+        Some(Self {
+            yield_lmd_per_day: optimal.yield_lmd_per_day,
+            yield_exp_per_day: optimal.yield_exp_per_day,
+            yield_total_value: optimal.yield_total_value,
+            sustained_efficiency: base
+                .rotation
+                .as_ref()
+                .map_or(0.0, |r| r.sustained_efficiency),
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct BaseWhatIfResponse {
+    /// Optimizer output for the submitted scenario. `current` and
+    /// `current_rotation` are only filled when the synced layout is used, since
+    /// a hypothetical layout has nobody stationed in it.
+    pub scenario: BaseImprovements,
+    pub scenario_yield: Option<BaseYieldSummary>,
+    /// The optimum on the user's synced base with their current roster, or
+    /// `None` if they have no synced base.
+    pub baseline_yield: Option<BaseYieldSummary>,
+}
+
+pub async fn what_if(
+    state: &AppState,
+    user_id: Uuid,
+    req: BaseWhatIfRequest,
+) -> Result<BaseWhatIfResponse, ApiError> {
+    let gd = state.default_game_data();
+
+    let (roster, building_json) = tokio::try_join!(
+        get_roster(&state.db, user_id),
+        get_building(&state.db, user_id),
+    )?;
+    let synced = building_json
+        .as_ref()
+        .map(UserBuilding::from_json)
+        .filter(|b| !b.is_empty());
+
+    let owned = base_profiles(&roster, &gd);
+    let profiles = with_planned(owned.clone(), &req.planned_operators, &gd)?;
+
+    let hypothetical = req.rooms.is_some();
+    let building = match req.rooms {
+        Some(rooms) => planned_building(&rooms, &gd)?,
+        None => synced
+            .as_ref()
+            .cloned()
+            .ok_or_else(|| ApiError::BadRequest("no synced base; submit `rooms`".into()))?,
+    };
+
+    let pins = resolve_pins(&req.pins, &profiles, &building)?;
+
+    let mut scenario = optimize_base(&profiles, &building, &gd, &pins);
+    if hypothetical {
+        scenario.current = None;
+        scenario.current_rotation = None;
+    }
+    let baseline_yield = synced
+        .as_ref()
+        .and_then(|b| BaseYieldSummary::of(&optimize_base(&owned, b, &gd, &[])));
+
+    Ok(BaseWhatIfResponse {
+        scenario_yield: BaseYieldSummary::of(&scenario),
+        scenario,
+        baseline_yield,
+    })
+}
+
+fn planned_building(rooms: &[PlannedRoom], gd: &GameData) -> Result<UserBuilding, ApiError> {
+    if rooms.is_empty() || rooms.len() > MAX_ROOMS {
+        return Err(ApiError::BadRequest(format!(
+            "rooms must have 1..={MAX_ROOMS} entries"
+        )));
+    }
+
+    let mut counts: HashMap<&str, i32> = HashMap::new();
+    let mut out = Vec::with_capacity(rooms.len());
+    for (i, room) in rooms.iter().enumerate() {
+        let def =
+            gd.building.rooms.get(&room.room_type).ok_or_else(|| {
+                ApiError::BadRequest(format!("unknown room type {}", room.room_type))
+            })?;
+        let max_level = def.phases.len() as i32;
+        if !(1..=max_level).contains(&room.level) {
+            return Err(ApiError::BadRequest(format!(
+                "{} level must be 1..={max_level}",
+                room.room_type
+            )));
+        }
+        let count = counts.entry(room.room_type.as_str()).or_default();
+        *count += 1;
+        if def.max_count > 0 && *count > def.max_count {
+            return Err(ApiError::BadRequest(format!(
+                "at most {} {} rooms",
+                def.max_count, room.room_type
+            )));
+        }
+        if let Some(formula) = &room.formula
+            && (room.room_type != "MANUFACTURE" || !FACTORY_FORMULAS.contains(&formula.as_str()))
+        {
+            return Err(ApiError::BadRequest(format!(
+                "invalid formula {formula} for {}",
+                room.room_type
+            )));
+        }
+        out.push(UserRoom {
+            slot_id: format!("plan_{i}"),
+            room_type: room.room_type.clone(),
+            level: room.level,
+            current_formula: room.formula.clone(),
+            ..Default::default()
+        });
+    }
+    Ok(UserBuilding { rooms: out })
+}
+
+/// Add planned operators to the owned profiles, replacing an owned operator's
+/// profile when it's listed (a planned promotion).
+fn with_planned(
+    mut profiles: Vec<OperatorBaseProfile>,
+    planned: &[PlannedOperator],
+    gd: &GameData,
+) -> Result<Vec<OperatorBaseProfile>, ApiError> {
+    for p in planned {
+        let op = gd
+            .operators
+            .get(&p.operator_id)
+            .ok_or_else(|| ApiError::BadRequest(format!("unknown operator {}", p.operator_id)))?;
+        let max_elite = op.phases.len().saturating_sub(1) as i16;
+        let elite = p.elite.unwrap_or(max_elite);
+        let phase = usize::try_from(elite)
+            .ok()
+            .and_then(|e| op.phases.get(e))
+            .ok_or_else(|| {
+                ApiError::BadRequest(format!("{} elite must be 0..={max_elite}", p.operator_id))
+            })?;
+        let max_level = phase.max_level as i16;
+        let level = p.level.unwrap_or(max_level);
+        if !(1..=max_level).contains(&level) {
+            return Err(ApiError::BadRequest(format!(
+                "{} level must be 1..={max_level} at E{elite}",
+                p.operator_id
+            )));
+        }
+        // Operators without base skills simply don't contribute.
+        let Some(profile) = base_profile(&p.operator_id, elite, level, gd) else {
+            continue;
+        };
+        profiles.retain(|o| o.char_id != p.operator_id);
+        profiles.push(profile);
+    }
+    Ok(profiles)
+}
+
+fn resolve_pins(
+    pins: &[PinnedOperator],
+    profiles: &[OperatorBaseProfile],
+    building: &UserBuilding,
+) -> Result<Vec<(String, String)>, ApiError> {
+    pins.iter()
+        .map(|pin| {
+            if !profiles.iter().any(|o| o.char_id == pin.operator_id) {
+                return Err(ApiError::BadRequest(format!(
+                    "{} is neither owned nor planned",
+                    pin.operator_id
+                )));
+            }
+            if !building.rooms.iter().any(|r| r.room_type == pin.room_type) {
+                return Err(ApiError::BadRequest(format!(
+                    "no {} room to pin {} to",
+                    pin.room_type, pin.operator_id
+                )));
+            }
+            Ok((pin.operator_id.clone(), pin.room_type.clone()))
+        })
+        .collect()
+}
@@ -0,0 +1,148 @@
+//! Game data diffs (`core::gamedata_diff`): a server's last hot reload against
+//! the load it replaced, and one server against another.
+//!
+//! The replaced load is held in memory only, so after a restart there is no
+//! changelog until the next reload.
+
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::app::cache::keys::CacheKey;
+use crate::app::error::ApiError;
+use crate::app::state::{AppState, ServerData};
+use crate::core::gamedata::types::GameData;
+use crate::core::gamedata_diff::{DiffOptions, diff};
+use crate::core::hypergryph::constants::Server;
+
+#[derive(Deserialize)]
+pub struct DiffParams {
+    /// Also compare names and descriptions; off by default since they differ
+    /// by language between servers.
+    pub text: Option<bool>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Changelog {
+    pub server: &'static str,
+    /// When the previous load was replaced; `None` before the first reload
+    /// since startup, with `diff` empty too.
+    pub replaced_at: Option<DateTime<Utc>>,
+    /// A [`GameDataDiff`](crate::core::gamedata_diff::GameDataDiff).
+    pub diff: Option<Value>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerDiff {
+    pub from: &'static str,
+    pub to: &'static str,
+    pub diff: Value,
+}
+
+/// Cache identity of a pair of loads, by their generations. Generations are
+/// never reused, so cached diffs of superseded data are simply never looked
+/// up again.
+fn loads_hash(generations: [u64; 2], opts: DiffOptions) -> u64 {
+    let mut h = DefaultHasher::new();
+    (generations, opts.text).hash(&mut h);
+    h.finish()
+}
+
+/// Diff off the async runtime (it serializes every entity of both loads),
+/// cached under `loads` (a [`loads_hash`]) when given.
+async fn cached_diff(
+    state: &AppState,
+    resource: &str,
+    server: Server,
+    loads: Option<u64>,
+    old: Arc<GameData>,
+    new: Arc<GameData>,
+    opts: DiffOptions,
+) -> Result<Value, ApiError> {
+    let key = loads.map(|fields_hash| CacheKey::StaticData {
+        resource,
+        server: server.as_str(),
+        fields_hash,
+        page: 0,
+    });
+    if let Some(key) = &key
+        && let Some(cached) = state.cache.get::<Value>(key).await
+    {
+        return Ok(cached);
+    }
+
+    let value = tokio::task::spawn_blocking(move || serde_json::to_value(diff(&old, &new, opts)))
+        .await
+        .map_err(|e| ApiError::Internal(e.into()))?
+        .map_err(|e| ApiError::Internal(e.into()))?;
+
+    if let Some(key) = &key {
+        state.cache.set(key, &value).await;
+    }
+    Ok(value)
+}
+
+/// What the last hot reload of `server` changed. Names and descriptions are
+/// compared too: both loads are the same language.
+pub async fn changelog(state: &AppState, server: Server) -> Result<Changelog, ApiError> {
+    let sd = state.try_server_data(server).ok_or(ApiError::NotFound)?;
+    let Some(previous) = sd.previous_game_data.load_full() else {
+        return Ok(Changelog {
+            server: server.as_str(),
+            replaced_at: None,
+            diff: None,
+        });
+    };
+
+    let opts = DiffOptions { text: true };
+    let value = cached_diff(
+        state,
+        "changelog",
+        server,
+        Some(loads_hash([previous.generation; 2], opts)),
+        Arc::clone(&previous.game_data),
+        Arc::clone(&previous.replaced_by),
+        opts,
+    )
+    .await?;
+    Ok(Changelog {
+        server: server.as_str(),
+        replaced_at: Some(previous.replaced_at),
+        diff: Some(value),
+    })
+}
+
+/// Changes going from `from`'s data to `to`'s; with `from` = EN and `to` =
+/// CN, what EN has yet to receive.
+pub async fn server_diff(
+    state: &AppState,
+    from: Server,
+    to: Server,
+    params: &DiffParams,
+) -> Result<ServerDiff, ApiError> {
+    let old = state.try_server_data(from).ok_or(ApiError::NotFound)?;
+    let new = state.try_server_data(to).ok_or(ApiError::NotFound)?;
+    let opts = DiffOptions {
+        text: params.text.unwrap_or(false),
+    };
+    // A reload bumps the generation before swapping the data, so unchanged
+    // generations around the loads mean the pair is the one they name.
+    let generations =
+        || [&old, &new].map(|sd: &Arc<ServerData>| sd.generation.load(Ordering::SeqCst));
+    let before = generations();
+    let (old_data, new_data) = (old.game_data.load_full(), new.game_data.load_full());
+    let loads = (generations() == before).then(|| loads_hash(before, opts));
+    let resource = format!("diff:{}", to.as_str());
+    let diff = cached_diff(state, &resource, from, loads, old_data, new_data, opts).await?;
+    Ok(ServerDiff {
+        from: from.as_str(),
+        to: to.as_str(),
+        diff,
+    })
+}
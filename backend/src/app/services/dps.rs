@@ -1,16 +1,25 @@
 use crate::app::cache::keys::CacheKey;
 use crate::app::cache::{CachedJson, cached_json};
 use crate::app::error::ApiError;
+use crate::app::services::level::{difficulty_param, get_stage_map};
 use crate::app::state::AppState;
+use crate::core::gamedata::types::GameData;
+use crate::core::gamedata::types::enemy::EnemyAttributes;
+use crate::core::gamedata::types::level::Modifier;
 use crate::core::gamedata::types::module::ModuleType;
 use crate::core::gamedata::types::operator::{Operator, OperatorModule};
 use crate::dps::engine::{
     self, DpsResult, HpsResult, OperatorFormula, calculate_dps, supported_healers,
     supported_operators,
 };
+use crate::dps::healing::{HealScenario, HealScenarioResult};
 use crate::dps::operator_unit::{
     EnemyStats, OperatorBuffs, OperatorConditionals, OperatorParams, OperatorShred,
 };
+use crate::dps::squad::{
+    MAX_SQUAD_SIZE, ReceivedEffects, SquadEffect, apply_received, received_effects,
+};
+use crate::dps::timeline::{MAX_DURATION, TimelineResult};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -40,7 +49,7 @@ type OperatorModuleRef<'a> = &'a OperatorModule;
 
 /// The operator's ADVANCED modules, sorted by uniequip number - the same order
 /// `OperatorData` uses, so a formula module's position indexes into it.
-fn advanced_modules_sorted(operator: &Operator) -> Vec<OperatorModuleRef<'_>> {
+pub(crate) fn advanced_modules_sorted(operator: &Operator) -> Vec<OperatorModuleRef<'_>> {
     let mut mods: Vec<_> = operator
         .modules
         .iter()
@@ -166,8 +175,8 @@ pub struct CalculateRequest {
     pub module_index: Option<i32>,
     pub module_level: Option<i32>,
     // Enemy
-    pub defense: Option<f64>,
-    pub res: Option<f64>,
+    #[serde(flatten)]
+    pub enemy: EnemyProfile,
     // Buffs
     pub buffs: Option<RequestBuffs>,
     pub shred: Option<RequestShred>,
@@ -179,6 +188,175 @@ pub struct CalculateRequest {
     pub all_cond: Option<bool>,
 }
 
+/// The enemy a calculation runs against: either raw DEF/RES, a handbook enemy,
+/// or both (raw values override the handbook's).
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct EnemyProfile {
+    pub defense: Option<f64>,
+    pub res: Option<f64>,
+    /// Enemy handbook id. Resolves DEF/RES/HP from the handbook; an explicit
+    /// `defense`/`res` still overrides the resolved value.
+    pub enemy_id: Option<String>,
+    /// `enemy_database` level entry. Defaults to the level `stage_id` spawns
+    /// the enemy at, else the base entry.
+    pub enemy_level: Option<i32>,
+    /// Stage whose modifiers (CC/IS runes) apply to the enemy.
+    pub stage_id: Option<String>,
+    /// Stage difficulty the stage's runes are filtered by; `NORMAL` unless
+    /// given.
+    pub difficulty: Option<String>,
+    /// Extra modifiers on top of the stage's own.
+    pub modifiers: Option<Vec<Modifier>>,
+}
+
+/// The resolved enemy behind `enemyId`, and how long the operator takes to
+/// kill it.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnemyTarget {
+    pub enemy_id: String,
+    pub name: String,
+    pub level: i32,
+    pub max_hp: i32,
+    pub defense: f64,
+    pub res: f64,
+    pub hp_recovery_per_sec: f64,
+    /// Seconds to kill at `averageDps`; `None` if it can't outpace regen.
+    pub time_to_kill: Option<f64>,
+    /// Seconds to kill at `skillDps`.
+    pub skill_time_to_kill: Option<f64>,
+    /// Skill damage beyond the enemy's HP - what a single target wastes.
+    pub overkill: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalculateResponse {
+    #[serde(flatten)]
+    pub dps: DpsResult,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<EnemyTarget>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineRequest {
+    #[serde(flatten)]
+    pub calc: CalculateRequest,
+    /// Simulated window in seconds. Defaults to 60, capped at `MAX_DURATION`.
+    pub duration: Option<f64>,
+    /// Incoming hits per second, for skills that charge when taking damage.
+    pub hits_taken_per_second: Option<f64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealScenarioRequest {
+    #[serde(flatten)]
+    pub calc: CalculateRequest,
+    #[serde(flatten)]
+    pub scenario: HealScenario,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealScenarioResponse {
+    pub hps: HpsResult,
+    pub scenario: HealScenarioResult,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SquadRequest {
+    pub members: Vec<CalculateRequest>,
+    /// Enemy stats for members that don't set their own.
+    pub defense: Option<f64>,
+    pub res: Option<f64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SquadMemberResult {
+    pub operator_id: String,
+    pub name: String,
+    /// Effects this member applies to the rest of the squad.
+    pub provides: Vec<SquadEffect>,
+    /// Effects received from the rest of the squad, uptime-weighted.
+    pub received: ReceivedEffects,
+    /// `None` for members without a DPS formula (pure supports).
+    pub dps: Option<DpsResult>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SquadResult {
+    pub members: Vec<SquadMemberResult>,
+    pub total_skill_dps: f64,
+    pub total_average_dps: f64,
+}
+
+/// Which enemy stat a curve sweeps. `Grid` sweeps both, DEF-major.
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CurveAxis {
+    #[default]
+    Defense,
+    Res,
+    Grid,
+}
+
+/// Largest number of samples along one axis.
+const MAX_CURVE_SAMPLES: usize = 101;
+/// Largest number of configurations in one curve request.
+const MAX_CURVE_CONFIGS: usize = 20;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurveRequest {
+    /// One series per configuration. Each config's own `defense`/`res` (or
+    /// resolved `enemyId`) fixes the stat that isn't swept.
+    pub configs: Vec<CalculateRequest>,
+    #[serde(default)]
+    pub axis: CurveAxis,
+    /// Sweep upper bounds; default to `OperatorParams`' 3000 DEF / 120 RES.
+    pub max_def: Option<f64>,
+    pub max_res: Option<f64>,
+    /// Samples per axis, endpoints included. Defaults to 31.
+    pub samples: Option<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurvePoint {
+    pub defense: f64,
+    pub res: f64,
+    pub skill_dps: f64,
+    pub total_damage: f64,
+    pub average_dps: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurveSeries {
+    pub operator_id: String,
+    pub name: String,
+    pub skill_index: Option<i32>,
+    pub module_index: Option<i32>,
+    /// `None` when the configuration can't be calculated.
+    pub points: Option<Vec<CurvePoint>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurveResult {
+    /// DEF values swept; empty when only RES is.
+    pub defense: Vec<f64>,
+    /// RES values swept; empty when only DEF is.
+    pub res: Vec<f64>,
+    pub series: Vec<CurveSeries>,
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RequestBuffs {
@@ -237,22 +415,352 @@ fn build_params(req: CalculateRequest) -> OperatorParams {
     }
 }
 
-pub fn calculate(state: &AppState, req: CalculateRequest) -> Result<DpsResult, ApiError> {
+/// An enemy resolved from the handbook, with stage/request modifiers applied.
+pub(crate) struct ResolvedEnemy {
+    enemy_id: String,
+    name: String,
+    level: i32,
+    attributes: EnemyAttributes,
+}
+
+/// Resolve `enemy_id` (+ `stage_id` / `modifiers`) to full stats. `Ok(None)`
+/// when the request names no enemy.
+pub(crate) async fn resolve_enemy(
+    state: &AppState,
+    req: &EnemyProfile,
+) -> Result<Option<ResolvedEnemy>, ApiError> {
+    let Some(enemy_id) = req.enemy_id.as_deref() else {
+        return Ok(None);
+    };
+
+    let stage_map = match req.stage_id.as_deref() {
+        Some(stage_id) => Some(get_stage_map(state, state.default_server, stage_id).await?),
+        None => None,
+    };
+
+    let gd = state.default_game_data();
+    let enemy = gd
+        .enemies
+        .enemy_data
+        .get(enemy_id)
+        .ok_or(ApiError::NotFound)?;
+
+    let stage_level = stage_map.as_ref().and_then(|m| {
+        m.roster
+            .iter()
+            .find(|r| r.enemy_id == enemy_id)
+            .map(|r| r.stat_level)
+    });
+    let level = req.enemy_level.or(stage_level).unwrap_or(0);
+    let mut attributes = enemy
+        .level_stats(level)
+        .map(|l| l.attributes.clone())
+        .ok_or_else(|| {
+            ApiError::BadRequest(format!("enemy {enemy_id} has no stats at level {level}"))
+        })?;
+
+    apply_modifiers(
+        enemy_id,
+        &mut attributes,
+        stage_map.as_ref().map_or(&[], |m| m.modifiers.as_slice()),
+        req.modifiers.as_deref().unwrap_or_default(),
+        difficulty_param(req.difficulty.as_deref())?,
+    );
+
+    Ok(Some(ResolvedEnemy {
+        enemy_id: enemy_id.to_owned(),
+        name: enemy.name.clone(),
+        level,
+        attributes,
+    }))
+}
+
+/// The stage's runes active at `difficulty`, then the request's own modifiers.
+fn apply_modifiers(
+    enemy_id: &str,
+    attributes: &mut EnemyAttributes,
+    stage: &[Modifier],
+    extra: &[Modifier],
+    difficulty: &str,
+) {
+    let active = stage.iter().filter(|m| m.in_difficulty(difficulty));
+    for modifier in active.chain(extra) {
+        modifier.apply_to_enemy(enemy_id, attributes);
+    }
+}
+
+/// Enemy DEF/RES for a request: explicit values win, then the resolved enemy,
+/// then 0.
+pub(crate) fn enemy_stats(req: &EnemyProfile, resolved: Option<&ResolvedEnemy>) -> EnemyStats {
+    EnemyStats {
+        defense: req
+            .defense
+            .or_else(|| resolved.map(|e| f64::from(e.attributes.def)))
+            .unwrap_or(0.0),
+        res: req
+            .res
+            .or_else(|| resolved.map(|e| e.attributes.magic_resistance))
+            .unwrap_or(0.0),
+    }
+}
+
+pub async fn calculate(
+    state: &AppState,
+    req: CalculateRequest,
+) -> Result<CalculateResponse, ApiError> {
+    let resolved = resolve_enemy(state, &req.enemy).await?;
+
     let gd = state.default_game_data();
     let operator = gd
         .operators
         .get(&req.operator_id)
         .ok_or(ApiError::NotFound)?;
 
-    let enemy = EnemyStats {
-        defense: req.defense.unwrap_or(0.0),
-        res: req.res.unwrap_or(0.0),
-    };
+    let enemy = enemy_stats(&req.enemy, resolved.as_ref());
     let params = build_params(req);
 
-    calculate_dps(operator, params, &enemy).ok_or(ApiError::BadRequest(
+    let dps = calculate_dps(operator, params, &enemy).ok_or(ApiError::BadRequest(
         "DPS calculation failed for this operator/config".into(),
-    ))
+    ))?;
+
+    let target = resolved.map(|e| {
+        let hp = f64::from(e.attributes.max_hp);
+        let regen = e.attributes.hp_recovery_per_sec;
+        EnemyTarget {
+            time_to_kill: engine::time_to_kill(hp, dps.average_dps, regen),
+            skill_time_to_kill: engine::time_to_kill(hp, dps.skill_dps, regen),
+            overkill: (dps.total_damage - hp).max(0.0),
+            enemy_id: e.enemy_id,
+            name: e.name,
+            level: e.level,
+            max_hp: e.attributes.max_hp,
+            defense: enemy.defense,
+            res: enemy.res,
+            hp_recovery_per_sec: regen,
+        }
+    });
+
+    Ok(CalculateResponse { dps, target })
+}
+
+pub async fn timeline(state: &AppState, req: TimelineRequest) -> Result<TimelineResult, ApiError> {
+    let duration = req.duration.unwrap_or(60.0);
+    if !(1.0..=MAX_DURATION).contains(&duration) {
+        return Err(ApiError::BadRequest(format!(
+            "duration must be between 1 and {MAX_DURATION} seconds"
+        )));
+    }
+    let hits_taken = req.hits_taken_per_second.unwrap_or(0.0);
+    let resolved = resolve_enemy(state, &req.calc.enemy).await?;
+
+    let gd = state.default_game_data();
+    let operator = gd
+        .operators
+        .get(&req.calc.operator_id)
+        .ok_or(ApiError::NotFound)?;
+
+    let enemy = enemy_stats(&req.calc.enemy, resolved.as_ref());
+    let params = build_params(req.calc);
+
+    engine::calculate_timeline(operator, params, &enemy, duration, hits_taken).ok_or(
+        ApiError::BadRequest("DPS timeline failed for this operator/config".into()),
+    )
+}
+
+pub async fn calculate_squad(state: &AppState, req: SquadRequest) -> Result<SquadResult, ApiError> {
+    if req.members.is_empty() || req.members.len() > MAX_SQUAD_SIZE {
+        return Err(ApiError::BadRequest(format!(
+            "a squad needs between 1 and {MAX_SQUAD_SIZE} members"
+        )));
+    }
+
+    let gd = state.default_game_data();
+    let mut members = Vec::with_capacity(req.members.len());
+    for mut member in req.members {
+        let operator = gd
+            .operators
+            .get(&member.operator_id)
+            .ok_or(ApiError::NotFound)?;
+        if member.enemy.enemy_id.is_none() {
+            member.enemy.defense = member.enemy.defense.or(req.defense);
+            member.enemy.res = member.enemy.res.or(req.res);
+        }
+        let resolved = resolve_enemy(state, &member.enemy).await?;
+        let enemy = enemy_stats(&member.enemy, resolved.as_ref());
+        let params = build_params(member);
+        let provides = engine::squad_effects(operator, params.clone());
+        members.push((operator, enemy, params, provides));
+    }
+
+    let provided: Vec<Vec<SquadEffect>> = members.iter().map(|m| m.3.clone()).collect();
+    let results: Vec<SquadMemberResult> = members
+        .into_iter()
+        .enumerate()
+        .map(|(i, (operator, enemy, mut params, provides))| {
+            let received = received_effects(&provided, i);
+            apply_received(&mut params, &received);
+            SquadMemberResult {
+                operator_id: operator.id.clone().unwrap_or_default(),
+                name: operator.name.clone(),
+                provides,
+                received,
+                dps: calculate_dps(operator, params, &enemy),
+            }
+        })
+        .collect();
+
+    let dps = results.iter().filter_map(|m| m.dps.as_ref());
+    let total_skill_dps = dps.clone().map(|d| d.skill_dps).sum();
+    let total_average_dps = dps.map(|d| d.average_dps).sum();
+
+    Ok(SquadResult {
+        members: results,
+        total_skill_dps,
+        total_average_dps,
+    })
+}
+
+/// `samples` evenly spaced values from 0 to `max`, inclusive.
+fn sweep(max: f64, samples: usize) -> Vec<f64> {
+    let step = max / (samples - 1) as f64;
+    (0..samples).map(|i| step * i as f64).collect()
+}
+
+pub async fn calculate_curve(state: &AppState, req: CurveRequest) -> Result<CurveResult, ApiError> {
+    if req.configs.is_empty() || req.configs.len() > MAX_CURVE_CONFIGS {
+        return Err(ApiError::BadRequest(format!(
+            "a curve needs between 1 and {MAX_CURVE_CONFIGS} configs"
+        )));
+    }
+    let samples = req.samples.unwrap_or(31).clamp(2, MAX_CURVE_SAMPLES);
+    let max_def = req.max_def.unwrap_or(3000.0).max(0.0);
+    let max_res = req.max_res.unwrap_or(120.0).max(0.0);
+
+    let def_sweep = sweep(max_def, samples);
+    let res_sweep = sweep(max_res, samples);
+
+    // Enemy resolution may hit the stage loader, so do it up front.
+    let mut configs = Vec::with_capacity(req.configs.len());
+    for config in req.configs {
+        let resolved = resolve_enemy(state, &config.enemy).await?;
+        let fixed = enemy_stats(&config.enemy, resolved.as_ref());
+        configs.push((config, fixed));
+    }
+
+    let (defense, res) = match req.axis {
+        CurveAxis::Defense => (def_sweep, Vec::new()),
+        CurveAxis::Res => (Vec::new(), res_sweep),
+        CurveAxis::Grid => (def_sweep, res_sweep),
+    };
+
+    // Each series sweeps the whole grid on rayon; keep it off the runtime.
+    let gd = state.default_game_data();
+    let (def_axis, res_axis) = (defense.clone(), res.clone());
+    let series = tokio::task::spawn_blocking(move || {
+        configs
+            .into_iter()
+            .map(|(config, fixed)| curve_series(&gd, config, fixed, &def_axis, &res_axis))
+            .collect::<Result<Vec<_>, ApiError>>()
+    })
+    .await
+    .map_err(|e| ApiError::Internal(e.into()))??;
+
+    Ok(CurveResult {
+        defense,
+        res,
+        series,
+    })
+}
+
+/// One config's DPS across the DEF/RES grid (its own fixed value on an
+/// unswept axis).
+fn curve_series(
+    gd: &GameData,
+    config: CalculateRequest,
+    fixed: EnemyStats,
+    defense: &[f64],
+    res: &[f64],
+) -> Result<CurveSeries, ApiError> {
+    let operator = gd
+        .operators
+        .get(&config.operator_id)
+        .ok_or(ApiError::NotFound)?;
+
+    let def_values = if defense.is_empty() {
+        vec![fixed.defense]
+    } else {
+        defense.to_vec()
+    };
+    let res_values = if res.is_empty() {
+        vec![fixed.res]
+    } else {
+        res.to_vec()
+    };
+    let grid: Vec<EnemyStats> = def_values
+        .iter()
+        .flat_map(|&d| {
+            res_values
+                .iter()
+                .map(move |&r| EnemyStats { defense: d, res: r })
+        })
+        .collect();
+
+    let operator_id = config.operator_id.clone();
+    let skill_index = config.skill_index;
+    let module_index = config.module_index;
+    let points =
+        engine::calculate_dps_curve(operator, build_params(config), &grid).map(|results| {
+            grid.iter()
+                .zip(results)
+                .map(|(e, r)| CurvePoint {
+                    defense: e.defense,
+                    res: e.res,
+                    skill_dps: r.skill_dps,
+                    total_damage: r.total_damage,
+                    average_dps: r.average_dps,
+                })
+                .collect()
+        });
+
+    Ok(CurveSeries {
+        operator_id,
+        name: operator.name.clone(),
+        skill_index,
+        module_index,
+        points,
+    })
+}
+
+pub fn calculate_heal_scenario(
+    state: &AppState,
+    req: HealScenarioRequest,
+) -> Result<HealScenarioResponse, ApiError> {
+    if req.scenario.injured_allies < 1 || req.scenario.injured_allies > MAX_SQUAD_SIZE as i32 {
+        return Err(ApiError::BadRequest(format!(
+            "injuredAllies must be between 1 and {MAX_SQUAD_SIZE}"
+        )));
+    }
+    if req.scenario.target_max_hp <= 0.0 {
+        return Err(ApiError::BadRequest("targetMaxHp must be positive".into()));
+    }
+    if req.scenario.incoming_dps < 0.0 {
+        return Err(ApiError::BadRequest(
+            "incomingDps must not be negative".into(),
+        ));
+    }
+
+    let gd = state.default_game_data();
+    let operator = gd
+        .operators
+        .get(&req.calc.operator_id)
+        .ok_or(ApiError::NotFound)?;
+
+    let params = build_params(req.calc);
+
+    let (hps, scenario) = engine::calculate_heal_scenario(operator, params, &req.scenario).ok_or(
+        ApiError::BadRequest("HPS calculation failed for this operator/config".into()),
+    )?;
+    Ok(HealScenarioResponse { hps, scenario })
 }
 
 pub fn calculate_hps(state: &AppState, req: CalculateRequest) -> Result<HpsResult, ApiError> {
@@ -268,3 +776,39 @@ pub fn calculate_hps(state: &AppState, req: CalculateRequest) -> Result<HpsResul
         "HPS calculation failed for this operator/config".into(),
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::gamedata::types::level::ModifierValue;
+
+    fn rune(difficulty: &str, value: f64) -> Modifier {
+        Modifier {
+            key: "enemy_attribute_mul".into(),
+            difficulty: difficulty.into(),
+            profession: String::new(),
+            blackboard: vec![ModifierValue {
+                key: "def".into(),
+                value,
+                value_str: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn challenge_runes_skip_normal_mode() {
+        let stage = [rune("FOUR_STAR", 2.0), rune("ALL", 1.5)];
+        let base = EnemyAttributes {
+            def: 100,
+            ..EnemyAttributes::default()
+        };
+
+        let mut normal = base.clone();
+        apply_modifiers("enemy_1007_slime", &mut normal, &stage, &[], "NORMAL");
+        assert_eq!(normal.def, 150);
+
+        let mut challenge = base;
+        apply_modifiers("enemy_1007_slime", &mut challenge, &stage, &[], "FOUR_STAR");
+        assert_eq!(challenge.def, 300);
+    }
+}
@@ -0,0 +1,581 @@
+//! Farming plan: which stages to run, and how often, to cover what the
+//! planner's aggregated requirements are still missing.
+//!
+//! Drop rates come from a drop matrix in the Penguin Statistics export format
+//! (`core::drop_rates`), submitted with the request or imported per server;
+//! `stage_table` only knows qualitative drop bands. The solver is greedy
+//! rather than an LP:
+//!
+//! 1. Every item gets a sanity value: its cheapest single-stage cost per unit.
+//! 2. Each craftable requirement is either farmed directly or crafted from its
+//!    ingredients, whichever is cheaper at those values. Workshop byproducts
+//!    (a chance at a same-tier material per craft) count toward crafting.
+//! 3. Stages are picked by needed-drop value per sanity, each run until one of
+//!    the items it covers is done, until nothing is missing.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::app::error::ApiError;
+use crate::app::services::planner::list_plans;
+use crate::app::state::AppState;
+use crate::core::drop_rates::{DropMatrix, DropMatrixEntry, stage_table_id};
+use crate::core::gamedata::types::GameData;
+use crate::database::models::planner::PlanRequirementItem;
+use crate::database::queries::drop_rates::get_drop_matrix;
+
+/// Expected byproducts per `F_EVOLVE` workshop craft (base rate, no operators).
+const BYPRODUCT_RATE: f64 = 0.1;
+/// Matrix rows with fewer recorded runs than this are too noisy to plan on.
+const DEFAULT_MIN_SAMPLES: u64 = 100;
+const EVOLVE_FORMULA_TYPE: &str = "F_EVOLVE";
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FarmingRequest {
+    /// Drop rates to plan with. Omitted = the matrix imported for the
+    /// server (`bin/import_drop_rates.rs`).
+    pub drop_matrix: Option<DropMatrix>,
+    /// Operator ids to plan for, as in `/plans?active=`. Empty = every plan.
+    #[serde(default)]
+    pub active: Vec<String>,
+    /// Stages the player can't or won't run.
+    #[serde(default)]
+    pub exclude_stages: Vec<String>,
+    pub min_samples: Option<u64>,
+    /// Consider crafting missing items in the workshop. Default true.
+    pub allow_crafting: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpectedDrop {
+    pub item_id: String,
+    pub name: String,
+    pub count: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageRun {
+    pub stage_id: String,
+    pub code: String,
+    pub ap_cost: i32,
+    pub runs: u32,
+    pub sanity: i64,
+    /// Expected drops of the items the plan farms, over all `runs`.
+    pub drops: Vec<ExpectedDrop>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CraftStep {
+    pub item_id: String,
+    pub name: String,
+    pub crafts: i32,
+    pub expected_byproducts: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingItem {
+    pub item_id: String,
+    pub name: String,
+    pub count: i32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FarmingPlan {
+    pub stages: Vec<StageRun>,
+    /// Workshop crafts the plan relies on, ingredients farmed above.
+    pub crafts: Vec<CraftStep>,
+    pub total_sanity: i64,
+    pub total_runs: u32,
+    /// LMD the runs pay out on clear.
+    pub stage_lmd: i64,
+    /// Missing items no imported drop or recipe covers (LMD and EXP included:
+    /// the planner tracks them, the matrix doesn't).
+    pub unfarmable: Vec<MissingItem>,
+}
+
+/// Expected per-run drops of one stage.
+#[derive(Debug, Clone)]
+pub(crate) struct StageRates {
+    pub stage_id: String,
+    pub ap_cost: i32,
+    pub drops: Vec<(String, f64)>,
+}
+
+/// Matrix rows → per-stage rates, keeping only stages in `stage_table` that
+/// cost sanity and items the stage's drop info lists (when it lists any).
+pub(crate) fn stage_rates(
+    matrix: &[DropMatrixEntry],
+    gd: &GameData,
+    min_samples: u64,
+    exclude: &[String],
+) -> Vec<StageRates> {
+    let mut by_stage: HashMap<&str, StageRates> = HashMap::new();
+    for entry in matrix {
+        if entry.times < min_samples.max(1) || entry.quantity == 0 {
+            continue;
+        }
+        let Some(stage) =
+            stage_table_id(&entry.stage_id, &gd.stages).and_then(|id| gd.stages.get(id))
+        else {
+            continue;
+        };
+        if stage.ap_cost <= 0
+            || exclude
+                .iter()
+                .any(|s| *s == stage.stage_id || *s == stage.code)
+            || !gd.materials.items.contains_key(&entry.item_id)
+        {
+            continue;
+        }
+        let listed = stage.stage_drop_info.as_ref().is_none_or(|info| {
+            info.display_detail_rewards.is_empty()
+                || info
+                    .display_detail_rewards
+                    .iter()
+                    .any(|r| r.id == entry.item_id)
+        });
+        if !listed {
+            continue;
+        }
+        by_stage
+            .entry(stage.stage_id.as_str())
+            .or_insert_with(|| StageRates {
+                stage_id: stage.stage_id.clone(),
+                ap_cost: stage.ap_cost,
+                drops: Vec::new(),
+            })
+            .drops
+            .push((entry.item_id.clone(), entry.per_run()));
+    }
+    let mut stages: Vec<StageRates> = by_stage.into_values().collect();
+    stages.sort_by(|a, b| a.stage_id.cmp(&b.stage_id));
+    stages
+}
+
+/// Cheapest single-stage sanity per unit of each item.
+fn drop_values(stages: &[StageRates]) -> HashMap<String, f64> {
+    let mut values: HashMap<String, f64> = HashMap::new();
+    for stage in stages {
+        for (item_id, rate) in &stage.drops {
+            let cost = f64::from(stage.ap_cost) / rate;
+            values
+                .entry(item_id.clone())
+                .and_modify(|v| *v = v.min(cost))
+                .or_insert(cost);
+        }
+    }
+    values
+}
+
+/// Sanity value of one workshop byproduct per craft of each `F_EVOLVE` item:
+/// `BYPRODUCT_RATE` times the mean value of that formula tier's outputs.
+fn byproduct_values(gd: &GameData, values: &HashMap<String, f64>) -> HashMap<String, f64> {
+    let evolve: Vec<_> = gd
+        .building
+        .workshop_formulas
+        .values()
+        .filter(|f| f.formula_type == EVOLVE_FORMULA_TYPE)
+        .collect();
+    let mut tiers: HashMap<i32, (f64, usize)> = HashMap::new();
+    for f in &evolve {
+        if let Some(v) = values.get(&f.item_id) {
+            let tier = tiers.entry(f.rarity).or_default();
+            tier.0 += v;
+            tier.1 += 1;
+        }
+    }
+    evolve
+        .iter()
+        .filter_map(|f| {
+            let (sum, n) = tiers.get(&f.rarity)?;
+            Some((f.item_id.clone(), BYPRODUCT_RATE * sum / *n as f64))
+        })
+        .collect()
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Solution {
+    /// `(index into stages, runs)`.
+    pub runs: Vec<(usize, u32)>,
+    /// `(item, crafts)`, in requirement-tree order.
+    pub crafts: Vec<(String, i32)>,
+    /// Items the stages must drop.
+    pub targets: HashMap<String, f64>,
+    pub unfarmable: HashMap<String, i32>,
+}
+
+pub(crate) struct Solver<'a> {
+    stages: &'a [StageRates],
+    values: HashMap<String, f64>,
+    /// Byproduct value per craft, by crafted item.
+    byproducts: HashMap<String, f64>,
+    allow_crafting: bool,
+}
+
+impl<'a> Solver<'a> {
+    pub fn new(
+        stages: &'a [StageRates],
+        byproducts: HashMap<String, f64>,
+        allow_crafting: bool,
+    ) -> Self {
+        Self {
+            stages,
+            values: drop_values(stages),
+            byproducts,
+            allow_crafting,
+        }
+    }
+
+    pub fn solve(&self, requirements: &[PlanRequirementItem]) -> Solution {
+        let mut solution = Solution::default();
+        for node in requirements {
+            self.commit(node, &mut solution);
+        }
+        solution.runs = self.cover(&solution.targets);
+        solution
+    }
+
+    /// Crafts needed to cover `node`'s shortfall, when crafting is an option.
+    fn crafts_needed(&self, node: &PlanRequirementItem) -> Option<i32> {
+        if !self.allow_crafting || !node.can_craft {
+            return None;
+        }
+        let recipe = node.recipe.as_ref()?;
+        recipe
+            .costs
+            .iter()
+            .find(|c| c.count > 0)
+            .map(|c| c.item.required_count / c.count)
+            .filter(|&n| n > 0)
+    }
+
+    fn direct_cost(&self, node: &PlanRequirementItem) -> f64 {
+        self.values
+            .get(&node.id)
+            .map_or(f64::INFINITY, |v| v * f64::from(node.missing_count))
+    }
+
+    /// Sanity to craft `node`'s shortfall from farmed ingredients, net of
+    /// byproducts.
+    fn craft_cost(&self, node: &PlanRequirementItem) -> f64 {
+        let (Some(crafts), Some(recipe)) = (self.crafts_needed(node), &node.recipe) else {
+            return f64::INFINITY;
+        };
+        let ingredients: f64 = recipe.costs.iter().map(|c| self.cost(&c.item)).sum();
+        let byproducts = self.byproducts.get(&node.id).copied().unwrap_or(0.0);
+        ingredients - byproducts * f64::from(crafts)
+    }
+
+    fn cost(&self, node: &PlanRequirementItem) -> f64 {
+        if node.missing_count == 0 {
+            return 0.0;
+        }
+        self.direct_cost(node).min(self.craft_cost(node))
+    }
+
+    /// Farm `node` directly or craft it, whichever is cheaper. An item neither
+    /// way covers is crafted when it has a recipe, so the ingredients actually
+    /// missing are what gets reported.
+    fn commit(&self, node: &PlanRequirementItem, solution: &mut Solution) {
+        if node.missing_count == 0 {
+            return;
+        }
+        let direct = self.direct_cost(node);
+        let craft = self.craft_cost(node);
+        let crafts = self.crafts_needed(node);
+        match (crafts, &node.recipe) {
+            (Some(crafts), Some(recipe)) if craft <= direct => {
+                solution.crafts.push((node.id.clone(), crafts));
+                for cost in &recipe.costs {
+                    self.commit(&cost.item, solution);
+                }
+            }
+            _ if direct.is_finite() => {
+                *solution.targets.entry(node.id.clone()).or_default() +=
+                    f64::from(node.missing_count);
+            }
+            _ => {
+                *solution.unfarmable.entry(node.id.clone()).or_default() += node.missing_count;
+            }
+        }
+    }
+
+    /// Greedy stage cover of `targets`: take the stage with the best
+    /// needed-drop value per sanity, run it until one of its needed items is
+    /// covered, repeat.
+    fn cover(&self, targets: &HashMap<String, f64>) -> Vec<(usize, u32)> {
+        let mut remaining = targets.clone();
+        let mut runs: Vec<(usize, u32)> = Vec::new();
+        loop {
+            let needed = |(item_id, rate): &(String, f64)| {
+                remaining
+                    .get(item_id)
+                    .filter(|&&r| r > f64::EPSILON)
+                    .map(|&r| (r, *rate))
+            };
+            let best = self
+                .stages
+                .iter()
+                .enumerate()
+                .map(|(i, stage)| {
+                    let gain: f64 = stage
+                        .drops
+                        .iter()
+                        .filter_map(|d| needed(d).map(|(r, rate)| r.min(rate) * self.values[&d.0]))
+                        .sum();
+                    (i, gain / f64::from(stage.ap_cost))
+                })
+                .filter(|(_, score)| *score > 0.0)
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+            let Some((i, _)) = best else { break };
+
+            let stage = &self.stages[i];
+            let count = stage
+                .drops
+                .iter()
+                .filter_map(needed)
+                .map(|(r, rate)| (r / rate).ceil() as u32)
+                .min()
+                .unwrap_or(1)
+                .max(1);
+            for (item_id, rate) in &stage.drops {
+                if let Some(r) = remaining.get_mut(item_id) {
+                    *r = (*r - rate * f64::from(count)).max(0.0);
+                }
+            }
+            match runs.iter_mut().find(|(s, _)| *s == i) {
+                Some((_, n)) => *n += count,
+                None => runs.push((i, count)),
+            }
+        }
+        runs
+    }
+}
+
+pub async fn farming_plan(
+    state: &AppState,
+    user_id: Uuid,
+    req: FarmingRequest,
+) -> Result<FarmingPlan, ApiError> {
+    let gd = state.default_game_data();
+    let requirements = list_plans(state, user_id, req.active)
+        .await?
+        .aggregated_requirements;
+
+    let matrix = match req.drop_matrix {
+        Some(matrix) => matrix,
+        None => get_drop_matrix(&state.db, state.default_server.index() as i16).await?,
+    };
+    let stages = stage_rates(
+        &matrix.matrix,
+        &gd,
+        req.min_samples.unwrap_or(DEFAULT_MIN_SAMPLES),
+        &req.exclude_stages,
+    );
+    if stages.is_empty() {
+        return Err(ApiError::BadRequest(
+            "no usable stage drops; import a drop matrix or submit `dropMatrix`".into(),
+        ));
+    }
+    let byproducts = byproduct_values(&gd, &drop_values(&stages));
+    let solver = Solver::new(&stages, byproducts, req.allow_crafting.unwrap_or(true));
+    let solution = solver.solve(&requirements);
+
+    Ok(to_plan(&solution, &stages, &gd))
+}
+
+fn is_evolve(gd: &GameData, item_id: &str) -> bool {
+    gd.building
+        .workshop_formulas
+        .values()
+        .any(|f| f.item_id == item_id && f.formula_type == EVOLVE_FORMULA_TYPE)
+}
+
+fn item_name(gd: &GameData, item_id: &str) -> String {
+    gd.materials
+        .items
+        .get(item_id)
+        .map_or_else(|| item_id.to_owned(), |i| i.name.clone())
+}
+
+fn to_plan(solution: &Solution, stages: &[StageRates], gd: &GameData) -> FarmingPlan {
+    let mut runs: Vec<StageRun> = solution
+        .runs
+        .iter()
+        .map(|&(i, runs)| {
+            let rates = &stages[i];
+            let stage = gd.stages.get(&rates.stage_id);
+            StageRun {
+                stage_id: rates.stage_id.clone(),
+                code: stage.map_or_else(|| rates.stage_id.clone(), |s| s.code.clone()),
+                ap_cost: rates.ap_cost,
+                runs,
+                sanity: i64::from(rates.ap_cost) * i64::from(runs),
+                drops: rates
+                    .drops
+                    .iter()
+                    .filter(|(item_id, _)| solution.targets.contains_key(item_id))
+                    .map(|(item_id, rate)| ExpectedDrop {
+                        item_id: item_id.clone(),
+                        name: item_name(gd, item_id),
+                        count: rate * f64::from(runs),
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    runs.sort_by_key(|r| std::cmp::Reverse(r.sanity));
+
+    let stage_lmd = runs
+        .iter()
+        .map(|r| {
+            let gold = gd.stages.get(&r.stage_id).map_or(0, |s| s.gold_gain);
+            i64::from(gold) * i64::from(r.runs)
+        })
+        .sum();
+    let crafts = solution
+        .crafts
+        .iter()
+        .map(|(item_id, crafts)| CraftStep {
+            item_id: item_id.clone(),
+            name: item_name(gd, item_id),
+            crafts: *crafts,
+            expected_byproducts: if is_evolve(gd, item_id) {
+                BYPRODUCT_RATE * f64::from(*crafts)
+            } else {
+                0.0
+            },
+        })
+        .collect();
+    let mut unfarmable: Vec<MissingItem> = solution
+        .unfarmable
+        .iter()
+        .map(|(item_id, count)| MissingItem {
+            item_id: item_id.clone(),
+            name: item_name(gd, item_id),
+            count: *count,
+        })
+        .collect();
+    unfarmable.sort_by(|a, b| a.item_id.cmp(&b.item_id));
+
+    FarmingPlan {
+        total_sanity: runs.iter().map(|r| r.sanity).sum(),
+        total_runs: runs.iter().map(|r| r.runs).sum(),
+        stages: runs,
+        crafts,
+        stage_lmd,
+        unfarmable,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::planner::{PlanRecipe, PlanRecipeCost};
+
+    fn item(id: &str, missing: i32, recipe: Option<PlanRecipe>) -> PlanRequirementItem {
+        PlanRequirementItem {
+            id: id.to_owned(),
+            name: id.to_owned(),
+            icon_id: None,
+            image: None,
+            item_type: "MATERIAL".to_owned(),
+            rarity: 3,
+            sort_group: 0,
+            sort_subrank: 0,
+            required_count: missing,
+            inventory_count: 0,
+            craftable_count: 0,
+            missing_count: missing,
+            can_craft: recipe.is_some(),
+            craft_reason: String::new(),
+            recipe,
+        }
+    }
+
+    /// `crafts` of one output from `per_craft` of each ingredient, all missing.
+    fn crafted(id: &str, crafts: i32, inputs: &[(&str, i32)]) -> PlanRequirementItem {
+        let costs = inputs
+            .iter()
+            .map(|(ing, per_craft)| PlanRecipeCost {
+                count: *per_craft,
+                item: item(ing, crafts * per_craft, None),
+            })
+            .collect();
+        item(id, crafts, Some(PlanRecipe { count: 1, costs }))
+    }
+
+    fn stage(id: &str, ap_cost: i32, drops: &[(&str, f64)]) -> StageRates {
+        StageRates {
+            stage_id: id.to_owned(),
+            ap_cost,
+            drops: drops.iter().map(|(i, r)| ((*i).to_owned(), *r)).collect(),
+        }
+    }
+
+    #[test]
+    fn runs_a_stage_until_its_item_is_covered() {
+        let stages = [stage("s1", 10, &[("a", 0.5)])];
+        let solution = Solver::new(&stages, HashMap::new(), true).solve(&[item("a", 7, None)]);
+        assert_eq!(solution.runs, vec![(0, 14)]);
+        assert!(solution.unfarmable.is_empty());
+    }
+
+    #[test]
+    fn prefers_the_stage_that_covers_more_needs_per_sanity() {
+        // s2 drops both needed items; s1 and s3 are slightly better for one each.
+        let stages = [
+            stage("s1", 10, &[("a", 0.6)]),
+            stage("s2", 10, &[("a", 0.5), ("b", 0.5)]),
+            stage("s3", 10, &[("b", 0.6)]),
+        ];
+        let solution = Solver::new(&stages, HashMap::new(), true)
+            .solve(&[item("a", 10, None), item("b", 10, None)]);
+        assert_eq!(solution.runs, vec![(1, 20)]);
+    }
+
+    #[test]
+    fn crafts_when_ingredients_are_cheaper_than_the_product() {
+        // 3 t3 (10 sanity each) beat one t4 at 50.
+        let stages = [
+            stage("t3_stage", 10, &[("t3", 1.0)]),
+            stage("t4_stage", 50, &[("t4", 1.0)]),
+        ];
+        let node = crafted("t4", 2, &[("t3", 3)]);
+        let solution = Solver::new(&stages, HashMap::new(), true).solve(&[node]);
+        assert_eq!(solution.crafts, vec![("t4".to_owned(), 2)]);
+        assert_eq!(solution.targets.get("t3"), Some(&6.0));
+        assert_eq!(solution.runs, vec![(0, 6)]);
+    }
+
+    #[test]
+    fn byproducts_can_tip_the_choice_toward_crafting() {
+        let stages = [
+            stage("t3_stage", 10, &[("t3", 1.0)]),
+            stage("t4_stage", 29, &[("t4", 1.0)]),
+        ];
+        let node = crafted("t4", 1, &[("t3", 3)]);
+        let plain = Solver::new(&stages, HashMap::new(), true).solve(std::slice::from_ref(&node));
+        assert!(plain.crafts.is_empty());
+        let with_byproducts =
+            Solver::new(&stages, HashMap::from([("t4".to_owned(), 2.0)]), true).solve(&[node]);
+        assert_eq!(with_byproducts.crafts, vec![("t4".to_owned(), 1)]);
+    }
+
+    #[test]
+    fn reports_items_nothing_drops() {
+        let stages = [stage("s1", 10, &[("a", 1.0)])];
+        let node = crafted("t4", 1, &[("a", 1), ("rare", 2)]);
+        let solution = Solver::new(&stages, HashMap::new(), true).solve(&[node]);
+        assert_eq!(solution.unfarmable.get("rare"), Some(&2));
+        assert_eq!(solution.runs, vec![(0, 1)]);
+    }
+}
@@ -1,7 +1,13 @@
 use crate::app::cache::keys::CacheKey;
 use crate::app::error::ApiError;
 use crate::app::state::AppState;
+use crate::core::gacha_odds::{
+    BannerRule, HARD_PITY, TargetState, convolve, effective_six_rate, luck_percentile, mean,
+    pulls_to_target, six_count_pmf, six_star_rate,
+};
 use crate::core::gamedata::types::GameData;
+use crate::core::gamedata::types::gacha::GachaPoolClient;
+use crate::core::hypergryph::constants::Server;
 use crate::core::hypergryph::yostar::AccountPortalSession;
 use crate::database::models::gacha::{GachaRecord, GachaStats};
 use crate::database::queries::gacha;
@@ -11,7 +17,9 @@ use crate::database::queries::gacha::get_history_filtered;
 use crate::database::queries::gacha::get_or_create_settings;
 use crate::database::queries::gacha::insert_batch;
 use crate::database::queries::gacha::update_gacha_flags;
+use crate::database::queries::users::find_by_id;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
 /// Look up a `char_id`'s canonical rarity from game data. The Yostar API echoes a
@@ -22,6 +30,23 @@ pub fn rarity_from_gamedata(gd: &GameData, char_id: &str) -> Option<i16> {
     gd.operators.get(char_id).map(|op| op.rarity.to_star_int())
 }
 
+/// Derive gacha type from `pool_id` prefix
+pub fn gacha_type_for_pool(pool_id: &str) -> &'static str {
+    if pool_id.starts_with("LIMITED_") {
+        "limited"
+    } else if pool_id.starts_with("LINKAGE_") {
+        "linkage"
+    } else if pool_id.starts_with("CLASSIC_") {
+        "classic"
+    } else if pool_id.starts_with("SINGLE_") {
+        "single"
+    } else if pool_id.starts_with("BOOT_") {
+        "boot"
+    } else {
+        "normal"
+    }
+}
+
 #[derive(Deserialize)]
 pub struct GachaApiResponse {
     pub data: Option<GachaApiData>,
@@ -51,21 +76,8 @@ impl GachaApiItem {
         self.star.parse().unwrap_or(3)
     }
 
-    /// Derive gacha type from `pool_id` prefix
     fn gacha_type(&self) -> &'static str {
-        if self.pool_id.starts_with("LIMITED_") {
-            "limited"
-        } else if self.pool_id.starts_with("LINKAGE_") {
-            "linkage"
-        } else if self.pool_id.starts_with("CLASSIC_") {
-            "classic"
-        } else if self.pool_id.starts_with("SINGLE_") {
-            "single"
-        } else if self.pool_id.starts_with("BOOT_") {
-            "boot"
-        } else {
-            "normal"
-        }
+        gacha_type_for_pool(&self.pool_id)
     }
 
     /// Convert to the JSONB shape that `sp_insert_gacha_batch` expects.
@@ -955,3 +967,287 @@ pub async fn update_gacha_settings(
     update_gacha_flags(&state.db, user_id, store_records, share_anonymous_stats).await?;
     get_gacha_settings(state, user_id).await
 }
+
+// ============================================
+// Luck: pity state and odds per banner rule
+// ============================================
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PityGroupLuck {
+    /// `standard` / `kernel` for the shared counters, else the banner's `pool_id`.
+    pub group: String,
+    pub rule: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pool_name: Option<String>,
+    pub pulls: u32,
+    pub six_stars: u32,
+    pub expected_six_stars: f64,
+    /// Pulls since the last 6★ on this counter.
+    pub pity: u32,
+    pub next_six_rate: f64,
+    /// False once a per-banner counter's banner has closed.
+    pub active: bool,
+    pub luck_percentile: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChanceWithin {
+    pub pulls: u32,
+    pub chance: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetProjection {
+    pub pool_id: String,
+    pub char_id: String,
+    pub rule: &'static str,
+    pub featured: u32,
+    pub pity: u32,
+    pub banner_pulls: u32,
+    pub expected_pulls: f64,
+    pub pulls_for_50: Option<u32>,
+    pub pulls_for_90: Option<u32>,
+    pub chance_within: Vec<ChanceWithin>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GachaLuck {
+    pub groups: Vec<PityGroupLuck>,
+    pub total_pulls: u32,
+    pub six_stars: u32,
+    pub expected_six_stars: f64,
+    /// Where the user's 6★ count sits in the theoretical distribution for
+    /// their pulls: 50 is average, lower is unluckier. `None` without pulls.
+    pub luck_percentile: Option<f64>,
+    pub effective_six_rate: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<TargetProjection>,
+}
+
+const TARGET_CHECKPOINTS: [u32; 6] = [10, 50, 100, 150, 200, 300];
+
+pub(crate) struct PityCounter {
+    pub rule: BannerRule,
+    /// `None` for the shared Standard / Kernel counters.
+    pub pool_id: Option<String>,
+    pub pulls: u32,
+    pub six_stars: u32,
+    pub pity: u32,
+}
+
+/// The user's stored pulls replayed through every pity counter they touched.
+/// Counters start at 0 on the first stored pull - anything before the synced
+/// history is unknown.
+pub(crate) struct PityReplay {
+    pub counters: Vec<PityCounter>,
+    /// Pulls made on each banner, by `pool_id`.
+    pub banner_pulls: HashMap<String, u32>,
+    /// Banners on which a rate-up 6★ has already dropped.
+    pub featured_seen: HashSet<String>,
+}
+
+impl PityReplay {
+    /// `rows` as stored: newest first with each batch's `batch_index` 0 on
+    /// top, so the reverse is chronological.
+    pub fn new(pools: &HashMap<&str, &GachaPoolClient>, rows: &[GachaRecord]) -> Self {
+        let mut replay = Self {
+            counters: Vec::new(),
+            banner_pulls: HashMap::new(),
+            featured_seen: HashSet::new(),
+        };
+        for record in rows.iter().rev() {
+            let Some(rule) = record_rule(pools, record) else {
+                continue;
+            };
+            *replay
+                .banner_pulls
+                .entry(record.pool_id.clone())
+                .or_default() += 1;
+            let pool_id = (!rule.shares_pity()).then_some(record.pool_id.as_str());
+            let idx = replay
+                .counters
+                .iter()
+                .position(|c| c.rule == rule && c.pool_id.as_deref() == pool_id)
+                .unwrap_or_else(|| {
+                    replay.counters.push(PityCounter {
+                        rule,
+                        pool_id: pool_id.map(str::to_owned),
+                        pulls: 0,
+                        six_stars: 0,
+                        pity: 0,
+                    });
+                    replay.counters.len() - 1
+                });
+            let counter = &mut replay.counters[idx];
+            counter.pulls += 1;
+            if record.rarity == 6 {
+                counter.six_stars += 1;
+                counter.pity = 0;
+                if pools
+                    .get(record.pool_id.as_str())
+                    .is_some_and(|p| p.featured6.contains(&record.char_id))
+                {
+                    replay.featured_seen.insert(record.pool_id.clone());
+                }
+            } else {
+                counter.pity = (counter.pity + 1).min(HARD_PITY - 1);
+            }
+        }
+        replay
+    }
+
+    /// The counter a pull on `pool_id` under `rule` would advance.
+    pub fn counter(&self, rule: BannerRule, pool_id: &str) -> Option<&PityCounter> {
+        self.counters.iter().find(|c| {
+            c.rule == rule && (rule.shares_pity() || c.pool_id.as_deref() == Some(pool_id))
+        })
+    }
+
+    pub fn banner_pulls(&self, pool_id: &str) -> u32 {
+        self.banner_pulls.get(pool_id).copied().unwrap_or(0)
+    }
+}
+
+/// The rule a stored pull was made under: the live pool's `gacha_rule_type`
+/// when the pool is still in `gacha_table`, else the recorded `gacha_type`.
+fn record_rule(
+    pools: &HashMap<&str, &GachaPoolClient>,
+    record: &GachaRecord,
+) -> Option<BannerRule> {
+    match pools.get(record.pool_id.as_str()) {
+        Some(pool) => BannerRule::from_rule_type(&pool.gacha_rule_type),
+        None => BannerRule::from_gacha_type(record.gacha_type.as_deref().unwrap_or("normal")),
+    }
+}
+
+/// Compare the user's 6★ counts on each pity counter with the soft-pity
+/// model. With `target`, also project the pulls needed for that rate-up
+/// operator on `pool_id`.
+pub async fn get_luck(
+    state: &AppState,
+    user_id: Uuid,
+    target: Option<(&str, &str)>,
+) -> Result<GachaLuck, ApiError> {
+    let profile = find_by_id(&state.db, user_id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let gd = Server::parse(&profile.server)
+        .map_or_else(|| state.default_game_data(), |s| state.game_data(s));
+    let rows = get_all_for_user(&state.db, user_id).await?;
+    let target = target.map(|(pool_id, char_id)| (pool_id.to_owned(), char_id.to_owned()));
+
+    tokio::task::spawn_blocking(move || {
+        let target = target.as_ref().map(|(p, c)| (p.as_str(), c.as_str()));
+        luck_report(&gd, &rows, target)
+    })
+    .await
+    .map_err(|e| ApiError::Internal(e.into()))?
+}
+
+/// Replays the user's pity counters and folds them into the luck report;
+/// the 6★ count distributions make this CPU-bound.
+fn luck_report(
+    gd: &GameData,
+    rows: &[GachaRecord],
+    target: Option<(&str, &str)>,
+) -> Result<GachaLuck, ApiError> {
+    let pools: HashMap<&str, &GachaPoolClient> = gd
+        .gacha
+        .gacha_pool_client
+        .iter()
+        .map(|p| (p.gacha_pool_id.as_str(), p))
+        .collect();
+
+    let replay = PityReplay::new(&pools, rows);
+    let counters = &replay.counters;
+
+    let now = chrono::Utc::now().timestamp();
+    let mut total_pmf: Vec<f64> = vec![1.0];
+    let mut groups = Vec::with_capacity(counters.len());
+    for c in counters {
+        let pmf = six_count_pmf(c.pulls, 0);
+        total_pmf = convolve(&total_pmf, &pmf);
+        let pool = c.pool_id.as_deref().and_then(|id| pools.get(id));
+        groups.push(PityGroupLuck {
+            group: c
+                .pool_id
+                .clone()
+                .unwrap_or_else(|| c.rule.as_str().to_owned()),
+            rule: c.rule.as_str(),
+            pool_name: pool.map(|p| p.gacha_pool_name.clone()),
+            pulls: c.pulls,
+            six_stars: c.six_stars,
+            expected_six_stars: mean(&pmf),
+            pity: c.pity,
+            next_six_rate: six_star_rate(c.pity),
+            active: c.pool_id.is_none() || pool.is_some_and(|p| p.end_time > now),
+            luck_percentile: luck_percentile(&pmf, c.six_stars as usize),
+        });
+    }
+
+    let total_pulls: u32 = counters.iter().map(|c| c.pulls).sum();
+    let six_stars: u32 = counters.iter().map(|c| c.six_stars).sum();
+
+    let target = match target {
+        Some((pool_id, char_id)) => {
+            let pool = pools.get(pool_id).ok_or(ApiError::NotFound)?;
+            let rule = BannerRule::from_rule_type(&pool.gacha_rule_type).ok_or_else(|| {
+                ApiError::BadRequest(format!(
+                    "no 6★ model for rule type {}",
+                    pool.gacha_rule_type
+                ))
+            })?;
+            // Banners whose rate-ups aren't in the pool blob (NORMAL, LINKAGE)
+            // are taken at their word: one rate-up, the requested operator.
+            if !pool.featured6.is_empty() && !pool.featured6.iter().any(|c| c == char_id) {
+                return Err(ApiError::BadRequest(format!(
+                    "{char_id} is not a rate-up 6★ on {pool_id}"
+                )));
+            }
+            let counter = replay.counter(rule, pool_id);
+            let banner_pulls = replay.banner_pulls(pool_id);
+            let featured = pool.featured6.len().max(1) as u32;
+            let pity = counter.map_or(0, |c| c.pity);
+            let odds = pulls_to_target(TargetState {
+                rule,
+                featured,
+                pity,
+                banner_pulls,
+                featured_seen: replay.featured_seen.contains(pool_id),
+            });
+            Some(TargetProjection {
+                pool_id: pool_id.to_owned(),
+                char_id: char_id.to_owned(),
+                rule: rule.as_str(),
+                featured,
+                pity,
+                banner_pulls,
+                expected_pulls: odds.expected_pulls,
+                pulls_for_50: odds.pulls_for(0.5),
+                pulls_for_90: odds.pulls_for(0.9),
+                chance_within: TARGET_CHECKPOINTS
+                    .iter()
+                    .map(|&pulls| ChanceWithin {
+                        pulls,
+                        chance: odds.chance_within(pulls),
+                    })
+                    .collect(),
+            })
+        }
+        None => None,
+    };
+
+    Ok(GachaLuck {
+        groups,
+        total_pulls,
+        six_stars,
+        expected_six_stars: mean(&total_pmf),
+        luck_percentile: (total_pulls > 0).then(|| luck_percentile(&total_pmf, six_stars as usize)),
+        effective_six_rate: effective_six_rate(),
+        target,
+    })
+}
@@ -0,0 +1,725 @@
+//! Pull-history import/export in the formats other Arknights trackers use, so
+//! history from before our 90-day `/gacha/fetch` window isn't lost.
+//!
+//! | Format       | Shape
+//! |--------------|------------------------------------------------------------------
+//! | `json`       | Yostar rows: `[{charId, charName, star, poolId, poolName, at}]`, bare, as `{data: {rows}}` or `{records}` (also `export-gacha` dumps)
+//! | `csv`        | One pull per line with a header; columns matched by name (`charId`/`name`, `rarity`/`star`, `poolId`/`pool`, `at`/`time`, ...)
+//! | `hypergryph` | CN official log: `{list: [{ts, pool, chars: [{name, rarity (0-5), isNew}]}]}`
+//!
+//! Imports resolve operator names against every loaded server's game data and
+//! pool names against `gacha_table`, then skip pulls already stored under the
+//! same `(char_id, pull_timestamp, batch_index)`.
+//! Pulls whose banner can't be resolved are skipped rather than stored under a
+//! placeholder pool, which the pity replay would count as Standard.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use uuid::Uuid;
+
+use crate::app::error::ApiError;
+use crate::app::services::gacha::{gacha_type_for_pool, rarity_from_gamedata};
+use crate::app::state::AppState;
+use crate::core::gamedata::types::GameData;
+use crate::core::gamedata::types::gacha::GachaPoolClient;
+use crate::core::hypergryph::constants::Server;
+use crate::database::models::gacha::GachaRecord;
+use crate::database::queries::gacha::{get_all_for_user, insert_imported};
+use crate::database::queries::users::find_by_id;
+
+/// Epoch values below this are seconds, not milliseconds (year 5138 in s).
+const MS_THRESHOLD: i64 = 100_000_000_000;
+
+const CHAR_ID_KEYS: [&str; 3] = ["charId", "char_id", "operatorId"];
+const CHAR_NAME_KEYS: [&str; 4] = ["charName", "char_name", "name", "operator"];
+const RARITY_KEYS: [&str; 3] = ["star", "rarity", "stars"];
+const POOL_ID_KEYS: [&str; 2] = ["poolId", "pool_id"];
+const POOL_NAME_KEYS: [&str; 4] = ["poolName", "pool_name", "pool", "banner"];
+const TIME_KEYS: [&str; 7] = [
+    "at",
+    "pull_timestamp",
+    "pullTimestamp",
+    "timestamp",
+    "ts",
+    "time",
+    "date",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferFormat {
+    Json,
+    Csv,
+    Hypergryph,
+}
+
+impl TransferFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+            Self::Hypergryph => "hypergryph",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Json | Self::Hypergryph => "application/json",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json | Self::Hypergryph => "json",
+        }
+    }
+
+    /// Guess from the body: CN logs carry `list`/`chars`, other JSON is rows,
+    /// anything else is CSV.
+    fn detect(body: &str) -> Self {
+        let trimmed = body.trim_start_matches('\u{feff}').trim_start();
+        if !trimmed.starts_with(['{', '[']) {
+            Self::Csv
+        } else if trimmed.contains("\"chars\"") {
+            Self::Hypergryph
+        } else {
+            Self::Json
+        }
+    }
+}
+
+/// One pull as read from a file, before resolution against game data.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct RawPull {
+    char_id: Option<String>,
+    char_name: Option<String>,
+    rarity: Option<i16>,
+    pool_id: Option<String>,
+    pool_name: Option<String>,
+    /// Unix milliseconds.
+    at: i64,
+}
+
+fn parse(format: TransferFormat, body: &str) -> Result<Vec<RawPull>, String> {
+    let body = body.trim_start_matches('\u{feff}');
+    match format {
+        TransferFormat::Json => parse_json_rows(body),
+        TransferFormat::Csv => parse_csv_rows(body),
+        TransferFormat::Hypergryph => parse_hypergryph(body),
+    }
+}
+
+fn parse_json_rows(body: &str) -> Result<Vec<RawPull>, String> {
+    let value: Value = serde_json::from_str(body).map_err(|e| format!("invalid JSON: {e}"))?;
+    let rows = value
+        .as_array()
+        .or_else(|| value.pointer("/data/rows").and_then(Value::as_array))
+        .or_else(|| value.get("records").and_then(Value::as_array))
+        .or_else(|| value.get("rows").and_then(Value::as_array))
+        .ok_or("expected an array of pulls, {data: {rows}} or {records}")?;
+
+    rows.iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let obj = row
+                .as_object()
+                .ok_or_else(|| format!("row {}: not an object", i + 1))?;
+            raw_pull(|keys| json_field(obj, keys)).map_err(|e| format!("row {}: {e}", i + 1))
+        })
+        .collect()
+}
+
+fn json_field(obj: &Map<String, Value>, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .filter_map(|k| obj.get(*k))
+        .find_map(|v| match v {
+            Value::String(s) => Some(s.clone()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        })
+        .filter(|s| !s.is_empty())
+}
+
+fn parse_csv_rows(body: &str) -> Result<Vec<RawPull>, String> {
+    let mut records = read_csv(body).into_iter();
+    let header = records.next().ok_or("empty CSV")?;
+    records
+        .enumerate()
+        .filter(|(_, r)| r.iter().any(|c| !c.trim().is_empty()))
+        .map(|(i, r)| {
+            raw_pull(|keys| {
+                keys.iter()
+                    .filter_map(|k| header.iter().position(|h| h.trim() == *k))
+                    .find_map(|col| r.get(col).map(|c| c.trim().to_owned()))
+                    .filter(|s| !s.is_empty())
+            })
+            .map_err(|e| format!("line {}: {e}", i + 2))
+        })
+        .collect()
+}
+
+/// RFC 4180: quoted fields may hold commas, newlines and `""` escapes.
+fn read_csv(body: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if field.is_empty() => quoted = true,
+            (',', false) => record.push(std::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+#[derive(Deserialize)]
+struct HgLog {
+    list: Vec<HgBatch>,
+}
+
+#[derive(Deserialize)]
+struct HgBatch {
+    ts: i64,
+    pool: String,
+    chars: Vec<HgChar>,
+}
+
+#[derive(Deserialize)]
+struct HgChar {
+    name: String,
+    /// Zero-based: 5 is a 6★.
+    rarity: i16,
+}
+
+fn parse_hypergryph(body: &str) -> Result<Vec<RawPull>, String> {
+    let value: Value = serde_json::from_str(body).map_err(|e| format!("invalid JSON: {e}"))?;
+    let list = value
+        .get("data")
+        .filter(|d| d.get("list").is_some())
+        .unwrap_or(&value);
+    let batches: Vec<HgBatch> = if list.is_array() {
+        serde_json::from_value(list.clone())
+    } else {
+        serde_json::from_value::<HgLog>(list.clone()).map(|l| l.list)
+    }
+    .map_err(|e| format!("not a Hypergryph gacha log: {e}"))?;
+
+    Ok(batches
+        .into_iter()
+        .flat_map(|b| {
+            let at = to_millis(b.ts);
+            let pool = b.pool;
+            b.chars.into_iter().map(move |c| RawPull {
+                char_name: Some(c.name),
+                rarity: Some(c.rarity + 1),
+                pool_name: Some(pool.clone()),
+                at,
+                ..RawPull::default()
+            })
+        })
+        .collect())
+}
+
+/// Build a pull from a field lookup shared by the JSON and CSV readers.
+fn raw_pull(get: impl Fn(&[&str]) -> Option<String>) -> Result<RawPull, String> {
+    let time = get(&TIME_KEYS).ok_or("no timestamp")?;
+    let pull = RawPull {
+        char_id: get(&CHAR_ID_KEYS),
+        char_name: get(&CHAR_NAME_KEYS),
+        rarity: get(&RARITY_KEYS).and_then(|r| r.parse().ok()),
+        pool_id: get(&POOL_ID_KEYS),
+        pool_name: get(&POOL_NAME_KEYS),
+        at: parse_time(&time).ok_or_else(|| format!("unreadable time {time:?}"))?,
+    };
+    if pull.char_id.is_none() && pull.char_name.is_none() {
+        return Err("no operator id or name".into());
+    }
+    Ok(pull)
+}
+
+fn to_millis(epoch: i64) -> i64 {
+    if epoch < MS_THRESHOLD {
+        epoch * 1000
+    } else {
+        epoch
+    }
+}
+
+/// Epoch seconds or milliseconds, RFC 3339, or `YYYY-MM-DD HH:MM:SS` (UTC).
+fn parse_time(s: &str) -> Option<i64> {
+    if let Ok(n) = s.parse::<i64>() {
+        return Some(to_millis(n));
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.timestamp_millis());
+    }
+    [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y/%m/%d %H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S",
+    ]
+    .iter()
+    .find_map(|f| NaiveDateTime::parse_from_str(s, f).ok())
+    .map(|dt| Utc.from_utc_datetime(&dt).timestamp_millis())
+}
+
+/// Operator and banner lookups over every loaded server, so CN names resolve
+/// for an EN account and vice versa.
+struct Resolver {
+    names: HashMap<String, String>,
+    pools: HashMap<String, GachaPoolClient>,
+    pools_by_name: HashMap<String, Vec<GachaPoolClient>>,
+}
+
+impl Resolver {
+    fn new(game_data: &[&GameData]) -> Self {
+        let mut names = HashMap::new();
+        let mut pools: HashMap<String, GachaPoolClient> = HashMap::new();
+        for gd in game_data {
+            for (id, op) in &gd.operators {
+                names.entry(op.name.clone()).or_insert_with(|| id.clone());
+                names
+                    .entry(op.appellation.clone())
+                    .or_insert_with(|| id.clone());
+            }
+            for pool in &gd.gacha.gacha_pool_client {
+                pools
+                    .entry(pool.gacha_pool_id.clone())
+                    .or_insert_with(|| pool.clone());
+            }
+        }
+        let mut pools_by_name: HashMap<String, Vec<GachaPoolClient>> = HashMap::new();
+        for pool in pools.values() {
+            pools_by_name
+                .entry(pool.gacha_pool_name.clone())
+                .or_default()
+                .push(pool.clone());
+        }
+        Self {
+            names,
+            pools,
+            pools_by_name,
+        }
+    }
+
+    fn char_id(&self, pull: &RawPull) -> Option<String> {
+        pull.char_id
+            .clone()
+            .or_else(|| self.names.get(pull.char_name.as_deref()?).cloned())
+    }
+
+    /// `(pool_id, pool_name)`, or `None` when the pull names no banner in
+    /// `gacha_table`. Pool names repeat across reruns, so prefer the run open
+    /// at `at`.
+    fn pool(&self, pull: &RawPull) -> Option<(String, Option<String>)> {
+        if let Some(id) = &pull.pool_id {
+            let name = pull
+                .pool_name
+                .clone()
+                .or_else(|| self.pools.get(id).map(|p| p.gacha_pool_name.clone()));
+            return Some((id.clone(), name));
+        }
+        let at = pull.at / 1000;
+        let found = pull
+            .pool_name
+            .as_deref()
+            .and_then(|name| self.pools_by_name.get(name))
+            .and_then(|runs| {
+                runs.iter()
+                    .find(|p| p.open_time <= at && at <= p.end_time)
+                    .or_else(|| {
+                        runs.iter()
+                            .filter(|p| p.open_time <= at)
+                            .max_by_key(|p| p.open_time)
+                    })
+                    .or(runs.first())
+            });
+        found.map(|p| (p.gacha_pool_id.clone(), Some(p.gacha_pool_name.clone())))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportResult {
+    pub format: &'static str,
+    pub parsed: usize,
+    pub inserted: u64,
+    /// Pulls already stored under the same `(char_id, pull_timestamp, batch_index)`.
+    pub duplicates: u64,
+    /// Operator names that matched nothing in game data; their pulls are skipped.
+    pub unresolved: Vec<String>,
+    /// Banner names that matched nothing in `gacha_table`; their pulls are
+    /// skipped.
+    pub unmatched_pools: Vec<String>,
+}
+
+/// Import rows ready for `insert_imported`, plus what couldn't be resolved.
+struct Resolved {
+    records: Vec<Value>,
+    unresolved: Vec<String>,
+    unmatched_pools: Vec<String>,
+}
+
+/// Resolve parsed pulls into `gacha_records` rows. `batch_index` is the
+/// pull's position within its `(pull_timestamp, pool_id)` batch counted
+/// newest first, like `/gacha/fetch` assigns it: files listed oldest first
+/// are walked backwards, so either export order imports the same indices.
+fn to_records(pulls: &[RawPull], resolver: &Resolver, gd: &GameData) -> Resolved {
+    let mut unresolved: HashSet<String> = HashSet::new();
+    let mut unmatched_pools: HashSet<String> = HashSet::new();
+    let mut batch_counters: HashMap<(i64, String), i16> = HashMap::new();
+    let mut records = Vec::with_capacity(pulls.len());
+    let oldest_first = pulls
+        .first()
+        .zip(pulls.last())
+        .is_some_and(|(a, b)| a.at < b.at);
+    let mut ordered: Vec<&RawPull> = pulls.iter().collect();
+    if oldest_first {
+        ordered.reverse();
+    }
+    for pull in ordered {
+        let Some(char_id) = resolver.char_id(pull) else {
+            unresolved.extend(pull.char_name.clone());
+            continue;
+        };
+        let Some((pool_id, pool_name)) = resolver.pool(pull) else {
+            unmatched_pools.insert(pull.pool_name.clone().unwrap_or_default());
+            continue;
+        };
+        let counter = batch_counters
+            .entry((pull.at, pool_id.clone()))
+            .or_insert(0);
+        let batch_index = *counter;
+        *counter += 1;
+        records.push(json!({
+            "char_id": char_id,
+            "pool_id": pool_id,
+            "rarity": rarity_from_gamedata(gd, &char_id).or(pull.rarity).unwrap_or(3),
+            "pull_timestamp": pull.at,
+            "pool_name": pool_name,
+            "gacha_type": gacha_type_for_pool(&pool_id),
+            "batch_index": batch_index,
+        }));
+    }
+    let sorted = |set: HashSet<String>| {
+        let mut v: Vec<String> = set.into_iter().collect();
+        v.sort_unstable();
+        v
+    };
+    Resolved {
+        records,
+        unresolved: sorted(unresolved),
+        unmatched_pools: sorted(unmatched_pools),
+    }
+}
+
+pub async fn import(
+    state: &AppState,
+    user_id: Uuid,
+    format: Option<TransferFormat>,
+    body: &str,
+) -> Result<ImportResult, ApiError> {
+    let format = format.unwrap_or_else(|| TransferFormat::detect(body));
+    let pulls = parse(format, body).map_err(ApiError::BadRequest)?;
+
+    let loaded: Vec<_> = state
+        .servers
+        .values()
+        .map(|s| s.game_data.load_full())
+        .collect();
+    let resolver = Resolver::new(&loaded.iter().map(AsRef::as_ref).collect::<Vec<_>>());
+    let gd = state.default_game_data();
+    let resolved = to_records(&pulls, &resolver, &gd);
+    let count = resolved.records.len() as u64;
+
+    let inserted = if count == 0 {
+        0
+    } else {
+        let value = Value::Array(resolved.records);
+        insert_imported(&state.db, user_id, &value).await?
+    };
+
+    Ok(ImportResult {
+        format: format.as_str(),
+        parsed: pulls.len(),
+        inserted,
+        duplicates: count - inserted,
+        unresolved: resolved.unresolved,
+        unmatched_pools: resolved.unmatched_pools,
+    })
+}
+
+/// Rows in stored order, newest first with `batch_index` 0 on top of each
+/// batch, which is the official endpoints' order too. Keeping it means a
+/// re-import assigns the same `batch_index` and de-duplicates cleanly.
+fn render(format: TransferFormat, records: &[GachaRecord], gd: &GameData) -> String {
+    let name = |id: &str| {
+        gd.operators
+            .get(id)
+            .map_or_else(|| id.to_owned(), |op| op.name.clone())
+    };
+    match format {
+        TransferFormat::Json => {
+            let rows: Vec<Value> = records
+                .iter()
+                .map(|r| {
+                    json!({
+                        "charId": r.char_id,
+                        "charName": name(&r.char_id),
+                        "star": r.rarity.to_string(),
+                        "poolId": r.pool_id,
+                        "poolName": r.pool_name.clone().unwrap_or_default(),
+                        "typeName": r.gacha_type.clone().unwrap_or_else(|| "normal".to_owned()),
+                        "at": r.pull_timestamp,
+                    })
+                })
+                .collect();
+            Value::Array(rows).to_string()
+        }
+        TransferFormat::Csv => {
+            let mut out =
+                String::from("charId,charName,rarity,poolId,poolName,gachaType,at,time\n");
+            for r in records {
+                let time = Utc
+                    .timestamp_millis_opt(r.pull_timestamp)
+                    .single()
+                    .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+                    .unwrap_or_default();
+                let _ = writeln!(
+                    out,
+                    "{},{},{},{},{},{},{},{}",
+                    csv_field(&r.char_id),
+                    csv_field(&name(&r.char_id)),
+                    r.rarity,
+                    csv_field(&r.pool_id),
+                    csv_field(r.pool_name.as_deref().unwrap_or_default()),
+                    r.gacha_type.as_deref().unwrap_or("normal"),
+                    r.pull_timestamp,
+                    time,
+                );
+            }
+            out
+        }
+        TransferFormat::Hypergryph => {
+            let mut list: Vec<Value> = Vec::new();
+            let mut current: Option<(i64, &str)> = None;
+            for r in records {
+                let key = (r.pull_timestamp, r.pool_id.as_str());
+                let ch = json!({
+                    "name": name(&r.char_id),
+                    "rarity": r.rarity - 1,
+                    "isNew": false,
+                });
+                if current == Some(key)
+                    && let Some(chars) = list
+                        .last_mut()
+                        .and_then(|b| b.get_mut("chars"))
+                        .and_then(Value::as_array_mut)
+                {
+                    chars.push(ch);
+                    continue;
+                }
+                current = Some(key);
+                list.push(json!({
+                    "ts": r.pull_timestamp / 1000,
+                    "pool": r.pool_name.clone().unwrap_or_else(|| r.pool_id.clone()),
+                    "chars": [ch],
+                }));
+            }
+            json!({ "list": list }).to_string()
+        }
+    }
+}
+
+pub struct ExportFile {
+    pub filename: String,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+pub async fn export(
+    state: &AppState,
+    user_id: Uuid,
+    format: TransferFormat,
+) -> Result<ExportFile, ApiError> {
+    let profile = find_by_id(&state.db, user_id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let gd = Server::parse(&profile.server)
+        .map_or_else(|| state.default_game_data(), |s| state.game_data(s));
+    let records = get_all_for_user(&state.db, user_id).await?;
+
+    Ok(ExportFile {
+        filename: format!(
+            "gacha_{}_{}.{}",
+            profile.uid,
+            format.as_str(),
+            format.extension()
+        ),
+        content_type: format.content_type(),
+        body: render(format, &records, &gd),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_reader_handles_quotes_and_crlf() {
+        let rows = read_csv("a,b\r\n\"x, y\",\"say \"\"hi\"\"\"\r\n1,\n");
+        assert_eq!(
+            rows,
+            vec![
+                vec!["a".to_owned(), "b".to_owned()],
+                vec!["x, y".to_owned(), "say \"hi\"".to_owned()],
+                vec!["1".to_owned(), String::new()],
+            ]
+        );
+    }
+
+    #[test]
+    fn csv_columns_match_by_alias() {
+        let pulls = parse(
+            TransferFormat::Csv,
+            "time,operator,star,banner\n2023-05-01 10:00:00,Exusiai,6,Standard\n",
+        )
+        .unwrap();
+        assert_eq!(pulls.len(), 1);
+        assert_eq!(pulls[0].char_name.as_deref(), Some("Exusiai"));
+        assert_eq!(pulls[0].rarity, Some(6));
+        assert_eq!(pulls[0].at, 1_682_935_200_000);
+    }
+
+    #[test]
+    fn yostar_rows_and_seconds_timestamps() {
+        let pulls = parse(
+            TransferFormat::Json,
+            r#"{"data":{"rows":[{"charId":"char_103_angel","star":"6","poolId":"NORMAL_1","at":1682935200}]}}"#,
+        )
+        .unwrap();
+        assert_eq!(pulls[0].char_id.as_deref(), Some("char_103_angel"));
+        assert_eq!(pulls[0].at, 1_682_935_200_000);
+    }
+
+    #[test]
+    fn hypergryph_logs_shift_rarity_and_detect() {
+        let body = r#"{"data":{"list":[{"ts":1682935200,"pool":"P","chars":[{"name":"A","rarity":5,"isNew":true},{"name":"B","rarity":2,"isNew":false}]}]}}"#;
+        assert_eq!(TransferFormat::detect(body), TransferFormat::Hypergryph);
+        let pulls = parse(TransferFormat::Hypergryph, body).unwrap();
+        assert_eq!(pulls.len(), 2);
+        assert_eq!(pulls[0].rarity, Some(6));
+        assert_eq!(pulls[1].pool_name.as_deref(), Some("P"));
+    }
+
+    #[test]
+    fn batches_index_in_file_order() {
+        let resolver = Resolver::new(&[]);
+        let pull = |id: &str| RawPull {
+            char_id: Some(id.to_owned()),
+            pool_id: Some("LIMITED_1".to_owned()),
+            at: 1000,
+            ..RawPull::default()
+        };
+        let Resolved {
+            records,
+            unresolved,
+            ..
+        } = to_records(
+            &[pull("a"), pull("b"), pull("a")],
+            &resolver,
+            &GameData::default(),
+        );
+        assert!(unresolved.is_empty());
+        let indices: Vec<i64> = records
+            .iter()
+            .map(|r| r["batch_index"].as_i64().unwrap())
+            .collect();
+        assert_eq!(indices, [0, 1, 2]);
+        assert_eq!(records[0]["gacha_type"], "limited");
+    }
+
+    #[test]
+    fn oldest_first_files_index_newest_first() {
+        let resolver = Resolver::new(&[]);
+        let pull = |id: &str, at: i64| RawPull {
+            char_id: Some(id.to_owned()),
+            pool_id: Some("NORMAL_1".to_owned()),
+            at,
+            ..RawPull::default()
+        };
+        let keys = |pulls: &[RawPull]| {
+            let mut keys: Vec<(String, i64, i64)> =
+                to_records(pulls, &resolver, &GameData::default())
+                    .records
+                    .iter()
+                    .map(|r| {
+                        (
+                            r["char_id"].as_str().unwrap().to_owned(),
+                            r["pull_timestamp"].as_i64().unwrap(),
+                            r["batch_index"].as_i64().unwrap(),
+                        )
+                    })
+                    .collect();
+            keys.sort();
+            keys
+        };
+
+        let newest_first = [pull("c", 2000), pull("b", 1000), pull("a", 1000)];
+        let oldest_first = [pull("a", 1000), pull("b", 1000), pull("c", 2000)];
+        assert_eq!(keys(&newest_first), keys(&oldest_first));
+        assert_eq!(
+            keys(&oldest_first),
+            [
+                ("a".to_owned(), 1000, 1),
+                ("b".to_owned(), 1000, 0),
+                ("c".to_owned(), 2000, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn unmatched_banners_are_skipped() {
+        let resolver = Resolver::new(&[]);
+        let pull = RawPull {
+            char_id: Some("char_103_angel".to_owned()),
+            pool_name: Some("Somewhere Else".to_owned()),
+            at: 1000,
+            ..RawPull::default()
+        };
+        let resolved = to_records(&[pull], &resolver, &GameData::default());
+        assert!(resolved.records.is_empty());
+        assert_eq!(resolved.unmatched_pools, ["Somewhere Else"]);
+    }
+}
@@ -1178,25 +1178,53 @@ async fn build_base_improvements(
         return Ok(BaseImprovements::default());
     }
 
-    // Roster → base-skill profiles. Drops operators with no entry in
-    // building_data.chars (e.g. tokens, drones).
-    let profiles: Vec<OperatorBaseProfile> = roster
+    let profiles = base_profiles(roster, game_data);
+    Ok(optimize_base(&profiles, &user_building, game_data, &[]))
+}
+
+/// Roster → base-skill profiles. Drops operators with no entry in
+/// building_data.chars (e.g. tokens, drones).
+pub(crate) fn base_profiles(
+    roster: &[RosterEntry],
+    game_data: &GameData,
+) -> Vec<OperatorBaseProfile> {
+    roster
         .iter()
-        .filter_map(|entry| {
-            let bc = game_data.building.chars.get(&entry.operator_id)?;
-            let static_op = game_data.operators.get(&entry.operator_id);
-            let faction_tags = static_op.map(faction_tags_of).unwrap_or_default();
-            let rarity = static_op.map_or(0, |o| o.rarity.to_star_int());
-            Some(OperatorBaseProfile::build(
-                entry,
-                bc,
-                faction_tags,
-                rarity,
-                &game_data.building,
-            ))
-        })
-        .collect();
+        .filter_map(|entry| base_profile(&entry.operator_id, entry.elite, entry.level, game_data))
+        .collect()
+}
 
+/// Base-skill profile for one operator at `elite`/`level`, or `None` if it has
+/// no base skills.
+pub(crate) fn base_profile(
+    char_id: &str,
+    elite: i16,
+    level: i16,
+    game_data: &GameData,
+) -> Option<OperatorBaseProfile> {
+    let bc = game_data.building.chars.get(char_id)?;
+    let static_op = game_data.operators.get(char_id);
+    let faction_tags = static_op.map(faction_tags_of).unwrap_or_default();
+    let rarity = static_op.map_or(0, |o| o.rarity.to_star_int());
+    Some(OperatorBaseProfile::build_at(
+        elite,
+        level,
+        bc,
+        faction_tags,
+        rarity,
+        &game_data.building,
+    ))
+}
+
+/// Run the base optimizers over `profiles` stationed in `user_building`.
+/// `pins` are `(char_id, room_type)` pairs the peak assignment must honour on
+/// top of any the resource economy reserves.
+pub(crate) fn optimize_base(
+    profiles: &[OperatorBaseProfile],
+    user_building: &UserBuilding,
+    game_data: &GameData,
+    pins: &[(String, String)],
+) -> BaseImprovements {
     let name_to_char = build_name_to_char(&game_data.operators);
     let (registry, morale_drains) = build_registry(&game_data.building.buffs, &name_to_char);
 
@@ -1207,10 +1235,10 @@ async fn build_base_improvements(
     // and its support plan + consumer payoffs are surfaced below. It's a peak/snapshot
     // strategy (it needs operators resting to feed the pool), so the overrides apply ONLY to
     // `optimal` - not `current`, `sustained`, or the shift rotation. 252 is left unmodeled.
-    let perception = is_243_layout(&user_building).then(|| {
+    let perception = is_243_layout(user_building).then(|| {
         evaluate(
-            &profiles,
-            &user_building,
+            profiles,
+            user_building,
             &game_data.building,
             &morale_drains,
             &registry,
@@ -1223,7 +1251,7 @@ async fn build_base_improvements(
     // and a Fiammetta-type morale-swap manager (when owned) - rather than treating the boost as
     // free. Both are gated to a 243 economy; an empty perception leaves the plain optimum.
     let mut optimal_registry = registry.clone();
-    let mut optimal_pins: Vec<(String, String)> = Vec::new();
+    let mut optimal_pins: Vec<(String, String)> = pins.to_vec();
     if let Some(p) = &perception {
         for (buff_id, pct) in &p.overrides {
             optimal_registry.insert(
@@ -1249,24 +1277,24 @@ async fn build_base_improvements(
     }
 
     let current = compute_current_assignment(
-        &profiles,
-        &user_building,
+        profiles,
+        user_building,
         &game_data.building,
         &registry,
         &morale_drains,
         None,
     );
     let optimal = compute_optimal_assignment_with_pins(
-        &profiles,
-        &user_building,
+        profiles,
+        user_building,
         &game_data.building,
         &optimal_registry,
         &morale_drains,
         &optimal_pins,
     );
     let sustained = compute_sustained_assignment(
-        &profiles,
-        &user_building,
+        profiles,
+        user_building,
         &game_data.building,
         &registry,
         &morale_drains,
@@ -1275,17 +1303,17 @@ async fn build_base_improvements(
     // The player's current base as a rotation main, so the comparison can show
     // their sustained output against the optimizer's. A morale-swap manager (Fiammetta) the player
     // owns holds one operator (e.g. a 24/7 Proviso) at full morale, so credit it here too.
-    let cur_recovery = morale_recovery(&user_building);
+    let cur_recovery = morale_recovery(user_building);
     let cur_sustained_set = morale_sustained_beneficiaries(
         &current,
-        &profiles,
+        profiles,
         &morale_drains,
         cur_recovery,
         &game_data.building,
     );
     let current_sustained = sustained_efficiency_of(
         &current,
-        &profiles,
+        profiles,
         &morale_drains,
         cur_recovery,
         &registry,
@@ -1306,14 +1334,14 @@ async fn build_base_improvements(
     let current_dto = base_assignment_to_dto(&current, game_data);
     let optimal_dto = base_assignment_to_dto(&optimal, game_data);
     let rotation_dto = rotation_to_dto(&sustained, game_data);
-    let layout = build_layout_summary(&user_building);
+    let layout = build_layout_summary(user_building);
 
     // The shift rotation is only shown for a 243 base layout (2 trading posts,
     // 4 factories, 3 power plants) - the structure the rotation is designed around.
-    let shift_rotation = if is_243_layout(&user_building) {
+    let shift_rotation = if is_243_layout(user_building) {
         let rotation = recommend_shift_rotation(
-            &profiles,
-            &user_building,
+            profiles,
+            user_building,
             &game_data.building,
             &registry,
             &morale_drains,
@@ -1321,8 +1349,8 @@ async fn build_base_improvements(
         Some(shift_rotation_to_dto(
             &rotation,
             game_data,
-            &profiles,
-            &user_building,
+            profiles,
+            user_building,
             &registry,
             &morale_drains,
         ))
@@ -1334,7 +1362,7 @@ async fn build_base_improvements(
         .as_ref()
         .and_then(|p| perception_to_dto(p, game_data));
 
-    Ok(BaseImprovements {
+    BaseImprovements {
         current: Some(current_dto),
         current_rotation,
         optimal: Some(optimal_dto),
@@ -1342,7 +1370,7 @@ async fn build_base_improvements(
         layout,
         shift_rotation,
         perception,
-    })
+    }
 }
 
 /// Build the resource-economy plan DTO, or `None` when the roster powers no economy on this
@@ -1391,7 +1419,7 @@ fn perception_to_dto(
 
 /// A "243" base: exactly 2 trading posts, 4 factories, and 3 power plants. The
 /// shift rotation is built for this layout, so it is only offered for it.
-fn is_243_layout(building: &UserBuilding) -> bool {
+pub(crate) fn is_243_layout(building: &UserBuilding) -> bool {
     let count = |room_type: &str| {
         building
             .rooms
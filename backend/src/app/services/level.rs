@@ -11,8 +11,24 @@ use serde_json::Value;
 
 use crate::app::cache::keys::CacheKey;
 use crate::app::error::ApiError;
-use crate::app::state::AppState;
+use crate::app::state::{AppState, ServerData};
+use crate::core::gamedata::types::GameData;
+use crate::core::gamedata::types::level::{
+    DIFFICULTIES, StageMap, parse_difficulty, parse_stage_map,
+};
+use crate::core::gamedata::types::stage::Stage;
 use crate::core::hypergryph::constants::Server;
+use crate::core::wave_simulation::simulate;
+
+/// A `difficulty` request parameter, `NORMAL` when absent.
+pub fn difficulty_param(difficulty: Option<&str>) -> Result<&'static str, ApiError> {
+    parse_difficulty(difficulty.unwrap_or("NORMAL")).ok_or_else(|| {
+        ApiError::BadRequest(format!(
+            "difficulty must be one of {}",
+            DIFFICULTIES.join(", ")
+        ))
+    })
+}
 
 pub async fn get_level(
     state: &AppState,
@@ -33,6 +49,78 @@ pub async fn get_level(
     }
 
     let gd = server_data.game_data.load_full();
+    let bytes = read_level_file(&server_data, &gd, stage_id).await?;
+
+    let mut raw: Value =
+        serde_json::from_slice(&bytes).map_err(|e| ApiError::Internal(e.into()))?;
+    reshape_map_matrix(&mut raw);
+    let value = camelize_keys(raw);
+
+    state.cache.set(&key, &value).await;
+    Ok(value)
+}
+
+/// The stage's level parsed into a [`StageMap`] (tiles, routes, schedule,
+/// roster, modifiers). Not cached: callers use it for one-off server-side
+/// computations, not for serving the map itself.
+pub async fn get_stage_map(
+    state: &AppState,
+    server: Server,
+    stage_id: &str,
+) -> Result<StageMap, ApiError> {
+    let server_data = state.try_server_data(server).ok_or(ApiError::NotFound)?;
+    let gd = server_data.game_data.load_full();
+    let bytes = read_level_file(&server_data, &gd, stage_id).await?;
+
+    let (stage, level_id) = match gd.stages.get(stage_id) {
+        Some(stage) => (stage.clone(), stage.level_id.clone().unwrap_or_default()),
+        None => (
+            Stage {
+                stage_id: stage_id.to_owned(),
+                ..Default::default()
+            },
+            gd.mode_levels.get(stage_id).cloned().unwrap_or_default(),
+        ),
+    };
+    parse_stage_map(&stage, &level_id, &bytes, &gd.enemies)
+        .map_err(|e| ApiError::Internal(e.into()))
+}
+
+/// Undefended replay of the stage's waves: per-`step` enemy positions, the
+/// leak timeline and first arrival per tile. Cached per stage and step.
+pub async fn get_wave_simulation(
+    state: &AppState,
+    server: Server,
+    stage_id: &str,
+    step: f64,
+) -> Result<Value, ApiError> {
+    let resource = format!("level-sim:{stage_id}:{step}");
+    let key = CacheKey::StaticData {
+        resource: &resource,
+        server: server.as_str(),
+        fields_hash: 0,
+        page: 0,
+    };
+    if let Some(cached) = state.cache.get::<Value>(&key).await {
+        return Ok(cached);
+    }
+
+    let map = get_stage_map(state, server, stage_id).await?;
+    let value = tokio::task::spawn_blocking(move || serde_json::to_value(simulate(&map, step)))
+        .await
+        .map_err(|e| ApiError::Internal(e.into()))?
+        .map_err(|e| ApiError::Internal(e.into()))?;
+
+    state.cache.set(&key, &value).await;
+    Ok(value)
+}
+
+/// Resolve a stage id (or a mode-level id) to its `level_*.json` and read it.
+async fn read_level_file(
+    server_data: &ServerData,
+    gd: &GameData,
+    stage_id: &str,
+) -> Result<Vec<u8>, ApiError> {
     let level_id = match gd.stages.get(stage_id) {
         Some(stage) => stage.level_id.clone().ok_or(ApiError::NotFound)?,
         None => gd
@@ -42,21 +130,16 @@ pub async fn get_level(
             .ok_or(ApiError::NotFound)?,
     };
 
-    // level_id (e.g. "Obt/Main/level_main_01-07") lowercases to the on-disk path
-    // under `gamedata/levels/`.
-    let rel = level_id.to_lowercase().replace('\\', "/");
-    let path = format!("{}/gamedata/levels/{rel}.json", server_data.assets_dir);
-    let bytes = tokio::fs::read(&path)
+    tokio::fs::read(level_path(server_data, &level_id))
         .await
-        .map_err(|_| ApiError::NotFound)?;
-
-    let mut raw: Value =
-        serde_json::from_slice(&bytes).map_err(|e| ApiError::Internal(e.into()))?;
-    reshape_map_matrix(&mut raw);
-    let value = camelize_keys(raw);
+        .map_err(|_| ApiError::NotFound)
+}
 
-    state.cache.set(&key, &value).await;
-    Ok(value)
+/// On-disk path of a level: the `level_id` (e.g. "Obt/Main/level_main_01-07")
+/// lowercased under `gamedata/levels/`.
+pub(crate) fn level_path(server_data: &ServerData, level_id: &str) -> String {
+    let rel = level_id.to_lowercase().replace('\\', "/");
+    format!("{}/gamedata/levels/{rel}.json", server_data.assets_dir)
 }
 
 /// Rewrite `MapData.Map` from the EN/Yostar flattened `{Column_size, Row_size,
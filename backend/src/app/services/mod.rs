@@ -1,6 +1,11 @@
 pub mod auth;
+pub mod base_simulation;
+pub mod base_what_if;
+pub mod changelog;
 pub mod dps;
+pub mod farming;
 pub mod gacha;
+pub mod gacha_transfer;
 pub mod game_session;
 pub mod improvements;
 pub mod leaderboard;
@@ -8,9 +13,16 @@ pub mod level;
 pub mod operator_notes;
 pub mod operators;
 pub mod planner;
+pub mod pull_planner;
+pub mod recruitment;
+pub mod replays;
 pub mod roster;
+pub mod roster_dps;
+pub mod roster_history;
 pub mod search;
 pub mod social;
+pub mod stage_metrics;
+pub mod stage_modifiers;
 pub mod static_data;
 pub mod stats;
 pub mod tier_list;
@@ -13,6 +13,7 @@ use crate::core::gamedata::types::operator::{
 use crate::core::gamedata::types::skin::SkinData;
 use crate::core::gamedata::types::voice::Voices;
 use crate::core::hypergryph::constants::Server;
+use crate::core::operator_query::{OperatorQuery, StatLine, run};
 use crate::database::queries::operator_ownership;
 
 /// Compact operator record for client-side search palettes and autocompletes.
@@ -167,6 +168,37 @@ pub async fn get_index(
     Ok(entries)
 }
 
+/// An operator matching a structured query: its index record plus base stats
+/// at the query's sort elite/level (max otherwise).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperatorQueryHit {
+    #[serde(flatten)]
+    pub entry: OperatorIndexEntry,
+    pub stats_at: Option<StatLine>,
+}
+
+/// Filter and sort a server's operators. Runs straight over the loaded data;
+/// a pass over a few hundred operators is cheaper than caching every query.
+pub fn query_operators(
+    state: &AppState,
+    server: Server,
+    query: &OperatorQuery,
+) -> Result<Vec<OperatorQueryHit>, ApiError> {
+    let server_data = state.try_server_data(server).ok_or(ApiError::NotFound)?;
+    let gd = server_data.game_data.load();
+    Ok(run(&gd.operators, query)
+        .into_iter()
+        .filter_map(|m| {
+            let op = gd.operators.get(&m.id)?;
+            Some(OperatorQueryHit {
+                entry: to_index_entry(&m.id, op),
+                stats_at: m.stats,
+            })
+        })
+        .collect())
+}
+
 /// Population-level ownership: how many sharing players own each operator, plus
 /// the denominator. Only operators with at least one owner are listed; a missing
 /// id implies zero owners. `totalUsers` is the eligible population on this
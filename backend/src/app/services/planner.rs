@@ -171,7 +171,7 @@ fn resolve_requirement_item(
     }
 }
 
-fn calculate_leveling_costs(
+pub(crate) fn calculate_leveling_costs(
     operator: &Operator,
     gamedata: &GameData,
     current_elite: i16,
@@ -485,25 +485,13 @@ fn get_plan_direct_materials(
         &mut materials,
     );
 
-    if plan.target_elite > current_elite {
-        for elite in (current_elite + 1)..=plan.target_elite {
-            if let Some(ref evolve_costs) = operator.phases[elite as usize].evolve_cost {
-                for cost in evolve_costs {
-                    *materials.entry(cost.id.clone()).or_insert(0) += cost.count;
-                }
-            }
-        }
-    }
-
-    if plan.target_skill_level > current_skill_level {
-        for i in (current_skill_level - 1)..(plan.target_skill_level - 1) {
-            if let Some(lvl_up) = operator.all_skill_level_up.get(i as usize) {
-                for cost in &lvl_up.lvl_up_cost {
-                    *materials.entry(cost.id.clone()).or_insert(0) += cost.count;
-                }
-            }
-        }
-    }
+    add_promotion_costs(operator, current_elite, plan.target_elite, &mut materials);
+    add_skill_level_costs(
+        operator,
+        current_skill_level,
+        plan.target_skill_level,
+        &mut materials,
+    );
 
     for target_skill in &skill_plans {
         let idx = target_skill.skill_index;
@@ -511,18 +499,13 @@ fn get_plan_direct_materials(
             .iter()
             .find(|m| m.index == idx)
             .map_or(0, |m| m.mastery);
-
-        if target_skill.mastery_level > current_mast
-            && let Some(skill_entry) = operator.skills.get(idx as usize)
-        {
-            for i in (current_mast as usize)..(target_skill.mastery_level as usize) {
-                if let Some(cond) = skill_entry.level_up_cost_cond.get(i) {
-                    for cost in &cond.level_up_cost {
-                        *materials.entry(cost.id.clone()).or_insert(0) += cost.count;
-                    }
-                }
-            }
-        }
+        add_mastery_costs(
+            operator,
+            idx,
+            current_mast,
+            target_skill.mastery_level,
+            &mut materials,
+        );
     }
 
     for target_module in &module_plans {
@@ -530,24 +513,98 @@ fn get_plan_direct_materials(
             .iter()
             .find(|m| m.id == target_module.module_id)
             .map_or(0, |m| m.level);
-        if target_module.module_stage > current_level
-            && let Some(op_mod) = operator
-                .modules
-                .iter()
-                .find(|m| m.module.uni_equip_id == target_module.module_id)
-            && let Some(ref item_cost_map) = op_mod.module.item_cost
+        add_module_costs(
+            operator,
+            &target_module.module_id,
+            current_level,
+            target_module.module_stage,
+            &mut materials,
+        );
+    }
+
+    Ok(materials)
+}
+
+/// Promotion materials for `from` -> `to` elite (evolve costs only; leveling
+/// is `calculate_leveling_costs`).
+pub(crate) fn add_promotion_costs(
+    operator: &Operator,
+    from: i16,
+    to: i16,
+    materials: &mut HashMap<String, i32>,
+) {
+    for elite in (from + 1)..=to {
+        if let Some(evolve_costs) = operator
+            .phases
+            .get(elite as usize)
+            .and_then(|p| p.evolve_cost.as_ref())
         {
-            for stage in (current_level + 1)..=target_module.module_stage {
-                if let Some(costs) = item_cost_map.get(&stage.to_string()) {
-                    for cost in costs {
-                        *materials.entry(cost.id.clone()).or_insert(0) += cost.count;
-                    }
-                }
+            for cost in evolve_costs {
+                *materials.entry(cost.id.clone()).or_insert(0) += cost.count;
             }
         }
     }
+}
 
-    Ok(materials)
+/// Shared skill-level materials for `from` -> `to` (1-7).
+pub(crate) fn add_skill_level_costs(
+    operator: &Operator,
+    from: i16,
+    to: i16,
+    materials: &mut HashMap<String, i32>,
+) {
+    for i in (from - 1).max(0)..(to - 1) {
+        if let Some(lvl_up) = operator.all_skill_level_up.get(i as usize) {
+            for cost in &lvl_up.lvl_up_cost {
+                *materials.entry(cost.id.clone()).or_insert(0) += cost.count;
+            }
+        }
+    }
+}
+
+/// Mastery materials for the 0-based skill `skill_index`, `from` -> `to`.
+pub(crate) fn add_mastery_costs(
+    operator: &Operator,
+    skill_index: i16,
+    from: i16,
+    to: i16,
+    materials: &mut HashMap<String, i32>,
+) {
+    let Some(skill_entry) = operator.skills.get(skill_index as usize) else {
+        return;
+    };
+    for i in from.max(0)..to {
+        if let Some(cond) = skill_entry.level_up_cost_cond.get(i as usize) {
+            for cost in &cond.level_up_cost {
+                *materials.entry(cost.id.clone()).or_insert(0) += cost.count;
+            }
+        }
+    }
+}
+
+/// Module stage materials for `module_id` (a `uniEquipId`), `from` -> `to`.
+pub(crate) fn add_module_costs(
+    operator: &Operator,
+    module_id: &str,
+    from: i16,
+    to: i16,
+    materials: &mut HashMap<String, i32>,
+) {
+    let Some(item_cost_map) = operator
+        .modules
+        .iter()
+        .find(|m| m.module.uni_equip_id == module_id)
+        .and_then(|m| m.module.item_cost.as_ref())
+    else {
+        return;
+    };
+    for stage in (from + 1)..=to {
+        if let Some(costs) = item_cost_map.get(&stage.to_string()) {
+            for cost in costs {
+                *materials.entry(cost.id.clone()).or_insert(0) += cost.count;
+            }
+        }
+    }
 }
 
 /// Builds requirement trees for the aggregated plan materials against a single
@@ -0,0 +1,586 @@
+//! Pull planner: how likely a player is to get the rate-up operators they are
+//! saving for. Starts from the synced wallet (`orundum`, `gacha_tickets`,
+//! `ten_pull_tickets`, optionally originite), adds projected free income day
+//! by day, and plays the target banners in order thousands of times with the
+//! pity model from `core::gacha_odds`.
+//!
+//! The strategy is the saver's: skip everything else, pull on each target
+//! banner as late as possible (its close, or `until`) and stop once every
+//! wanted operator on it has dropped. Event, store and login-calendar rewards
+//! aren't projected, so the odds lean pessimistic.
+
+use std::collections::HashMap;
+
+use rand::rngs::SmallRng;
+use rand::{RngExt, SeedableRng};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::app::error::ApiError;
+use crate::app::services::gacha::PityReplay;
+use crate::app::state::AppState;
+use crate::core::gacha_odds::{BannerRule, HARD_PITY, six_star_rate};
+use crate::core::gamedata::types::gacha::GachaPoolClient;
+use crate::core::hypergryph::constants::Server;
+use crate::database::models::user::UserProfile;
+use crate::database::queries::gacha::get_all_for_user;
+use crate::database::queries::users::find_by_id;
+
+pub const ORUNDUM_PER_PULL: i64 = 600;
+pub const ORUNDUM_PER_ORIGINITE: i64 = 180;
+const DAILY_MISSION_ORUNDUM: i64 = 100;
+const WEEKLY_MISSION_ORUNDUM: i64 = 500;
+/// Weekly Annihilation cap.
+const ANNIHILATION_ORUNDUM: i64 = 1800;
+const MONTHLY_CARD_ORUNDUM: i64 = 200;
+
+const DAY_SECS: i64 = 86_400;
+const DEFAULT_SIMULATIONS: u32 = 5000;
+const MAX_SIMULATIONS: u32 = 20_000;
+const MAX_TARGETS: usize = 10;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanTarget {
+    pub pool_id: String,
+    pub char_id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PullPlanRequest {
+    pub targets: Vec<PlanTarget>,
+    /// Unix seconds. Defaults to the last target banner's close.
+    pub until: Option<i64>,
+    /// Count originite as orundum (1 : 180). Off by default.
+    #[serde(default)]
+    pub spend_originite: bool,
+    /// Keep the monthly card running past its synced end.
+    #[serde(default)]
+    pub renew_monthly_card: bool,
+    /// Clear Annihilation to the weekly cap. Defaults to true.
+    pub annihilation: Option<bool>,
+    pub simulations: Option<u32>,
+    /// Fixes the random stream, for reproducible plans.
+    pub seed: Option<u64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Wallet {
+    pub orundum: i64,
+    pub originite: i64,
+    pub gacha_tickets: i64,
+    pub ten_pull_tickets: i64,
+    /// Pulls the wallet buys today, originite included only when spent.
+    pub pulls: i64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Income {
+    pub days: i64,
+    pub daily_missions: i64,
+    pub weekly_missions: i64,
+    pub annihilation: i64,
+    pub monthly_card: i64,
+    pub orundum: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetOutcome {
+    pub pool_id: String,
+    pub pool_name: String,
+    pub char_id: String,
+    pub rule: &'static str,
+    /// When the plan pulls on the banner; `None` if it opens after `until`.
+    pub pull_at: Option<i64>,
+    pub probability: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BannerOutcome {
+    pub pool_id: String,
+    pub pull_at: i64,
+    /// Pulls the plan can afford on arrival in the best case (nothing spent
+    /// earlier).
+    pub max_affordable_pulls: i64,
+    pub mean_pulls_spent: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PullPlan {
+    pub simulations: u32,
+    pub until: i64,
+    pub wallet: Wallet,
+    /// Free income projected from now to `until`.
+    pub income: Income,
+    pub monthly_card_until: Option<i64>,
+    pub targets: Vec<TargetOutcome>,
+    pub banners: Vec<BannerOutcome>,
+    /// Chance of every target, reachable or not.
+    pub all_targets_probability: f64,
+    /// Median pulls still in the wallet at `until`.
+    pub median_pulls_left: i64,
+}
+
+/// Income assumptions for one plan.
+#[derive(Debug, Clone, Copy)]
+struct IncomeModel {
+    now: i64,
+    annihilation: bool,
+    /// Last second the monthly card pays out; `None` = no card.
+    monthly_card_until: Option<i64>,
+}
+
+impl IncomeModel {
+    /// Free orundum from `now` to `at`. Days count whole daily resets passed;
+    /// weekly rewards land every seventh day.
+    fn between(&self, at: i64) -> Income {
+        let days = ((at - self.now) / DAY_SECS).max(0);
+        let weeks = days / 7;
+        let card_days = self
+            .monthly_card_until
+            .map_or(0, |end| ((end.min(at) - self.now) / DAY_SECS).max(0));
+        let mut income = Income {
+            days,
+            daily_missions: days * DAILY_MISSION_ORUNDUM,
+            weekly_missions: weeks * WEEKLY_MISSION_ORUNDUM,
+            annihilation: if self.annihilation {
+                weeks * ANNIHILATION_ORUNDUM
+            } else {
+                0
+            },
+            monthly_card: card_days * MONTHLY_CARD_ORUNDUM,
+            orundum: 0,
+        };
+        income.orundum = income.daily_missions
+            + income.weekly_missions
+            + income.annihilation
+            + income.monthly_card;
+        income
+    }
+}
+
+/// One target banner as the simulation sees it.
+#[derive(Debug, Clone)]
+struct BannerPlan {
+    pool_id: String,
+    rule: BannerRule,
+    /// Rate-up 6★s; the plan's targets are the first `targets.len()`.
+    featured: u32,
+    pull_at: i64,
+    /// Indices into the request's target list.
+    targets: Vec<usize>,
+    banner_pulls: u32,
+    featured_seen: bool,
+}
+
+#[derive(Debug, Clone)]
+struct SimInput {
+    banners: Vec<BannerPlan>,
+    target_count: usize,
+    /// Pity carried from banner to banner, keyed by `pity_key`.
+    start_pity: HashMap<String, u32>,
+    /// Orundum-equivalent wallet at the start.
+    budget: i64,
+    /// Cumulative free income at each banner's `pull_at`, then at `until`.
+    income_at: Vec<i64>,
+}
+
+#[derive(Debug)]
+struct SimOutcome {
+    target_hits: Vec<u32>,
+    all_hits: u32,
+    pulls_spent: Vec<u64>,
+    pulls_left: Vec<i64>,
+}
+
+fn pity_key(rule: BannerRule, pool_id: &str) -> String {
+    if rule.shares_pity() {
+        rule.as_str().to_owned()
+    } else {
+        pool_id.to_owned()
+    }
+}
+
+fn simulate(input: &SimInput, runs: u32, rng: &mut SmallRng) -> SimOutcome {
+    let mut outcome = SimOutcome {
+        target_hits: vec![0; input.target_count],
+        all_hits: 0,
+        pulls_spent: vec![0; input.banners.len()],
+        pulls_left: Vec::with_capacity(runs as usize),
+    };
+
+    for _ in 0..runs {
+        let mut pity = input.start_pity.clone();
+        let mut spent = 0i64;
+        let mut got = vec![false; input.target_count];
+
+        for (b, banner) in input.banners.iter().enumerate() {
+            let counter = pity
+                .entry(pity_key(banner.rule, &banner.pool_id))
+                .or_default();
+            let mut budget = input.budget + input.income_at[b] - spent;
+            let mut banner_pulls = banner.banner_pulls;
+            let mut guarantee_pending = banner
+                .rule
+                .featured_guarantee()
+                .is_some_and(|g| !banner.featured_seen && banner_pulls < g);
+
+            while budget >= ORUNDUM_PER_PULL && banner.targets.iter().any(|&t| !got[t]) {
+                budget -= ORUNDUM_PER_PULL;
+                spent += ORUNDUM_PER_PULL;
+                banner_pulls += 1;
+                outcome.pulls_spent[b] += 1;
+
+                let forced = guarantee_pending
+                    && banner
+                        .rule
+                        .featured_guarantee()
+                        .is_some_and(|g| banner_pulls >= g);
+                let featured_hit = if forced || rng.random::<f64>() < six_star_rate(*counter) {
+                    *counter = 0;
+                    forced || rng.random::<f64>() < banner.rule.featured_share()
+                } else {
+                    *counter = (*counter + 1).min(HARD_PITY - 1);
+                    false
+                };
+                if featured_hit {
+                    guarantee_pending = false;
+                    let pick = rng.random_range(0..banner.featured) as usize;
+                    if let Some(&t) = banner.targets.get(pick) {
+                        got[t] = true;
+                    }
+                }
+                if banner.rule.spark() == Some(banner_pulls)
+                    && let Some(&t) = banner.targets.iter().find(|&&t| !got[t])
+                {
+                    got[t] = true;
+                }
+            }
+        }
+
+        for (hits, &g) in outcome.target_hits.iter_mut().zip(&got) {
+            *hits += u32::from(g);
+        }
+        outcome.all_hits += u32::from(got.iter().all(|&g| g));
+        let final_income = input.income_at.last().copied().unwrap_or(0);
+        outcome
+            .pulls_left
+            .push((input.budget + final_income - spent) / ORUNDUM_PER_PULL);
+    }
+    outcome
+}
+
+fn wallet_of(profile: &UserProfile, spend_originite: bool) -> Wallet {
+    let mut wallet = Wallet {
+        orundum: i64::from(profile.orundum.unwrap_or(0)),
+        originite: i64::from(profile.originite.unwrap_or(0)),
+        gacha_tickets: i64::from(profile.gacha_tickets.unwrap_or(0)),
+        ten_pull_tickets: i64::from(profile.ten_pull_tickets.unwrap_or(0)),
+        pulls: 0,
+    };
+    wallet.pulls = wallet_orundum(&wallet, spend_originite) / ORUNDUM_PER_PULL;
+    wallet
+}
+
+/// The wallet in orundum, tickets at one pull each.
+fn wallet_orundum(wallet: &Wallet, spend_originite: bool) -> i64 {
+    let originite = if spend_originite {
+        wallet.originite * ORUNDUM_PER_ORIGINITE
+    } else {
+        0
+    };
+    wallet.orundum
+        + originite
+        + (wallet.gacha_tickets + wallet.ten_pull_tickets * 10) * ORUNDUM_PER_PULL
+}
+
+pub async fn plan(
+    state: &AppState,
+    user_id: Uuid,
+    req: &PullPlanRequest,
+) -> Result<PullPlan, ApiError> {
+    if req.targets.is_empty() || req.targets.len() > MAX_TARGETS {
+        return Err(ApiError::BadRequest(format!(
+            "between 1 and {MAX_TARGETS} targets"
+        )));
+    }
+    for (i, t) in req.targets.iter().enumerate() {
+        if req.targets[..i]
+            .iter()
+            .any(|u| u.pool_id == t.pool_id && u.char_id == t.char_id)
+        {
+            return Err(ApiError::BadRequest(format!(
+                "{} on {} is listed twice",
+                t.char_id, t.pool_id
+            )));
+        }
+    }
+    let profile = find_by_id(&state.db, user_id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let gd = Server::parse(&profile.server)
+        .map_or_else(|| state.default_game_data(), |s| state.game_data(s));
+    let pools: HashMap<&str, &GachaPoolClient> = gd
+        .gacha
+        .gacha_pool_client
+        .iter()
+        .map(|p| (p.gacha_pool_id.as_str(), p))
+        .collect();
+
+    let now = chrono::Utc::now().timestamp();
+    let mut resolved = Vec::with_capacity(req.targets.len());
+    for target in &req.targets {
+        let pool = *pools
+            .get(target.pool_id.as_str())
+            .ok_or_else(|| ApiError::BadRequest(format!("unknown banner {}", target.pool_id)))?;
+        let rule = BannerRule::from_rule_type(&pool.gacha_rule_type).ok_or_else(|| {
+            ApiError::BadRequest(format!(
+                "no 6★ model for rule type {}",
+                pool.gacha_rule_type
+            ))
+        })?;
+        if pool.end_time <= now {
+            return Err(ApiError::BadRequest(format!(
+                "{} has already closed",
+                target.pool_id
+            )));
+        }
+        if !pool.featured6.is_empty() && !pool.featured6.contains(&target.char_id) {
+            return Err(ApiError::BadRequest(format!(
+                "{} is not a rate-up 6★ on {}",
+                target.char_id, target.pool_id
+            )));
+        }
+        resolved.push((pool, rule));
+    }
+
+    let until = req.until.unwrap_or_else(|| {
+        resolved
+            .iter()
+            .map(|(p, _)| p.end_time)
+            .max()
+            .unwrap_or(now)
+    });
+    if until <= now {
+        return Err(ApiError::BadRequest("until is in the past".into()));
+    }
+
+    let rows = get_all_for_user(&state.db, user_id).await?;
+    let replay = PityReplay::new(&pools, &rows);
+
+    // One banner entry per pool, pulled at its close (or `until`), in order.
+    let mut banners: Vec<BannerPlan> = Vec::new();
+    for (i, (pool, rule)) in resolved.iter().enumerate() {
+        if pool.open_time > until {
+            continue;
+        }
+        match banners.iter_mut().find(|b| b.pool_id == pool.gacha_pool_id) {
+            Some(banner) => banner.targets.push(i),
+            None => banners.push(BannerPlan {
+                pool_id: pool.gacha_pool_id.clone(),
+                rule: *rule,
+                featured: 0,
+                pull_at: pool.end_time.min(until),
+                targets: vec![i],
+                banner_pulls: replay.banner_pulls(&pool.gacha_pool_id),
+                featured_seen: replay.featured_seen.contains(&pool.gacha_pool_id),
+            }),
+        }
+    }
+    for banner in &mut banners {
+        let listed = pools
+            .get(banner.pool_id.as_str())
+            .map_or(0, |p| p.featured6.len());
+        banner.featured = listed.max(banner.targets.len()) as u32;
+    }
+    banners.sort_by_key(|b| b.pull_at);
+
+    let monthly_card_until = if req.renew_monthly_card {
+        Some(until)
+    } else {
+        profile.monthly_sub_end.filter(|&end| end > now)
+    };
+    let income_model = IncomeModel {
+        now,
+        annihilation: req.annihilation.unwrap_or(true),
+        monthly_card_until,
+    };
+    let wallet = wallet_of(&profile, req.spend_originite);
+    let budget = wallet_orundum(&wallet, req.spend_originite);
+
+    let mut start_pity: HashMap<String, u32> = HashMap::new();
+    for banner in &banners {
+        let pity = replay
+            .counter(banner.rule, &banner.pool_id)
+            .map_or(0, |c| c.pity);
+        start_pity.insert(pity_key(banner.rule, &banner.pool_id), pity);
+    }
+    let input = SimInput {
+        income_at: banners
+            .iter()
+            .map(|b| b.pull_at)
+            .chain(std::iter::once(until))
+            .map(|t| income_model.between(t).orundum)
+            .collect(),
+        banners,
+        target_count: req.targets.len(),
+        start_pity,
+        budget,
+    };
+
+    let runs = req
+        .simulations
+        .unwrap_or(DEFAULT_SIMULATIONS)
+        .clamp(1, MAX_SIMULATIONS);
+    let seed = req.seed.unwrap_or_else(rand::random);
+    let (input, mut outcome) = tokio::task::spawn_blocking(move || {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let outcome = simulate(&input, runs, &mut rng);
+        (input, outcome)
+    })
+    .await
+    .map_err(|e| ApiError::Internal(e.into()))?;
+    outcome.pulls_left.sort_unstable();
+
+    let targets = req
+        .targets
+        .iter()
+        .zip(&resolved)
+        .enumerate()
+        .map(|(i, (t, (pool, rule)))| TargetOutcome {
+            pool_id: t.pool_id.clone(),
+            pool_name: pool.gacha_pool_name.clone(),
+            char_id: t.char_id.clone(),
+            rule: rule.as_str(),
+            pull_at: input
+                .banners
+                .iter()
+                .find(|b| b.pool_id == t.pool_id)
+                .map(|b| b.pull_at),
+            probability: f64::from(outcome.target_hits[i]) / f64::from(runs),
+        })
+        .collect();
+    let banners = input
+        .banners
+        .iter()
+        .enumerate()
+        .map(|(b, banner)| BannerOutcome {
+            pool_id: banner.pool_id.clone(),
+            pull_at: banner.pull_at,
+            max_affordable_pulls: (budget + input.income_at[b]) / ORUNDUM_PER_PULL,
+            mean_pulls_spent: outcome.pulls_spent[b] as f64 / f64::from(runs),
+        })
+        .collect();
+
+    Ok(PullPlan {
+        simulations: runs,
+        until,
+        wallet,
+        income: income_model.between(until),
+        monthly_card_until,
+        targets,
+        banners,
+        all_targets_probability: f64::from(outcome.all_hits) / f64::from(runs),
+        median_pulls_left: outcome.pulls_left[outcome.pulls_left.len() / 2],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn banner(pool_id: &str, rule: BannerRule, pull_at: i64, targets: Vec<usize>) -> BannerPlan {
+        BannerPlan {
+            pool_id: pool_id.to_owned(),
+            rule,
+            featured: targets.len() as u32,
+            pull_at,
+            targets,
+            banner_pulls: 0,
+            featured_seen: false,
+        }
+    }
+
+    fn input(banners: Vec<BannerPlan>, budget_pulls: i64) -> SimInput {
+        SimInput {
+            income_at: vec![0; banners.len() + 1],
+            target_count: banners.iter().map(|b| b.targets.len()).sum(),
+            banners,
+            start_pity: HashMap::new(),
+            budget: budget_pulls * ORUNDUM_PER_PULL,
+        }
+    }
+
+    #[test]
+    fn income_counts_days_weeks_and_the_card() {
+        let model = IncomeModel {
+            now: 0,
+            annihilation: true,
+            monthly_card_until: Some(10 * DAY_SECS),
+        };
+        let income = model.between(14 * DAY_SECS + 5);
+        assert_eq!(income.days, 14);
+        assert_eq!(income.weekly_missions, 2 * WEEKLY_MISSION_ORUNDUM);
+        assert_eq!(income.annihilation, 2 * ANNIHILATION_ORUNDUM);
+        assert_eq!(income.monthly_card, 10 * MONTHLY_CARD_ORUNDUM);
+        assert_eq!(income.orundum, 1400 + 1000 + 3600 + 2000);
+    }
+
+    #[test]
+    fn a_full_spark_budget_always_lands_the_limited() {
+        let input = input(
+            vec![banner("LIMITED_1", BannerRule::Limited, 10, vec![0])],
+            300,
+        );
+        let mut rng = SmallRng::seed_from_u64(7);
+        let outcome = simulate(&input, 500, &mut rng);
+        assert_eq!(outcome.target_hits, vec![500]);
+        assert!(outcome.pulls_left.iter().all(|&p| p >= 0));
+    }
+
+    #[test]
+    fn a_banner_sparks_only_once() {
+        let mut spent = banner("LIMITED_1", BannerRule::Limited, 10, vec![0]);
+        // Already past the spark: the next 60 pulls reach 600, not a second one.
+        spent.banner_pulls = 540;
+        let input = input(vec![spent], 60);
+        let mut rng = SmallRng::seed_from_u64(7);
+        let outcome = simulate(&input, 500, &mut rng);
+        assert!(outcome.target_hits[0] < 500);
+    }
+
+    #[test]
+    fn an_empty_wallet_gets_nothing() {
+        let input = input(
+            vec![banner("NORMAL_1", BannerRule::Standard, 10, vec![0])],
+            0,
+        );
+        let mut rng = SmallRng::seed_from_u64(7);
+        assert_eq!(simulate(&input, 100, &mut rng).target_hits, vec![0]);
+    }
+
+    #[test]
+    fn earlier_banners_eat_into_later_ones() {
+        let solo = input(
+            vec![banner("LIMITED_2", BannerRule::Limited, 20, vec![0])],
+            150,
+        );
+        let shared = input(
+            vec![
+                banner("LIMITED_1", BannerRule::Limited, 10, vec![0]),
+                banner("LIMITED_2", BannerRule::Limited, 20, vec![1]),
+            ],
+            150,
+        );
+        let mut rng = SmallRng::seed_from_u64(7);
+        let alone = simulate(&solo, 2000, &mut rng).target_hits[0];
+        let after = simulate(&shared, 2000, &mut rng).target_hits[1];
+        assert!(after < alone);
+    }
+}
@@ -0,0 +1,243 @@
+//! `/recruitment/solve`: the canonical tag solver (`core::recruitment`) with
+//! each outcome flagged against the caller's roster.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::app::error::ApiError;
+use crate::app::state::AppState;
+use crate::core::gamedata::types::gacha::GachaTag;
+use crate::core::hypergryph::constants::Server;
+use crate::core::recruitment::{
+    MAX_OFFERED_TAGS, Recruitable, SolveOptions, TagCombination, recruitable_pool, solve,
+};
+use crate::database::queries::roster::get_roster;
+use crate::database::queries::users::find_by_id;
+
+#[derive(Deserialize)]
+pub struct SolveParams {
+    /// Comma-separated tag ids or names (any case).
+    pub tags: String,
+    pub server: Option<String>,
+    pub uid: Option<String>,
+    #[serde(alias = "include_robots")]
+    #[serde(rename = "includeRobots", default)]
+    pub include_robots: Option<bool>,
+    #[serde(alias = "include_two_stars")]
+    #[serde(rename = "includeTwoStars", default)]
+    pub include_two_stars: Option<bool>,
+    #[serde(alias = "include_three_stars")]
+    #[serde(rename = "includeThreeStars", default)]
+    pub include_three_stars: Option<bool>,
+}
+
+/// An outcome against the roster. Absent when solving without a user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OwnershipStatus {
+    /// Not in the roster.
+    New,
+    /// Owned below max potential, so a duplicate still counts.
+    Potential,
+    /// Owned at max potential.
+    Maxed,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecruitOutcome {
+    #[serde(flatten)]
+    pub operator: Recruitable,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<OwnershipStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub potential: Option<i16>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecruitResult {
+    pub tags: Vec<GachaTag>,
+    pub operators: Vec<RecruitOutcome>,
+    pub guaranteed_rarity: i16,
+    pub max_rarity: i16,
+    pub five_star_chance: f64,
+    /// Outcomes not yet owned.
+    pub new_count: usize,
+    /// Every outcome is new or still needs potential.
+    pub all_useful: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecruitSolution {
+    pub server: &'static str,
+    pub tags: Vec<GachaTag>,
+    /// Whether outcomes carry roster flags.
+    pub roster_aware: bool,
+    pub results: Vec<RecruitResult>,
+}
+
+/// `(potential, max potential)` per owned operator.
+type Holdings = HashMap<String, (i16, i16)>;
+
+fn parse_tags(all: &[GachaTag], input: &str) -> Result<Vec<GachaTag>, ApiError> {
+    let mut tags: Vec<GachaTag> = Vec::new();
+    for token in input.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        let tag = match token.parse::<i32>() {
+            Ok(id) => all.iter().find(|t| t.tag_id == id),
+            Err(_) => all.iter().find(|t| t.tag_name.eq_ignore_ascii_case(token)),
+        }
+        .ok_or_else(|| ApiError::BadRequest(format!("unknown recruitment tag {token}")))?;
+        if !tags.iter().any(|t| t.tag_id == tag.tag_id) {
+            tags.push(tag.clone());
+        }
+    }
+    if tags.is_empty() {
+        return Err(ApiError::BadRequest("no tags given".into()));
+    }
+    if tags.len() > MAX_OFFERED_TAGS {
+        return Err(ApiError::BadRequest(format!(
+            "at most {MAX_OFFERED_TAGS} tags"
+        )));
+    }
+    Ok(tags)
+}
+
+fn status_of(holdings: &Holdings, char_id: &str) -> (OwnershipStatus, Option<i16>) {
+    match holdings.get(char_id) {
+        None => (OwnershipStatus::New, None),
+        Some(&(potential, max)) if potential < max => (OwnershipStatus::Potential, Some(potential)),
+        Some(&(potential, _)) => (OwnershipStatus::Maxed, Some(potential)),
+    }
+}
+
+fn to_result(combo: &TagCombination, holdings: Option<&Holdings>) -> RecruitResult {
+    let operators: Vec<RecruitOutcome> = combo
+        .operators
+        .iter()
+        .map(|op| {
+            let (status, potential) = holdings
+                .map(|h| status_of(h, &op.char_id))
+                .map_or((None, None), |(s, p)| (Some(s), p));
+            RecruitOutcome {
+                operator: (*op).clone(),
+                status,
+                potential,
+            }
+        })
+        .collect();
+    let new_count = operators
+        .iter()
+        .filter(|o| o.status == Some(OwnershipStatus::New))
+        .count();
+    let all_useful = holdings.is_some()
+        && operators
+            .iter()
+            .all(|o| o.status != Some(OwnershipStatus::Maxed));
+
+    RecruitResult {
+        tags: combo.tags.iter().map(|t| (*t).clone()).collect(),
+        guaranteed_rarity: combo.guaranteed_rarity,
+        max_rarity: combo.max_rarity,
+        five_star_chance: combo.five_star_chance(),
+        operators,
+        new_count,
+        all_useful,
+    }
+}
+
+/// Solve `params.tags` on the user's server (or `params.server`), flagging
+/// outcomes against the roster when `user_id` is given.
+pub async fn solve_tags(
+    state: &AppState,
+    user_id: Option<Uuid>,
+    params: &SolveParams,
+) -> Result<RecruitSolution, ApiError> {
+    let (server, holdings) = match user_id {
+        Some(user_id) => {
+            let profile = find_by_id(&state.db, user_id)
+                .await?
+                .ok_or(ApiError::NotFound)?;
+            let server = Server::parse(&profile.server).unwrap_or(state.default_server);
+            (server, Some(get_roster(&state.db, user_id).await?))
+        }
+        None => {
+            let server = match params.server.as_deref() {
+                Some(s) => Server::parse(s)
+                    .ok_or_else(|| ApiError::BadRequest(format!("unknown server {s}")))?,
+                None => state.default_server,
+            };
+            (server, None)
+        }
+    };
+    let gd = state.game_data(server);
+
+    let holdings: Option<Holdings> = holdings.map(|roster| {
+        roster
+            .into_iter()
+            .map(|entry| {
+                let max = gd
+                    .operators
+                    .get(&entry.operator_id)
+                    .map_or(5, |op| op.max_potential_level as i16);
+                (entry.operator_id, (entry.potential, max))
+            })
+            .collect()
+    });
+
+    let tags = parse_tags(&gd.gacha.gacha_tags, &params.tags)?;
+    let pool = recruitable_pool(&gd);
+    let options = SolveOptions {
+        include_robots: params.include_robots.unwrap_or(true),
+        include_two_stars: params.include_two_stars.unwrap_or(true),
+        include_three_stars: params.include_three_stars.unwrap_or(true),
+    };
+    let results = solve(&pool, &tags, options)
+        .iter()
+        .map(|combo| to_result(combo, holdings.as_ref()))
+        .collect();
+
+    Ok(RecruitSolution {
+        server: server.as_str(),
+        roster_aware: holdings.is_some(),
+        tags,
+        results,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(tag_id: i32, tag_name: &str) -> GachaTag {
+        GachaTag {
+            tag_id,
+            tag_name: tag_name.to_owned(),
+            tag_group: 0,
+        }
+    }
+
+    #[test]
+    fn tags_parse_by_id_or_name() {
+        let all = [tag(2, "Sniper"), tag(11, "Top Operator"), tag(1001, "DPS")];
+        let tags = parse_tags(&all, "top operator, 2,dps,2").unwrap();
+        let ids: Vec<i32> = tags.iter().map(|t| t.tag_id).collect();
+        assert_eq!(ids, [11, 2, 1001]);
+        assert!(parse_tags(&all, "Nuker").is_err());
+        assert!(parse_tags(&all, " , ").is_err());
+    }
+
+    #[test]
+    fn ownership_flags() {
+        let holdings: Holdings = [("a".to_owned(), (2, 5)), ("b".to_owned(), (5, 5))].into();
+        assert_eq!(
+            status_of(&holdings, "a"),
+            (OwnershipStatus::Potential, Some(2))
+        );
+        assert_eq!(status_of(&holdings, "b"), (OwnershipStatus::Maxed, Some(5)));
+        assert_eq!(status_of(&holdings, "c"), (OwnershipStatus::New, None));
+    }
+}
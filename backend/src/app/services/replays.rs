@@ -0,0 +1,231 @@
+//! Auto-deploy replay archive: opt-in harvesting during `/refresh` and the
+//! list / viewer endpoints over `user_battle_replays`.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::app::error::ApiError;
+use crate::app::services::game_session;
+use crate::app::services::level::get_stage_map;
+use crate::app::state::AppState;
+use crate::core::battle_replay::{ReplayAction, ReplayRecord, align_actions};
+use crate::core::hypergryph::constants::{AuthSession, Server};
+use crate::core::hypergryph::yostar::{
+    ReplayHarvestOptions, ReplayOutcome, harvest_replays, saved_replay_targets,
+};
+use crate::database::models::replay::ReplaySummary;
+use crate::database::queries::replays::{
+    archived_stage_ids, get_replay, list_replays, upsert_replays,
+};
+use crate::database::queries::users::find_by_id;
+
+/// Which saved replays a refresh fetches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplayHarvest {
+    /// Only stages not archived yet.
+    New,
+    /// Every saved replay, picking up re-recorded ones.
+    All,
+}
+
+#[derive(Deserialize)]
+pub struct RefreshParams {
+    /// Harvest replays after the sync; off unless given.
+    pub replays: Option<ReplayHarvest>,
+}
+
+/// Most saved replays one refresh queues; with `new`, the rest are picked up
+/// by later refreshes.
+const MAX_PER_REFRESH: usize = 200;
+/// Replays fetched between archive writes, so an interrupted harvest keeps
+/// what it already fetched.
+const UPSERT_CHUNK: usize = 25;
+
+/// What a refresh queued; the harvest itself runs in the background.
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarvestQueued {
+    /// Saved replays the background harvest will request.
+    pub queued: usize,
+    /// Saved replays past the per-refresh cap, left for a later refresh.
+    pub deferred: usize,
+}
+
+#[derive(Debug, Default)]
+struct HarvestSummary {
+    requested: usize,
+    stored: usize,
+    failed: usize,
+}
+
+/// Queue the replays `sync` (a raw `account/syncData`) flags as saved and
+/// harvest them on a background task, which persists `session` itself once
+/// it's done with it. The archive is secondary to the sync, so failures are
+/// logged, not returned.
+pub async fn start_harvest(
+    state: &AppState,
+    user_id: Uuid,
+    uid: &str,
+    session: AuthSession,
+    server: Server,
+    sync: &serde_json::Value,
+    mode: ReplayHarvest,
+) -> HarvestQueued {
+    let mut targets = saved_replay_targets(sync);
+    if mode == ReplayHarvest::New {
+        match archived_stage_ids(&state.db, user_id).await {
+            Ok(archived) => targets.retain(|(_, stage_id)| !archived.contains(stage_id)),
+            Err(e) => {
+                tracing::warn!(%user_id, error = ?e, "failed to read archived replays");
+                return HarvestQueued::default();
+            }
+        }
+    }
+
+    let deferred = targets.len().saturating_sub(MAX_PER_REFRESH);
+    targets.truncate(MAX_PER_REFRESH);
+    let queued = HarvestQueued {
+        queued: targets.len(),
+        deferred,
+    };
+    if targets.is_empty() {
+        return queued;
+    }
+
+    let state = state.clone();
+    let uid = uid.to_owned();
+    tokio::spawn(async move {
+        let summary = harvest(&state, user_id, &uid, session, server, &targets).await;
+        tracing::info!(
+            %user_id,
+            requested = summary.requested,
+            stored = summary.stored,
+            failed = summary.failed,
+            "replay harvest finished"
+        );
+    });
+    queued
+}
+
+/// Fetch `targets` sequentially on `session`, archiving every
+/// [`UPSERT_CHUNK`] replays and saving the session after each chunk. A run
+/// that hits the error limit still keeps what it fetched.
+async fn harvest(
+    state: &AppState,
+    user_id: Uuid,
+    uid: &str,
+    mut session: AuthSession,
+    server: Server,
+    targets: &[(String, String)],
+) -> HarvestSummary {
+    let mut summary = HarvestSummary {
+        requested: targets.len(),
+        ..HarvestSummary::default()
+    };
+    let opts = ReplayHarvestOptions::default();
+    for chunk in targets.chunks(UPSERT_CHUNK) {
+        let mut records: Vec<ReplayRecord> = Vec::new();
+        let result = harvest_replays(
+            &state.http_client,
+            &mut session,
+            server,
+            chunk.iter().cloned(),
+            &opts,
+            |battle_type, stage_id, outcome| match outcome {
+                Ok(ReplayOutcome::Replay(replay)) => {
+                    records.push(ReplayRecord::from_replay(battle_type, &replay));
+                }
+                Ok(ReplayOutcome::NoSaved) => {}
+                Err(e) => {
+                    summary.failed += 1;
+                    tracing::debug!(%user_id, stage_id, error = ?e, "getBattleReplay failed");
+                }
+            },
+        )
+        .await;
+        game_session::save(state, uid, &session).await;
+
+        match upsert_replays(&state.db, user_id, &records).await {
+            Ok(()) => summary.stored += records.len(),
+            Err(e) => tracing::warn!(%user_id, error = ?e, "failed to archive replays"),
+        }
+        if let Err(e) = result {
+            tracing::warn!(%user_id, error = ?e, "replay harvest stopped early");
+            break;
+        }
+    }
+    summary
+}
+
+#[derive(Deserialize)]
+pub struct ReplayParams {
+    pub uid: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayListEntry {
+    #[serde(flatten)]
+    pub summary: ReplaySummary,
+    pub code: Option<String>,
+    pub name: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayView {
+    #[serde(flatten)]
+    pub record: ReplayRecord,
+    pub code: Option<String>,
+    pub name: Option<String>,
+    /// The log on the stage viewer grid (`x` = column, `y` = row from the
+    /// top); `None` when the stage has no level file, leaving only `log`.
+    pub actions: Option<Vec<ReplayAction>>,
+}
+
+async fn user_server(state: &AppState, user_id: Uuid) -> Result<Server, ApiError> {
+    let profile = find_by_id(&state.db, user_id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    Ok(Server::parse(&profile.server).unwrap_or(state.default_server))
+}
+
+pub async fn list(state: &AppState, user_id: Uuid) -> Result<Vec<ReplayListEntry>, ApiError> {
+    let gd = state.game_data(user_server(state, user_id).await?);
+    let rows = list_replays(&state.db, user_id).await?;
+    Ok(rows
+        .into_iter()
+        .map(|summary| {
+            let stage = gd.stages.get(&summary.stage_id);
+            ReplayListEntry {
+                code: stage.map(|s| s.code.clone()),
+                name: stage.and_then(|s| s.name.clone()),
+                summary,
+            }
+        })
+        .collect())
+}
+
+pub async fn view(state: &AppState, user_id: Uuid, stage_id: &str) -> Result<ReplayView, ApiError> {
+    let server = user_server(state, user_id).await?;
+    let blob = get_replay(&state.db, user_id, stage_id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let record: ReplayRecord =
+        serde_json::from_value(blob).map_err(|e| ApiError::Internal(e.into()))?;
+
+    let gd = state.game_data(server);
+    let stage = gd.stages.get(stage_id);
+    let actions = get_stage_map(state, server, stage_id)
+        .await
+        .ok()
+        .map(|map| align_actions(&record.log, map.height));
+
+    Ok(ReplayView {
+        code: stage.map(|s| s.code.clone()),
+        name: stage.and_then(|s| s.name.clone()),
+        actions,
+        record,
+    })
+}
@@ -1,11 +1,19 @@
 use chrono::Utc;
 use serde::Deserialize;
 
-use crate::database::queries::roster::sync_user_data;
+use crate::database::queries::roster::{get_roster, sync_user_data};
 use crate::database::queries::score::update_score;
 use crate::database::queries::users::find_by_uid;
 use crate::{
-    app::{error::ApiError, services::game_session, state::AppState},
+    app::{
+        error::ApiError,
+        services::{
+            game_session,
+            replays::{self, ReplayHarvest},
+            roster_history,
+        },
+        state::AppState,
+    },
     core::{
         gamedata::types::campaign::CampaignRotations,
         grade::calculate::calculate_user_grade,
@@ -84,6 +92,8 @@ pub struct PlayerStatus {
     pub hgg_shard: Option<i64>,
     pub lgg_shard: Option<i64>,
     pub practice_ticket: Option<i64>,
+    pub android_diamond: Option<i64>,
+    pub ios_diamond: Option<i64>,
     #[serde(rename = "monthlySubscriptionEndTime")]
     pub monthly_sub_end: Option<i64>,
     pub register_ts: Option<i64>,
@@ -194,10 +204,14 @@ pub struct CheckIn {
     pub can_check_in: Option<i64>,
 }
 
+/// Sync the account; with `replays`, also queue a background archive of its
+/// saved auto-deploy replays and report what was queued under `replayHarvest`
+/// in the returned body.
 pub async fn refresh(
     state: &AppState,
     user_id: &str,
     server: Server,
+    replays: Option<ReplayHarvest>,
 ) -> Result<serde_json::Value, ApiError> {
     let mut session = game_session::ensure_fresh(state, user_id, server).await?;
 
@@ -219,7 +233,7 @@ pub async fn refresh(
     let data: SyncDataResponse =
         serde_json::from_str(&text).map_err(|e| ApiError::Internal(e.into()))?;
 
-    let raw: serde_json::Value =
+    let mut raw: serde_json::Value =
         serde_json::from_str(&text).map_err(|e| ApiError::Internal(e.into()))?;
 
     let user = data
@@ -271,6 +285,12 @@ pub async fn refresh(
         .cloned()
         .unwrap_or_else(|| serde_json::json!({}));
 
+    // Read before the sync overwrites it, so the history can diff against it.
+    let before = match find_by_uid(&state.db, user_id).await? {
+        Some(existing) => Some(get_roster(&state.db, existing.id).await?),
+        None => None,
+    };
+
     sync_user_data(
         &state.db,
         user_id,
@@ -300,6 +320,8 @@ pub async fn refresh(
     .await?;
 
     if let Some(user) = find_by_uid(&state.db, user_id).await? {
+        roster_history::record_sync(state, user.id, before.as_deref()).await;
+
         let grade = calculate_user_grade(&state.db, user.id, &state.default_game_data()).await?;
 
         update_score(
@@ -319,6 +341,14 @@ pub async fn refresh(
             },
         )
         .await?;
+
+        if let Some(mode) = replays {
+            let queued =
+                replays::start_harvest(state, user.id, user_id, session, server, &raw, mode).await;
+            if let Some(body) = raw.as_object_mut() {
+                body.insert("replayHarvest".into(), serde_json::json!(queued));
+            }
+        }
     }
 
     Ok(raw)
@@ -557,6 +587,9 @@ fn extract_status(status: Option<&PlayerStatus>) -> serde_json::Value {
         "main_stage_progress": s.main_stage_progress.as_deref().unwrap_or(""),
         "resume": s.resume.as_deref().unwrap_or(""),
         "friend_num_limit": s.friend_num_limit.unwrap_or(0),
+        // Android and iOS keep separate paid wallets over shared free
+        // originite; whichever is larger is the one the player plays on.
+        "originite": s.android_diamond.unwrap_or(0).max(s.ios_diamond.unwrap_or(0)),
     })
 }
 
@@ -0,0 +1,828 @@
+//! DPS over a user's synced roster: every owned operator with a DPS formula,
+//! evaluated at the investment the user actually has, and what each next
+//! investment step would add per unit of cost.
+
+use std::collections::HashMap;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::app::error::ApiError;
+use crate::app::services::dps::{
+    EnemyProfile, advanced_modules_sorted, enemy_stats, resolve_enemy,
+};
+use crate::app::services::planner::{
+    add_mastery_costs, add_module_costs, add_promotion_costs, add_skill_level_costs,
+    calculate_leveling_costs,
+};
+use crate::app::state::AppState;
+use crate::core::gamedata::types::GameData;
+use crate::core::gamedata::types::material::ItemRarity;
+use crate::core::gamedata::types::operator::{Operator, OperatorProfession};
+use crate::database::models::roster::RosterEntry;
+use crate::database::queries::roster::get_roster;
+use crate::dps::engine::{DpsResult, OperatorFormula, calculate_dps, get_formula};
+use crate::dps::operator_unit::{EnemyStats, OperatorParams};
+
+#[derive(Deserialize)]
+pub(crate) struct RosterMastery {
+    pub index: i16,
+    pub mastery: i16,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct RosterModule {
+    pub id: String,
+    pub level: i16,
+    #[serde(default)]
+    pub locked: bool,
+}
+
+/// Which DPS figure a ranking sorts on.
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RankBy {
+    #[default]
+    Skill,
+    Average,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RosterDpsRequest {
+    #[serde(flatten)]
+    pub enemy: EnemyProfile,
+    pub targets: Option<i32>,
+    /// Only rank these classes (e.g. `["CASTER"]`).
+    pub professions: Option<Vec<OperatorProfession>>,
+    #[serde(default)]
+    pub rank_by: RankBy,
+    /// Keep the top N rows (after sorting).
+    pub limit: Option<usize>,
+}
+
+/// One operator x skill row of the ranking.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RosterDpsEntry {
+    pub operator_id: String,
+    pub name: String,
+    pub rarity: i16,
+    pub profession: OperatorProfession,
+    /// 1-based, as everywhere else in the DPS API.
+    pub skill_index: i32,
+    pub elite: i16,
+    pub level: i16,
+    /// 1-6.
+    pub potential: i32,
+    /// Skill level 1-7, or 7 once mastered.
+    pub skill_level: i16,
+    pub mastery: i16,
+    /// Module the row was evaluated with - the user's best unlocked one for
+    /// this skill, or `None` if basic attacks do better / none are unlocked.
+    pub module_id: Option<String>,
+    pub module_level: i16,
+    /// `None` when the skill isn't unlocked yet.
+    pub dps: Option<DpsResult>,
+    /// What's holding this row back, e.g. "not E2", "S3 at M0".
+    pub limits: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RosterDpsRanking {
+    pub defense: f64,
+    pub res: f64,
+    pub entries: Vec<RosterDpsEntry>,
+}
+
+pub async fn rank_roster(
+    state: &AppState,
+    user_id: Uuid,
+    req: RosterDpsRequest,
+) -> Result<RosterDpsRanking, ApiError> {
+    let resolved = resolve_enemy(state, &req.enemy).await?;
+    let enemy = enemy_stats(&req.enemy, resolved.as_ref());
+    let roster = get_roster(&state.db, user_id).await?;
+    let gd = state.default_game_data();
+    let (defense, res) = (enemy.defense, enemy.res);
+
+    // Every owned operator x skill through the engine; keep it off the runtime.
+    let entries = tokio::task::spawn_blocking(move || {
+        let mut entries: Vec<RosterDpsEntry> = roster
+            .par_iter()
+            .filter_map(|entry| {
+                let operator = gd.operators.get(&entry.operator_id)?;
+                let formula = get_formula(&entry.operator_id)?;
+                let wanted = req
+                    .professions
+                    .as_ref()
+                    .is_none_or(|p| p.contains(&operator.profession));
+                wanted.then(|| rank_operator(&gd, operator, formula, entry, &enemy, &req))
+            })
+            .flatten()
+            .collect();
+
+        let key = |e: &RosterDpsEntry| {
+            e.dps
+                .as_ref()
+                .map_or(f64::NEG_INFINITY, |d| rank_value(d, req.rank_by))
+        };
+        entries.sort_by(|a, b| key(b).total_cmp(&key(a)));
+        if let Some(limit) = req.limit {
+            entries.truncate(limit);
+        }
+        entries
+    })
+    .await
+    .map_err(|e| ApiError::Internal(e.into()))?;
+
+    Ok(RosterDpsRanking {
+        defense,
+        res,
+        entries,
+    })
+}
+
+/// The user's investment in one operator, decoded from the roster row.
+#[derive(Clone)]
+pub(crate) struct Investment {
+    pub elite: i16,
+    pub level: i16,
+    /// 1-6, the engine's convention.
+    pub potential: i32,
+    pub trust: i32,
+    pub skill_level: i16,
+    /// Mastery per skill, by 0-based skill index.
+    pub masteries: HashMap<i16, i16>,
+    /// Unlocked module levels by module id.
+    pub modules: HashMap<String, i16>,
+}
+
+impl Investment {
+    pub(crate) fn from_roster(gd: &GameData, entry: &RosterEntry) -> Self {
+        let masteries: Vec<RosterMastery> =
+            serde_json::from_value(entry.masteries.clone()).unwrap_or_default();
+        let modules: Vec<RosterModule> =
+            serde_json::from_value(entry.modules.clone()).unwrap_or_default();
+        Self {
+            elite: entry.elite,
+            level: entry.level,
+            potential: i32::from(entry.potential) + 1,
+            trust: gd.favor.trust_pct(entry.favor_point).min(100.0) as i32,
+            skill_level: entry.skill_level,
+            masteries: masteries
+                .into_iter()
+                .map(|m| (m.index, m.mastery))
+                .collect(),
+            modules: modules
+                .into_iter()
+                .filter(|m| !m.locked && m.level > 0)
+                .map(|m| (m.id, m.level))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn mastery(&self, skill_index: i32) -> i16 {
+        self.masteries
+            .get(&((skill_index - 1) as i16))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Engine params for `skill_index` (1-based) with formula module `module`
+    /// (0 = none) at `module_level`.
+    pub(crate) fn params(
+        &self,
+        skill_index: i32,
+        module: i32,
+        module_level: i16,
+        targets: Option<i32>,
+    ) -> OperatorParams {
+        let mastery = self.mastery(skill_index);
+        OperatorParams {
+            promotion: Some(i32::from(self.elite)),
+            level: Some(i32::from(self.level)),
+            potential: Some(self.potential),
+            trust: Some(self.trust),
+            skill_index: Some(skill_index),
+            mastery_level: Some(i32::from(mastery)),
+            // An explicit skill level wins over mastery, so only pass it
+            // while the skill is unmastered.
+            skill_level: (mastery == 0).then_some(i32::from(self.skill_level)),
+            module_index: Some(module),
+            module_level: Some(i32::from(module_level)),
+            targets,
+            ..Default::default()
+        }
+    }
+}
+
+/// The formula's modules the user has unlocked: `(formula value, module id,
+/// level)`.
+pub(crate) fn owned_modules(
+    operator: &Operator,
+    formula: &OperatorFormula,
+    investment: &Investment,
+) -> Vec<(i32, String, i16)> {
+    let sorted = advanced_modules_sorted(operator);
+    formula
+        .available_modules
+        .iter()
+        .enumerate()
+        .filter_map(|(pos, &value)| {
+            let id = sorted.get(pos)?.module.id.clone()?;
+            let level = *investment.modules.get(&id)?;
+            Some((value, id, level))
+        })
+        .collect()
+}
+
+/// Elite phase a 1-based skill slot unlocks at.
+pub(crate) const fn skill_unlock_elite(skill_index: i32) -> i16 {
+    match skill_index {
+        1 => 0,
+        2 => 1,
+        _ => 2,
+    }
+}
+
+fn rank_operator(
+    gd: &GameData,
+    operator: &Operator,
+    formula: &OperatorFormula,
+    entry: &RosterEntry,
+    enemy: &EnemyStats,
+    req: &RosterDpsRequest,
+) -> Vec<RosterDpsEntry> {
+    let investment = Investment::from_roster(gd, entry);
+    let max_elite = operator.phases.len().saturating_sub(1) as i16;
+    let max_level = operator
+        .phases
+        .get(investment.elite as usize)
+        .map_or(0, |p| p.max_level) as i16;
+
+    formula
+        .available_skills
+        .iter()
+        .copied()
+        .filter(|&s| s >= 1 && s as usize <= operator.skills.len())
+        .map(|skill_index| {
+            let unlocked = investment.elite >= skill_unlock_elite(skill_index);
+
+            let best = if unlocked {
+                best_module_dps(
+                    operator,
+                    formula,
+                    &investment,
+                    skill_index,
+                    req.targets,
+                    enemy,
+                    req.rank_by,
+                )
+            } else {
+                None
+            };
+            let (dps, module_id, module_level) = match best {
+                Some((dps, id, level)) => (Some(dps), id, level),
+                None => (None, None, 0),
+            };
+
+            let limits = limiting_factors(&Limits {
+                skill_index,
+                elite: investment.elite,
+                max_elite,
+                level: investment.level,
+                max_level,
+                skill_level: investment.skill_level,
+                mastery: investment.mastery(skill_index),
+                has_modules: !formula.available_modules.is_empty(),
+                module_level: module_id.as_ref().map(|_| module_level),
+            });
+
+            RosterDpsEntry {
+                operator_id: entry.operator_id.clone(),
+                name: operator.name.clone(),
+                rarity: operator.rarity.to_star_int(),
+                profession: operator.profession.clone(),
+                skill_index,
+                elite: investment.elite,
+                level: investment.level,
+                potential: investment.potential,
+                skill_level: investment.skill_level,
+                mastery: investment.mastery(skill_index),
+                module_id,
+                module_level,
+                dps,
+                limits,
+            }
+        })
+        .collect()
+}
+
+/// DPS for `skill_index` with no module and with each of the user's unlocked
+/// ones, keeping the best: `(dps, module id, module level)`.
+fn best_module_dps(
+    operator: &Operator,
+    formula: &OperatorFormula,
+    investment: &Investment,
+    skill_index: i32,
+    targets: Option<i32>,
+    enemy: &EnemyStats,
+    rank_by: RankBy,
+) -> Option<(DpsResult, Option<String>, i16)> {
+    let modules = owned_modules(operator, formula, investment);
+    let candidates =
+        std::iter::once((0, None, 0)).chain(modules.into_iter().map(|(v, id, l)| (v, Some(id), l)));
+
+    let mut best: Option<(DpsResult, Option<String>, i16)> = None;
+    for (value, id, level) in candidates {
+        let params = investment.params(skill_index, value, level, targets);
+        let Some(dps) = calculate_dps(operator, params, enemy) else {
+            continue;
+        };
+        let better = best
+            .as_ref()
+            .is_none_or(|(b, ..)| rank_value(&dps, rank_by) > rank_value(b, rank_by));
+        if better {
+            best = Some((dps, id, level));
+        }
+    }
+    best
+}
+
+const fn rank_value(dps: &DpsResult, rank_by: RankBy) -> f64 {
+    match rank_by {
+        RankBy::Skill => dps.skill_dps,
+        RankBy::Average => dps.average_dps,
+    }
+}
+
+struct Limits {
+    skill_index: i32,
+    elite: i16,
+    max_elite: i16,
+    level: i16,
+    max_level: i16,
+    skill_level: i16,
+    mastery: i16,
+    /// Whether the formula models any module for this operator.
+    has_modules: bool,
+    /// Level of the module the row used, `None` if it used none.
+    module_level: Option<i16>,
+}
+
+/// Human-readable reasons a row isn't at its ceiling, most significant first.
+fn limiting_factors(l: &Limits) -> Vec<String> {
+    let mut limits = Vec::new();
+    let unlock = skill_unlock_elite(l.skill_index);
+    if l.elite < unlock {
+        limits.push(format!("S{} locked (needs E{unlock})", l.skill_index));
+        return limits;
+    }
+    if l.elite < l.max_elite {
+        limits.push(format!("not E{}", l.max_elite));
+    }
+    if l.level < l.max_level {
+        limits.push(format!("E{} Lv{}/{}", l.elite, l.level, l.max_level));
+    }
+    if l.skill_level < 7 {
+        limits.push(format!("SL{}", l.skill_level));
+    } else if l.max_elite == 2 && l.mastery < 3 {
+        limits.push(format!("S{} at M{}", l.skill_index, l.mastery));
+    }
+    if l.has_modules && l.elite == 2 {
+        match l.module_level {
+            None => limits.push("no module".to_owned()),
+            Some(level) if level < 3 => limits.push(format!("module stage {level}")),
+            Some(_) => {}
+        }
+    }
+    limits
+}
+
+/// What gets valued when ranking upgrade steps by cost.
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CostBasis {
+    #[default]
+    Sanity,
+    Lmd,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpgradeValueRequest {
+    #[serde(flatten)]
+    pub enemy: EnemyProfile,
+    pub targets: Option<i32>,
+    pub professions: Option<Vec<OperatorProfession>>,
+    /// DPS figure the gain is measured in.
+    #[serde(default)]
+    pub rank_by: RankBy,
+    #[serde(default)]
+    pub sort_by: CostBasis,
+    /// Sanity value per item id, overriding the rarity-based defaults.
+    pub item_values: Option<HashMap<String, f64>>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum UpgradeKind {
+    Promotion,
+    SkillLevel,
+    Mastery,
+    Module,
+    Potential,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemCount {
+    pub item_id: String,
+    pub count: i32,
+}
+
+/// One next investment step for one operator.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpgradeStep {
+    pub operator_id: String,
+    pub name: String,
+    pub kind: UpgradeKind,
+    /// e.g. "E2", "SL7", "S3 M2", "X stage 1", "P4".
+    pub label: String,
+    /// Skill the gain is measured on: the mastered skill for `mastery`, the
+    /// best skill after the step otherwise.
+    pub skill_index: i32,
+    pub dps_before: f64,
+    pub dps_after: f64,
+    pub dps_gain: f64,
+    /// Everything the step costs, LMD (`4001`) and EXP (`5001`) included.
+    pub materials: Vec<ItemCount>,
+    pub lmd: i32,
+    /// Sanity-equivalent of `materials`, LMD and EXP included.
+    pub sanity: f64,
+    pub gain_per_sanity: Option<f64>,
+    pub gain_per_lmd: Option<f64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpgradeValueResult {
+    pub defense: f64,
+    pub res: f64,
+    pub steps: Vec<UpgradeStep>,
+}
+
+/// CE-6 / LS-6: 36 sanity for 10k LMD or 10k EXP.
+const SANITY_PER_LMD: f64 = 36.0 / 10_000.0;
+const SANITY_PER_EXP: f64 = 36.0 / 10_000.0;
+
+/// Rough sanity value of one item by rarity - a stand-in for real drop-rate
+/// valuation, which callers can supply through `itemValues`. Potential tokens
+/// aren't farmable and default to 0.
+fn default_item_value(gd: &GameData, item_id: &str) -> f64 {
+    match item_id {
+        "4001" => SANITY_PER_LMD,
+        "5001" => SANITY_PER_EXP,
+        id if id.starts_with("p_char_") => 0.0,
+        id => gd
+            .materials
+            .items
+            .get(id)
+            .map_or(0.0, |item| match item.rarity {
+                ItemRarity::Tier1 => 1.0,
+                ItemRarity::Tier2 => 3.0,
+                ItemRarity::Tier3 => 12.0,
+                ItemRarity::Tier4 => 45.0,
+                ItemRarity::Tier5 => 100.0,
+                ItemRarity::Tier6 => 0.0,
+            }),
+    }
+}
+
+pub async fn upgrade_values(
+    state: &AppState,
+    user_id: Uuid,
+    req: UpgradeValueRequest,
+) -> Result<UpgradeValueResult, ApiError> {
+    let resolved = resolve_enemy(state, &req.enemy).await?;
+    let enemy = enemy_stats(&req.enemy, resolved.as_ref());
+    let roster = get_roster(&state.db, user_id).await?;
+    let gd = state.default_game_data();
+    let (defense, res) = (enemy.defense, enemy.res);
+
+    // Two engine runs per candidate step across the roster.
+    let steps = tokio::task::spawn_blocking(move || {
+        let mut steps: Vec<UpgradeStep> = roster
+            .par_iter()
+            .filter_map(|entry| {
+                let operator = gd.operators.get(&entry.operator_id)?;
+                let formula = get_formula(&entry.operator_id)?;
+                let wanted = req
+                    .professions
+                    .as_ref()
+                    .is_none_or(|p| p.contains(&operator.profession));
+                wanted.then(|| operator_steps(&gd, operator, formula, entry, &enemy, &req))
+            })
+            .flatten()
+            .collect();
+
+        let key = |s: &UpgradeStep| {
+            match req.sort_by {
+                CostBasis::Sanity => s.gain_per_sanity,
+                CostBasis::Lmd => s.gain_per_lmd,
+            }
+            .unwrap_or(f64::NEG_INFINITY)
+        };
+        steps.sort_by(|a, b| key(b).total_cmp(&key(a)));
+        if let Some(limit) = req.limit {
+            steps.truncate(limit);
+        }
+        steps
+    })
+    .await
+    .map_err(|e| ApiError::Internal(e.into()))?;
+
+    Ok(UpgradeValueResult {
+        defense,
+        res,
+        steps,
+    })
+}
+
+/// A candidate step before it's evaluated.
+struct Candidate {
+    kind: UpgradeKind,
+    label: String,
+    /// `Some` pins the evaluation to one skill.
+    skill: Option<i32>,
+    next: Investment,
+    materials: HashMap<String, i32>,
+}
+
+fn candidates(
+    gd: &GameData,
+    operator: &Operator,
+    formula: &OperatorFormula,
+    inv: &Investment,
+    skills: &[i32],
+) -> Vec<Candidate> {
+    let mut out = Vec::new();
+    let max_elite = operator.phases.len().saturating_sub(1) as i16;
+
+    if inv.elite < max_elite {
+        let elite = inv.elite + 1;
+        let level = operator.phases[elite as usize].max_level as i16;
+        let mut materials = HashMap::new();
+        calculate_leveling_costs(
+            operator,
+            gd,
+            inv.elite,
+            inv.level,
+            elite,
+            level,
+            &mut materials,
+        );
+        add_promotion_costs(operator, inv.elite, elite, &mut materials);
+        let mut next = inv.clone();
+        next.elite = elite;
+        next.level = level;
+        out.push(Candidate {
+            kind: UpgradeKind::Promotion,
+            label: format!("E{elite}"),
+            skill: None,
+            next,
+            materials,
+        });
+    }
+
+    // Skill levels 5-7 need E1.
+    if inv.skill_level < 7 && (inv.skill_level < 4 || inv.elite >= 1) {
+        let mut materials = HashMap::new();
+        add_skill_level_costs(
+            operator,
+            inv.skill_level,
+            inv.skill_level + 1,
+            &mut materials,
+        );
+        let mut next = inv.clone();
+        next.skill_level += 1;
+        out.push(Candidate {
+            kind: UpgradeKind::SkillLevel,
+            label: format!("SL{}", next.skill_level),
+            skill: None,
+            next,
+            materials,
+        });
+    }
+
+    if inv.elite == 2 && inv.skill_level == 7 {
+        for &skill in skills {
+            let mastery = inv.mastery(skill);
+            if mastery >= 3 {
+                continue;
+            }
+            let slot = (skill - 1) as i16;
+            let mut materials = HashMap::new();
+            add_mastery_costs(operator, slot, mastery, mastery + 1, &mut materials);
+            let mut next = inv.clone();
+            next.masteries.insert(slot, mastery + 1);
+            out.push(Candidate {
+                kind: UpgradeKind::Mastery,
+                label: format!("S{skill} M{}", mastery + 1),
+                skill: Some(skill),
+                next,
+                materials,
+            });
+        }
+
+        let sorted = advanced_modules_sorted(operator);
+        for module in sorted.iter().take(formula.available_modules.len()) {
+            let id = &module.module.uni_equip_id;
+            let stage = inv.modules.get(id).copied().unwrap_or(0);
+            if stage >= 3 {
+                continue;
+            }
+            let mut materials = HashMap::new();
+            add_module_costs(operator, id, stage, stage + 1, &mut materials);
+            let mut next = inv.clone();
+            next.modules.insert(id.clone(), stage + 1);
+            out.push(Candidate {
+                kind: UpgradeKind::Module,
+                label: format!(
+                    "{} stage {}",
+                    module.module.type_icon.to_uppercase(),
+                    stage + 1
+                ),
+                skill: None,
+                next,
+                materials,
+            });
+        }
+    }
+
+    if inv.potential < 6 {
+        let mut next = inv.clone();
+        next.potential += 1;
+        out.push(Candidate {
+            kind: UpgradeKind::Potential,
+            label: format!("P{}", next.potential),
+            skill: None,
+            next,
+            materials: HashMap::from([(operator.potential_item_id.clone(), 1)]),
+        });
+    }
+
+    out
+}
+
+fn operator_steps(
+    gd: &GameData,
+    operator: &Operator,
+    formula: &OperatorFormula,
+    entry: &RosterEntry,
+    enemy: &EnemyStats,
+    req: &UpgradeValueRequest,
+) -> Vec<UpgradeStep> {
+    let inv = Investment::from_roster(gd, entry);
+    let skills: Vec<i32> = formula
+        .available_skills
+        .iter()
+        .copied()
+        .filter(|&s| s >= 1 && s as usize <= operator.skills.len())
+        .collect();
+
+    let on_skill = |inv: &Investment, skill: i32| -> Option<f64> {
+        if inv.elite < skill_unlock_elite(skill) {
+            return None;
+        }
+        best_module_dps(
+            operator,
+            formula,
+            inv,
+            skill,
+            req.targets,
+            enemy,
+            req.rank_by,
+        )
+        .map(|(dps, ..)| rank_value(&dps, req.rank_by))
+    };
+    let best_skill = |inv: &Investment| -> Option<(i32, f64)> {
+        skills
+            .iter()
+            .filter_map(|&s| on_skill(inv, s).map(|v| (s, v)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    };
+
+    let current_best = best_skill(&inv);
+    let current_by_skill: HashMap<i32, f64> = skills
+        .iter()
+        .filter_map(|&s| on_skill(&inv, s).map(|v| (s, v)))
+        .collect();
+
+    candidates(gd, operator, formula, &inv, &skills)
+        .into_iter()
+        .filter_map(|c| {
+            let (skill_index, before, after) = match c.skill {
+                Some(skill) => (
+                    skill,
+                    current_by_skill.get(&skill).copied()?,
+                    on_skill(&c.next, skill)?,
+                ),
+                None => {
+                    let (skill, after) = best_skill(&c.next)?;
+                    (skill, current_best.map_or(0.0, |(_, v)| v), after)
+                }
+            };
+            let gain = after - before;
+            if gain <= 1e-6 {
+                return None;
+            }
+
+            let item_value = |id: &str| {
+                req.item_values
+                    .as_ref()
+                    .and_then(|v| v.get(id).copied())
+                    .unwrap_or_else(|| default_item_value(gd, id))
+            };
+            let sanity: f64 = c
+                .materials
+                .iter()
+                .map(|(id, &count)| item_value(id) * f64::from(count))
+                .sum();
+            let lmd = c.materials.get("4001").copied().unwrap_or(0);
+
+            let mut materials: Vec<ItemCount> = c
+                .materials
+                .into_iter()
+                .map(|(item_id, count)| ItemCount { item_id, count })
+                .collect();
+            materials.sort_by(|a, b| a.item_id.cmp(&b.item_id));
+
+            Some(UpgradeStep {
+                operator_id: entry.operator_id.clone(),
+                name: operator.name.clone(),
+                kind: c.kind,
+                label: c.label,
+                skill_index,
+                dps_before: before,
+                dps_after: after,
+                dps_gain: gain,
+                materials,
+                lmd,
+                sanity,
+                gain_per_sanity: (sanity > 0.0).then(|| gain / sanity),
+                gain_per_lmd: (lmd > 0).then(|| gain / f64::from(lmd)),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn maxed() -> Limits {
+        Limits {
+            skill_index: 3,
+            elite: 2,
+            max_elite: 2,
+            level: 90,
+            max_level: 90,
+            skill_level: 7,
+            mastery: 3,
+            has_modules: true,
+            module_level: Some(3),
+        }
+    }
+
+    #[test]
+    fn maxed_operator_has_no_limits() {
+        assert!(limiting_factors(&maxed()).is_empty());
+    }
+
+    #[test]
+    fn locked_skill_reports_only_the_unlock() {
+        let l = Limits {
+            elite: 1,
+            level: 70,
+            max_level: 70,
+            ..maxed()
+        };
+        assert_eq!(limiting_factors(&l), vec!["S3 locked (needs E2)"]);
+    }
+
+    #[test]
+    fn unmastered_and_unmoduled_skill_is_annotated() {
+        let l = Limits {
+            mastery: 0,
+            module_level: None,
+            ..maxed()
+        };
+        assert_eq!(limiting_factors(&l), vec!["S3 at M0", "no module"]);
+    }
+}
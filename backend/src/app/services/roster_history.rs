@@ -0,0 +1,353 @@
+//! Roster investment history: what changed between two syncs, recorded on
+//! every refresh, plus the browse/monthly-progress views over it.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, Months, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::app::error::ApiError;
+use crate::app::services::roster_dps::{RosterMastery, RosterModule};
+use crate::app::state::AppState;
+use crate::database::models::roster::{
+    MonthlyProgress, NewRosterEvent, RosterEntry, RosterEvent, RosterSnapshot,
+};
+use crate::database::queries::roster::{
+    get_roster, insert_roster_history, list_roster_events, list_roster_snapshots,
+    monthly_roster_progress,
+};
+
+pub const EVENT_KINDS: [&str; 7] = [
+    "obtained",
+    "elite",
+    "level",
+    "skill_level",
+    "mastery",
+    "module",
+    "potential",
+];
+
+const DEFAULT_EVENT_LIMIT: i64 = 100;
+const MAX_EVENT_LIMIT: i64 = 500;
+const DEFAULT_PROGRESS_MONTHS: u32 = 12;
+const MAX_PROGRESS_MONTHS: u32 = 36;
+
+fn masteries(entry: &RosterEntry) -> Vec<RosterMastery> {
+    serde_json::from_value(entry.masteries.clone()).unwrap_or_default()
+}
+
+/// Unlocked modules only: a locked module's level is meaningless.
+fn modules(entry: &RosterEntry) -> Vec<RosterModule> {
+    serde_json::from_value::<Vec<RosterModule>>(entry.modules.clone())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|m| !m.locked)
+        .collect()
+}
+
+/// Changes from `before` to `after`. A promotion resets the level, so a level
+/// change is only recorded within the same elite phase; downgrades (which
+/// shouldn't happen) are ignored.
+pub fn diff_rosters(before: &[RosterEntry], after: &[RosterEntry]) -> Vec<NewRosterEvent> {
+    let before: HashMap<&str, &RosterEntry> =
+        before.iter().map(|e| (e.operator_id.as_str(), e)).collect();
+    let mut events = Vec::new();
+    let mut push = |op: &str, kind, target, from: Option<i16>, to: i16| {
+        if from.is_none_or(|f| to > f) {
+            events.push(NewRosterEvent {
+                operator_id: op.to_owned(),
+                kind,
+                target,
+                from_value: from,
+                to_value: to,
+            });
+        }
+    };
+
+    for new in after {
+        let op = new.operator_id.as_str();
+        let Some(old) = before.get(op) else {
+            push(op, "obtained", None, None, new.elite);
+            continue;
+        };
+        push(op, "elite", None, Some(old.elite), new.elite);
+        if old.elite == new.elite {
+            push(op, "level", None, Some(old.level), new.level);
+        }
+        push(
+            op,
+            "skill_level",
+            None,
+            Some(old.skill_level),
+            new.skill_level,
+        );
+        push(op, "potential", None, Some(old.potential), new.potential);
+
+        let old_masteries: HashMap<i16, i16> = masteries(old)
+            .into_iter()
+            .map(|m| (m.index, m.mastery))
+            .collect();
+        for m in masteries(new).into_iter().filter(|m| m.mastery > 0) {
+            let from = old_masteries.get(&m.index).copied().unwrap_or(0);
+            push(
+                op,
+                "mastery",
+                Some(m.index.to_string()),
+                Some(from),
+                m.mastery,
+            );
+        }
+
+        let old_modules: HashMap<String, i16> =
+            modules(old).into_iter().map(|m| (m.id, m.level)).collect();
+        for m in modules(new).into_iter().filter(|m| m.level > 0) {
+            let from = old_modules.get(&m.id).copied();
+            push(op, "module", Some(m.id), from.or(Some(0)), m.level);
+        }
+    }
+    events
+}
+
+pub fn snapshot_of(roster: &[RosterEntry], synced_at: DateTime<Utc>) -> RosterSnapshot {
+    let mut snapshot = RosterSnapshot {
+        synced_at,
+        operators: roster.len() as i32,
+        elite2: 0,
+        masteries: 0,
+        mastery3: 0,
+        modules: 0,
+    };
+    for entry in roster {
+        if entry.elite >= 2 {
+            snapshot.elite2 += 1;
+        }
+        for m in masteries(entry) {
+            snapshot.masteries += i32::from(m.mastery);
+            if m.mastery >= 3 {
+                snapshot.mastery3 += 1;
+            }
+        }
+        snapshot.modules += modules(entry)
+            .iter()
+            .map(|m| i32::from(m.level))
+            .sum::<i32>();
+    }
+    snapshot
+}
+
+/// Record what the sync that just ran changed. `before` is the roster read
+/// before the sync, `None` for an account's first sync. History is secondary
+/// to the sync itself, so failures are logged, not returned.
+pub async fn record_sync(state: &AppState, user_id: Uuid, before: Option<&[RosterEntry]>) {
+    let result = async {
+        let after = get_roster(&state.db, user_id).await?;
+        let events = before.map(|b| diff_rosters(b, &after)).unwrap_or_default();
+        let snapshot = snapshot_of(&after, Utc::now());
+        insert_roster_history(&state.db, user_id, &events, &snapshot).await
+    }
+    .await;
+    if let Err(e) = result {
+        tracing::warn!(%user_id, error = ?e, "failed to record roster history");
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RosterHistoryParams {
+    pub uid: Option<String>,
+    pub operator: Option<String>,
+    /// Comma-separated event kinds.
+    pub kinds: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// Page cursor: the `nextBefore` of the previous page.
+    pub before: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RosterHistoryPage {
+    pub events: Vec<RosterEvent>,
+    /// Cursor for the next (older) page; `None` on the last page.
+    pub next_before: Option<i64>,
+}
+
+pub async fn history(
+    state: &AppState,
+    user_id: Uuid,
+    params: &RosterHistoryParams,
+) -> Result<RosterHistoryPage, ApiError> {
+    let kinds: Vec<String> = params
+        .kinds
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+        .map(str::to_owned)
+        .collect();
+    if let Some(bad) = kinds.iter().find(|k| !EVENT_KINDS.contains(&k.as_str())) {
+        return Err(ApiError::BadRequest(format!("unknown event kind {bad}")));
+    }
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_EVENT_LIMIT)
+        .clamp(1, MAX_EVENT_LIMIT);
+
+    let events = list_roster_events(
+        &state.db,
+        user_id,
+        params.operator.as_deref(),
+        &kinds,
+        params.from,
+        params.to,
+        params.before,
+        limit,
+    )
+    .await?;
+    let next_before = (events.len() as i64 == limit)
+        .then(|| events.last().map(|e| e.id))
+        .flatten();
+
+    Ok(RosterHistoryPage {
+        events,
+        next_before,
+    })
+}
+
+#[derive(Deserialize)]
+pub struct RosterProgressParams {
+    pub uid: Option<String>,
+    pub months: Option<u32>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RosterProgress {
+    /// Months with recorded changes, oldest first.
+    pub months: Vec<MonthlyProgress>,
+    /// Roster totals at every sync in the window, oldest first.
+    pub snapshots: Vec<RosterSnapshot>,
+}
+
+pub async fn progress(
+    state: &AppState,
+    user_id: Uuid,
+    params: &RosterProgressParams,
+) -> Result<RosterProgress, ApiError> {
+    let months = params
+        .months
+        .unwrap_or(DEFAULT_PROGRESS_MONTHS)
+        .clamp(1, MAX_PROGRESS_MONTHS);
+    // Whole calendar months: the current one plus `months - 1` before it.
+    let now = Utc::now();
+    let month_start = now
+        .date_naive()
+        .with_day(1)
+        .and_then(|d| d.checked_sub_months(Months::new(months - 1)))
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map_or(now, |d| d.and_utc());
+
+    let (months, snapshots) = tokio::try_join!(
+        monthly_roster_progress(&state.db, user_id, month_start),
+        list_roster_snapshots(&state.db, user_id, month_start),
+    )?;
+    Ok(RosterProgress { months, snapshots })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(
+        operator_id: &str,
+        elite: i16,
+        level: i16,
+        masteries: serde_json::Value,
+        modules: serde_json::Value,
+    ) -> RosterEntry {
+        RosterEntry {
+            user_id: Uuid::nil(),
+            operator_id: operator_id.to_owned(),
+            elite,
+            level,
+            exp: 0,
+            potential: 0,
+            skill_level: 7,
+            favor_point: 0,
+            skin_id: None,
+            default_skill: None,
+            voice_lan: None,
+            current_equip: None,
+            current_tmpl: None,
+            obtained_at: None,
+            masteries,
+            modules,
+        }
+    }
+
+    fn kinds(events: &[NewRosterEvent]) -> Vec<&str> {
+        events.iter().map(|e| e.kind).collect()
+    }
+
+    #[test]
+    fn new_operator_is_obtained_only() {
+        let after = [entry("char_002_amiya", 1, 40, json!([]), json!([]))];
+        let events = diff_rosters(&[], &after);
+        assert_eq!(kinds(&events), ["obtained"]);
+        assert_eq!(events[0].to_value, 1);
+    }
+
+    #[test]
+    fn promotion_skips_the_level_reset() {
+        let before = [entry("a", 1, 80, json!([]), json!([]))];
+        let after = [entry("a", 2, 1, json!([]), json!([]))];
+        let events = diff_rosters(&before, &after);
+        assert_eq!(kinds(&events), ["elite"]);
+        assert_eq!(events[0].from_value, Some(1));
+    }
+
+    #[test]
+    fn masteries_and_modules_record_per_target() {
+        let before = [entry(
+            "a",
+            2,
+            60,
+            json!([{"index": 0, "mastery": 1}, {"index": 2, "mastery": 0}]),
+            json!([{"id": "uniequip_002_a", "level": 0, "locked": true}]),
+        )];
+        let after = [entry(
+            "a",
+            2,
+            60,
+            json!([{"index": 0, "mastery": 1}, {"index": 2, "mastery": 3}]),
+            json!([{"id": "uniequip_002_a", "level": 2, "locked": false}]),
+        )];
+        let events = diff_rosters(&before, &after);
+        assert_eq!(kinds(&events), ["mastery", "module"]);
+        assert_eq!(events[0].target.as_deref(), Some("2"));
+        assert_eq!((events[0].from_value, events[0].to_value), (Some(0), 3));
+        assert_eq!((events[1].from_value, events[1].to_value), (Some(0), 2));
+    }
+
+    #[test]
+    fn snapshot_counts_investment() {
+        let roster = [
+            entry(
+                "a",
+                2,
+                90,
+                json!([{"index": 0, "mastery": 3}, {"index": 1, "mastery": 2}]),
+                json!([{"id": "m", "level": 3, "locked": false}]),
+            ),
+            entry("b", 1, 55, json!([]), json!([])),
+        ];
+        let s = snapshot_of(&roster, Utc::now());
+        assert_eq!(
+            (s.operators, s.elite2, s.masteries, s.mastery3, s.modules),
+            (2, 1, 5, 1, 3)
+        );
+    }
+}
@@ -2,6 +2,9 @@ use std::hash::{DefaultHasher, Hash, Hasher};
 
 use serde::{Deserialize, Serialize};
 
+use crate::core::gamedata_search::{DEFAULT_LIMIT, MAX_LIMIT, SearchHit, SearchKind};
+use crate::core::hypergryph::constants::Server;
+
 use crate::database::queries::users::count_by_nickname;
 use crate::database::queries::users::search_by_nickname;
 use crate::{
@@ -40,3 +43,49 @@ pub async fn search_users(
     state.cache.set(&key, &page).await;
     Ok(page)
 }
+
+#[derive(Deserialize)]
+pub struct GameDataSearchParams {
+    pub q: Option<String>,
+    /// Comma-separated kinds (`operator,skill,talent,item,enemy,stage,skin`);
+    /// all when omitted.
+    pub kinds: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameDataSearchResponse {
+    pub server: &'static str,
+    pub hits: Vec<SearchHit>,
+}
+
+/// Search a server's game data in its own language. Not cached: the index is
+/// in memory and a lookup is cheaper than a cache round trip.
+pub fn search_game_data(
+    state: &AppState,
+    server: Server,
+    params: &GameDataSearchParams,
+) -> Result<GameDataSearchResponse, ApiError> {
+    let sd = state.try_server_data(server).ok_or(ApiError::NotFound)?;
+    let q = params.q.as_deref().map(str::trim).unwrap_or_default();
+    if q.is_empty() {
+        return Err(ApiError::BadRequest("missing q".into()));
+    }
+    let kinds = params
+        .kinds
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .filter(|k| !k.trim().is_empty())
+        .map(|k| {
+            SearchKind::parse(k).ok_or_else(|| ApiError::BadRequest(format!("unknown kind {k}")))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    Ok(GameDataSearchResponse {
+        server: server.as_str(),
+        hits: sd.search_index.load().search(q, &kinds, limit),
+    })
+}
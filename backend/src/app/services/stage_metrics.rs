@@ -0,0 +1,144 @@
+//! Stage difficulty metrics (`core::stage_metrics`) for one stage and as a
+//! sortable index over every stage with a level file.
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::app::cache::keys::CacheKey;
+use crate::app::error::ApiError;
+use crate::app::extractors::pagination::Pagination;
+use crate::app::services::level::{get_stage_map, level_path};
+use crate::app::state::{AppState, ServerData};
+use crate::core::gamedata::types::level::parse_stage_map;
+use crate::core::hypergryph::constants::Server;
+use crate::core::stage_metrics::{SORT_KEYS, StageMetrics, analyze};
+
+/// Metrics for one stage at the difficulty it is played at; `None` when it has
+/// no readable level file.
+pub async fn stage_metrics(
+    state: &AppState,
+    server: Server,
+    stage_id: &str,
+) -> Option<StageMetrics> {
+    let map = get_stage_map(state, server, stage_id).await.ok()?;
+    let gd = state.try_server_data(server)?.game_data.load_full();
+    let difficulty = gd
+        .stages
+        .get(stage_id)
+        .map_or("NORMAL", |s| s.difficulty.as_str());
+    Some(analyze(&map, &gd.enemies, difficulty))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageMetricsRow {
+    pub stage_id: String,
+    pub code: String,
+    pub name: Option<String>,
+    pub zone_id: String,
+    #[serde(flatten)]
+    pub metrics: StageMetrics,
+}
+
+#[derive(Deserialize)]
+pub struct StageIndexParams {
+    pub zone: Option<String>,
+    /// One of [`SORT_KEYS`]; defaults to `totalHp`.
+    pub sort: Option<String>,
+    /// `asc` or `desc` (default).
+    pub order: Option<String>,
+    #[serde(flatten)]
+    pub pagination: Pagination,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageMetricsPage {
+    pub total: usize,
+    pub stages: Vec<StageMetricsRow>,
+}
+
+/// Every non-story stage with a level, analyzed off the async runtime. A few
+/// thousand level files, so it's built once per game-data load and cached.
+fn build_index(server_data: &ServerData) -> Vec<StageMetricsRow> {
+    let gd = server_data.game_data.load_full();
+    let mut rows: Vec<StageMetricsRow> = gd
+        .stages
+        .values()
+        .filter(|s| !s.is_story_only)
+        .collect::<Vec<_>>()
+        .par_iter()
+        .filter_map(|stage| {
+            let level_id = stage.level_id.as_deref()?;
+            let bytes = std::fs::read(level_path(server_data, level_id)).ok()?;
+            let map = parse_stage_map(stage, level_id, &bytes, &gd.enemies).ok()?;
+            Some(StageMetricsRow {
+                stage_id: stage.stage_id.clone(),
+                code: stage.code.clone(),
+                name: stage.name.clone(),
+                zone_id: stage.zone_id.clone(),
+                metrics: analyze(&map, &gd.enemies, stage.difficulty.as_str()),
+            })
+        })
+        .collect();
+    rows.sort_by(|a, b| a.stage_id.cmp(&b.stage_id));
+    rows
+}
+
+async fn index_rows(state: &AppState, server: Server) -> Result<Vec<StageMetricsRow>, ApiError> {
+    let server_data = state.try_server_data(server).ok_or(ApiError::NotFound)?;
+    let key = CacheKey::StaticData {
+        resource: "stage_metrics_index",
+        server: server.as_str(),
+        fields_hash: 0,
+        page: 0,
+    };
+    if let Some(cached) = state.cache.get::<Vec<StageMetricsRow>>(&key).await {
+        return Ok(cached);
+    }
+
+    let rows = tokio::task::spawn_blocking(move || build_index(&server_data))
+        .await
+        .map_err(|e| ApiError::Internal(e.into()))?;
+
+    state.cache.set(&key, &rows).await;
+    Ok(rows)
+}
+
+pub async fn stage_metrics_index(
+    state: &AppState,
+    server: Server,
+    params: &StageIndexParams,
+) -> Result<StageMetricsPage, ApiError> {
+    let sort = params.sort.as_deref().unwrap_or("totalHp");
+    if !SORT_KEYS.contains(&sort) {
+        return Err(ApiError::BadRequest(format!(
+            "sort must be one of {}",
+            SORT_KEYS.join(", ")
+        )));
+    }
+    let ascending = match params.order.as_deref() {
+        None | Some("desc") => false,
+        Some("asc") => true,
+        Some(other) => return Err(ApiError::BadRequest(format!("unknown order {other}"))),
+    };
+
+    let mut rows: Vec<StageMetricsRow> = index_rows(state, server)
+        .await?
+        .into_iter()
+        .filter(|r| params.zone.as_ref().is_none_or(|z| &r.zone_id == z))
+        .collect();
+    rows.sort_by(|a, b| {
+        let (a, b) = (a.metrics.sort_value(sort), b.metrics.sort_value(sort));
+        let ord = a.unwrap_or(0.0).total_cmp(&b.unwrap_or(0.0));
+        if ascending { ord } else { ord.reverse() }
+    });
+
+    let total = rows.len();
+    let stages = rows
+        .into_iter()
+        .skip(params.pagination.offset() as usize)
+        .take(params.pagination.limit() as usize)
+        .collect();
+    Ok(StageMetricsPage { total, stages })
+}
@@ -0,0 +1,160 @@
+//! Effective enemy stat sheets (`core::stage_modifiers`) for a stage under a
+//! difficulty and a set of picked CC runes.
+
+use serde::{Deserialize, Serialize};
+
+use crate::app::cache::keys::CacheKey;
+use crate::app::error::ApiError;
+use crate::app::services::level::{difficulty_param, get_stage_map};
+use crate::app::state::AppState;
+use crate::core::gamedata::enrich::stage_class::read_json;
+use crate::core::hypergryph::constants::Server;
+use crate::core::stage_modifiers::{
+    RUNE_TABLE, RunePack, StageStatSheet, evaluate, recal_rune_packs,
+};
+
+#[derive(Deserialize)]
+pub struct RiskParams {
+    /// `NORMAL`, `FOUR_STAR` (challenge), `EASY` or `SIX_STAR`; defaults to
+    /// `NORMAL`.
+    pub difficulty: Option<String>,
+    /// Comma-separated rune ids. Essential runes are always applied.
+    pub runes: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnemyStatsResponse {
+    #[serde(flatten)]
+    pub sheet: StageStatSheet,
+    /// Every rune selectable on this stage.
+    pub available_runes: Vec<RunePack>,
+    /// Where `availableRunes` come from. Only the permanent CC stages' runes
+    /// are loaded; a running season's stages list none.
+    pub rune_source: &'static str,
+}
+
+/// Rune packs for one stage, read from `crisis_v2_table` on first use.
+async fn stage_rune_packs(
+    state: &AppState,
+    server: Server,
+    stage_id: &str,
+) -> Result<Vec<RunePack>, ApiError> {
+    let resource = format!("recal-runes:{stage_id}");
+    let key = CacheKey::StaticData {
+        resource: &resource,
+        server: server.as_str(),
+        fields_hash: 0,
+        page: 0,
+    };
+    if let Some(cached) = state.cache.get::<Vec<RunePack>>(&key).await {
+        return Ok(cached);
+    }
+
+    let server_data = state.try_server_data(server).ok_or(ApiError::NotFound)?;
+    let stage = stage_id.to_owned();
+    let packs = tokio::task::spawn_blocking(move || {
+        read_json(server_data.game_data_dir.as_ref(), "crisis_v2_table")
+            .map(|table| recal_rune_packs(&table, &stage))
+            .unwrap_or_default()
+    })
+    .await
+    .map_err(|e| ApiError::Internal(e.into()))?;
+
+    state.cache.set(&key, &packs).await;
+    Ok(packs)
+}
+
+/// Resolve `runes` against `available`: unknown ids and two picks from one
+/// exclusive group are rejected; essential runes are added.
+fn select_runes<'a>(
+    available: &'a [RunePack],
+    runes: Option<&str>,
+) -> Result<Vec<&'a RunePack>, ApiError> {
+    let mut picked: Vec<&RunePack> = available.iter().filter(|p| p.essential).collect();
+    for id in runes
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    {
+        let pack = available
+            .iter()
+            .find(|p| p.id == id)
+            .ok_or_else(|| ApiError::BadRequest(format!("unknown rune {id}")))?;
+        if picked.iter().any(|p| p.id == pack.id) {
+            continue;
+        }
+        if let Some(group) = &pack.exclusive_group
+            && let Some(other) = picked
+                .iter()
+                .find(|p| p.exclusive_group.as_ref() == Some(group))
+        {
+            return Err(ApiError::BadRequest(format!(
+                "runes {} and {id} are mutually exclusive",
+                other.id
+            )));
+        }
+        picked.push(pack);
+    }
+    Ok(picked)
+}
+
+pub async fn get_enemy_stats(
+    state: &AppState,
+    server: Server,
+    stage_id: &str,
+    params: &RiskParams,
+) -> Result<EnemyStatsResponse, ApiError> {
+    let difficulty = difficulty_param(params.difficulty.as_deref())?;
+
+    let map = get_stage_map(state, server, stage_id).await?;
+    let available = stage_rune_packs(state, server, stage_id).await?;
+    let picked = select_runes(&available, params.runes.as_deref())?;
+
+    let gd = state.game_data(server);
+    let sheet = evaluate(&map, &gd.enemies, difficulty, &picked);
+    Ok(EnemyStatsResponse {
+        sheet,
+        available_runes: available,
+        rune_source: RUNE_TABLE,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pack(id: &str, essential: bool, group: Option<&str>) -> RunePack {
+        RunePack {
+            id: id.into(),
+            points: 1.0,
+            score: 0,
+            essential,
+            exclusive_group: group.map(Into::into),
+            description: String::new(),
+            modifiers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn rune_selection() {
+        let available = [
+            pack("base", true, None),
+            pack("hp_1", false, Some("hp")),
+            pack("hp_2", false, Some("hp")),
+            pack("atk", false, None),
+        ];
+        let ids = |picked: Vec<&RunePack>| -> Vec<String> {
+            picked.into_iter().map(|p| p.id.clone()).collect()
+        };
+
+        assert_eq!(ids(select_runes(&available, None).unwrap()), ["base"]);
+        assert_eq!(
+            ids(select_runes(&available, Some("atk, hp_2,atk")).unwrap()),
+            ["base", "atk", "hp_2"]
+        );
+        assert!(select_runes(&available, Some("hp_1,hp_2")).is_err());
+        assert!(select_runes(&available, Some("nope")).is_err());
+    }
+}
@@ -7,7 +7,10 @@ use crate::app::cache::keys::CacheKey;
 use crate::app::cache::{CachedJson, cached_json};
 use crate::app::error::ApiError;
 use crate::app::services::level::get_level;
+use crate::app::services::stage_metrics::stage_metrics;
 use crate::app::state::AppState;
+use crate::core::battle_replay::ReplaySquadMember;
+use crate::core::clear_squads::{ClearSquadStats, aggregate};
 use crate::core::gamedata::types::GameData;
 use crate::core::gamedata::types::chibi::ChibiCharacter;
 use crate::core::gamedata::types::enemy::{Enemy, RaceData};
@@ -17,6 +20,9 @@ use crate::core::gamedata::types::skin::DisplaySkin;
 use crate::core::gamedata::types::stage::Stage;
 use crate::core::gamedata::types::zone::Zone;
 use crate::core::hypergryph::constants::Server;
+use crate::core::stage_metrics::StageMetrics;
+use crate::database::queries::drop_rates::get_stage_drop_rates;
+use crate::database::queries::replays::shared_clear_squads;
 
 pub async fn get_resource(
     state: &AppState,
@@ -83,8 +89,26 @@ struct StageDetailResponse<'a> {
     /// Handbook records for every enemy referenced by the level (declared in
     /// `enemyDbRefs` or spawned by a wave action), keyed by enemy id.
     enemies: HashMap<&'a str, &'a Enemy>,
-    /// Item records for every non-`CHAR` drop in `stageDropInfo`, keyed by id.
+    /// Item records for every non-`CHAR` drop in `stageDropInfo` or
+    /// `expectedDrops`, keyed by id.
     materials: HashMap<&'a str, &'a Item>,
+    /// Observed drops per run from the imported drop matrix (empty when none
+    /// is stored for this server), most frequent first.
+    expected_drops: Vec<ExpectedStageDrop>,
+    /// Difficulty metrics from the level; `None` without a level file.
+    difficulty: Option<StageMetrics>,
+    /// Squads from archived replays of users sharing their stats; `None`
+    /// below `clear_squads::MIN_CLEARS` clears.
+    community_clears: Option<ClearSquadStats>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExpectedStageDrop {
+    item_id: String,
+    per_run: f64,
+    /// Runs the rate was observed over.
+    sample_size: i64,
 }
 
 /// Enemy ids referenced by a (camelCased) level: everything declared in
@@ -140,6 +164,26 @@ pub async fn get_stage_detail(
 
         // `get_level` caches on its own; `None` when the stage has no level file.
         let level_data = get_level(state, server, stage_id).await.ok();
+        let difficulty = stage_metrics(state, server, stage_id).await;
+        let squads: Vec<Vec<ReplaySquadMember>> =
+            shared_clear_squads(&state.db, server.index() as i16, stage_id)
+                .await?
+                .into_iter()
+                .filter_map(|squad| serde_json::from_value(squad).ok())
+                .collect();
+
+        let mut expected_drops: Vec<ExpectedStageDrop> =
+            get_stage_drop_rates(&state.db, server.index() as i16, stage_id)
+                .await?
+                .into_iter()
+                .filter(|r| r.times > 0)
+                .map(|r| ExpectedStageDrop {
+                    per_run: r.quantity as f64 / r.times as f64,
+                    item_id: r.item_id,
+                    sample_size: r.times,
+                })
+                .collect();
+        expected_drops.sort_by(|a, b| b.per_run.total_cmp(&a.per_run));
 
         let sd = state.try_server_data(server).ok_or(ApiError::NotFound)?;
         let gd = sd.game_data.load_full();
@@ -168,12 +212,25 @@ pub async fn get_stage_detail(
             }
         }
 
+        for drop in &expected_drops {
+            if let Some((k, item)) = gd.materials.items.get_key_value(&drop.item_id) {
+                materials.insert(k.as_str(), item);
+            }
+        }
+
+        let community_clears = aggregate(&squads, |char_id| {
+            gd.operators.get(char_id).map(|op| op.rarity.to_star_int())
+        });
+
         serde_json::to_string(&StageDetailResponse {
             stage,
             zone,
             level_data,
             enemies,
             materials,
+            expected_drops,
+            difficulty,
+            community_clears,
         })
         .map_err(|e| ApiError::Internal(e.into()))
     })
@@ -1,13 +1,16 @@
 use std::collections::HashMap;
 use std::ops::Deref;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
 
-use arc_swap::ArcSwap;
+use arc_swap::{ArcSwap, ArcSwapOption};
+use chrono::{DateTime, Utc};
 use reqwest::Client;
 use sqlx::PgPool;
 
 use crate::app::cache::store::CacheStore;
 use crate::core::gamedata::{assets::AssetIndex, types::GameData};
+use crate::core::gamedata_search::SearchIndex;
 use crate::core::hypergryph::constants::Server;
 
 #[derive(Clone)]
@@ -30,11 +33,39 @@ impl Deref for AppState {
 /// key is visible through all of them.
 pub struct ServerData {
     pub game_data: ArcSwap<GameData>,
+    /// Generation of `game_data` ([`next_load_generation`]), bumped before
+    /// every swap.
+    pub generation: AtomicU64,
+    /// The load the last hot reload replaced; empty until the first reload.
+    pub previous_game_data: ArcSwapOption<PreviousGameData>,
     pub asset_index: ArcSwap<AssetIndex>,
+    /// Rebuilt alongside `game_data` on every reload.
+    pub search_index: ArcSwap<SearchIndex>,
     pub game_data_dir: String,
     pub assets_dir: String,
 }
 
+/// A superseded [`GameData`], kept so `/changelog` can diff against it.
+/// Memory only: a restart starts without one, so the changelog is empty until
+/// the next reload.
+pub struct PreviousGameData {
+    pub game_data: Arc<GameData>,
+    /// The load that replaced it, and that load's generation.
+    pub replaced_by: Arc<GameData>,
+    pub generation: u64,
+    pub replaced_at: DateTime<Utc>,
+}
+
+/// Source of [`ServerData::generation`]. Seeded randomly per process, so
+/// generations from before a restart, or from another instance sharing the
+/// cache, don't collide with this one's.
+static LOAD_GENERATION: LazyLock<AtomicU64> =
+    LazyLock::new(|| AtomicU64::new(u64::from(rand::random::<u32>()) << 32));
+
+pub fn next_load_generation() -> u64 {
+    LOAD_GENERATION.fetch_add(1, Ordering::SeqCst)
+}
+
 pub struct AppStateInner {
     pub db: PgPool,
     pub cache: CacheStore,
@@ -102,13 +133,29 @@ impl AppState {
         self.asset_index(self.default_server)
     }
 
+    /// Install a reloaded [`GameData`], keeping the one it replaces as the
+    /// server's previous load.
     pub fn swap_game_data(&self, server: Server, new: GameData) {
-        self.server_data(server).game_data.store(Arc::new(new));
+        let sd = self.server_data(server);
+        let generation = next_load_generation();
+        sd.generation.store(generation, Ordering::SeqCst);
+        let new = Arc::new(new);
+        let old = sd.game_data.swap(Arc::clone(&new));
+        sd.previous_game_data.store(Some(Arc::new(PreviousGameData {
+            game_data: old,
+            replaced_by: new,
+            generation,
+            replaced_at: Utc::now(),
+        })));
     }
 
     pub fn swap_asset_index(&self, server: Server, new: AssetIndex) {
         self.server_data(server).asset_index.store(Arc::new(new));
     }
+
+    pub fn swap_search_index(&self, server: Server, new: SearchIndex) {
+        self.server_data(server).search_index.store(Arc::new(new));
+    }
 }
 
 pub struct AppConfig {
@@ -0,0 +1,119 @@
+//! DPS formula coverage report and golden-value snapshot.
+//!
+//! Walks every released operator, lists the ones (and the skills / modules)
+//! without a DPS formula, evaluates every formula skill x module x potential
+//! combination, and writes the values as a snapshot for
+//! `tests/dps_snapshot_test.rs` to diff against.
+//!
+//! Usage:
+//!   cargo run --release --bin dps-coverage                        # Write report + snapshot
+//!   cargo run --release --bin dps-coverage -- --check             # Diff against the snapshot, exit 1 on drift
+//!   cargo run --release --bin dps-coverage -- --report <path>     # Report path (default dps_coverage.json)
+//!   cargo run --release --bin dps-coverage -- --snapshot <path>   # Snapshot path (default tests/fixtures/dps_snapshot.json)
+
+use std::{fs, path::Path, path::PathBuf, time::Instant};
+
+use anyhow::{Context, Result};
+use backend::app::state::{default_bin_server_from_env, derive_assets_dir, derive_game_data_dir};
+use backend::core::gamedata::init_game_data;
+use backend::dps::coverage::{self, SNAPSHOT_TOLERANCE, Snapshot};
+use dotenv::dotenv;
+
+struct Args {
+    report: PathBuf,
+    snapshot: PathBuf,
+    check: bool,
+}
+
+fn parse_args() -> Result<Args> {
+    let mut a = Args {
+        report: PathBuf::from("dps_coverage.json"),
+        snapshot: PathBuf::from("tests/fixtures/dps_snapshot.json"),
+        check: false,
+    };
+    let mut it = std::env::args().skip(1);
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--report" => {
+                a.report = PathBuf::from(it.next().context("--report requires a path")?);
+            }
+            "--snapshot" => {
+                a.snapshot = PathBuf::from(it.next().context("--snapshot requires a path")?);
+            }
+            "--check" => a.check = true,
+            "-h" | "--help" => {
+                eprintln!(
+                    "Usage: dps-coverage [--check] [--report <path>] [--snapshot <path>]\n\
+                     \n\
+                     Writes a formula coverage report and a golden DPS snapshot.\n\
+                     With --check, diffs against the existing snapshot instead of\n\
+                     overwriting it and exits 1 if any value moved."
+                );
+                std::process::exit(0);
+            }
+            other => anyhow::bail!("unknown argument: {other}"),
+        }
+    }
+    Ok(a)
+}
+
+fn main() -> Result<()> {
+    dotenv().ok();
+    let args = parse_args()?;
+
+    // ASSETS_DIR is a base dir; the server's data loads from `{base}/{server}`
+    // (+ `/gamedata/excel`). The game-data server is BIN_SERVER/SERVERS-driven.
+    let base = std::env::var("ASSETS_DIR").unwrap_or_else(|_| "../assets/output".into());
+    let server = default_bin_server_from_env();
+    let data_dir = derive_game_data_dir(&base, server);
+    let assets_dir = derive_assets_dir(&base, server);
+
+    let t0 = Instant::now();
+    let game_data = init_game_data(Path::new(&data_dir), Path::new(&assets_dir))
+        .context("failed to load game data")?;
+    eprintln!(
+        "loaded {} operators in {:.2}s",
+        game_data.operators.len(),
+        t0.elapsed().as_secs_f64()
+    );
+
+    let (report, snapshot) = coverage::build(&game_data);
+    eprintln!(
+        "{}/{} operators covered, {} combinations, {} failures, {} operators with missing skills/modules",
+        report.operators_covered,
+        report.operators_total,
+        report.combinations,
+        report.failures.len(),
+        report.missing_slots.len(),
+    );
+
+    if args.check {
+        let existing = fs::read_to_string(&args.snapshot)
+            .with_context(|| format!("failed to read {}", args.snapshot.display()))?;
+        let existing: Snapshot = serde_json::from_str(&existing)?;
+        let diff = coverage::diff(&existing, &snapshot, SNAPSHOT_TOLERANCE);
+        if diff.is_empty() {
+            eprintln!("snapshot matches");
+            return Ok(());
+        }
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+        eprintln!(
+            "snapshot drift: {} changed, {} added, {} removed",
+            diff.changed.len(),
+            diff.added.len(),
+            diff.removed.len()
+        );
+        std::process::exit(1);
+    }
+
+    fs::write(&args.report, serde_json::to_string_pretty(&report)?)
+        .with_context(|| format!("failed to write {}", args.report.display()))?;
+    fs::write(&args.snapshot, serde_json::to_string_pretty(&snapshot)?)
+        .with_context(|| format!("failed to write {}", args.snapshot.display()))?;
+    eprintln!(
+        "wrote {} and {}",
+        args.report.display(),
+        args.snapshot.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,124 @@
+//! Import (or refresh) a server's stage drop rates from a Penguin Statistics
+//! drop-matrix export on disk.
+//!
+//! The export is the JSON body of Penguin's `/result/matrix?server=<CN|US|JP|KR>`
+//! (`{"matrix": [{"stageId", "itemId", "quantity", "times"}, ...]}`). Each
+//! import replaces everything stored for the server, so re-running with a
+//! newer export is the refresh.
+//!
+//! Usage:
+//!   cargo run --release --bin import-drop-rates -- <matrix.json>
+//!   cargo run --release --bin import-drop-rates -- --server cn <matrix.json>
+//!   cargo run --release --bin import-drop-rates -- --dry-run <matrix.json>
+
+use std::{path::PathBuf, time::Instant};
+
+use anyhow::{Context, Result, bail};
+use backend::app::state::default_bin_server_from_env;
+use backend::core::{drop_rates::load_drop_matrix, hypergryph::constants::Server};
+use backend::database::queries::drop_rates::{drop_rates_imported_at, replace_drop_rates};
+use dotenv::dotenv;
+use sqlx::postgres::PgPoolOptions;
+
+struct Args {
+    server: Option<Server>,
+    dry_run: bool,
+    path: PathBuf,
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "Usage: import-drop-rates [--server <code>] [--dry-run] <matrix.json>\n\
+         \n\
+         Replaces the server's stage_drop_rates rows with a Penguin Statistics\n\
+         drop-matrix export. --server defaults to BIN_SERVER / SERVERS."
+    );
+    std::process::exit(0);
+}
+
+fn parse_args() -> Result<Args> {
+    let mut server = None;
+    let mut dry_run = false;
+    let mut path = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--dry-run" => dry_run = true,
+            "--server" => {
+                let code = args.next().context("--server needs a value")?;
+                server = Some(Server::parse(&code).context("unknown server code")?);
+            }
+            "-h" | "--help" => usage(),
+            other if other.starts_with("--") => bail!("unknown arg: {other}"),
+            other => path = Some(PathBuf::from(other)),
+        }
+    }
+    let Some(path) = path else { usage() };
+    Ok(Args {
+        server,
+        dry_run,
+        path,
+    })
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv().ok();
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "import_drop_rates=info,backend=warn".into()),
+        )
+        .init();
+
+    let args = parse_args()?;
+    let server = args.server.unwrap_or_else(default_bin_server_from_env);
+
+    let matrix = load_drop_matrix(&args.path)
+        .with_context(|| format!("failed to load {}", args.path.display()))?;
+    let stages = matrix
+        .matrix
+        .iter()
+        .map(|e| e.stage_id.as_str())
+        .collect::<std::collections::HashSet<_>>()
+        .len();
+    tracing::info!(
+        server = server.as_str(),
+        rows = matrix.matrix.len(),
+        stages,
+        "drop matrix loaded",
+    );
+
+    if args.dry_run {
+        println!("(dry run - nothing written)");
+        return Ok(());
+    }
+
+    let database_url = std::env::var("DATABASE_URL").context("DATABASE_URL must be set")?;
+    let pool = PgPoolOptions::new()
+        .max_connections(2)
+        .connect(&database_url)
+        .await
+        .context("failed to connect to database")?;
+
+    let server_id = server.index() as i16;
+    if let Some(previous) = drop_rates_imported_at(&pool, server_id).await? {
+        tracing::info!(%previous, "replacing previous import");
+    }
+
+    let t0 = Instant::now();
+    let written = replace_drop_rates(&pool, server_id, &matrix)
+        .await
+        .context("failed to store drop rates")?;
+    tracing::info!(
+        rows = written,
+        elapsed_s = format!("{:.2}", t0.elapsed().as_secs_f64()),
+        "import complete",
+    );
+    tracing::info!(
+        "note: cached stage details keep their old drops until the next ttl expiry; \
+         restart the server or flush redis to force a refresh"
+    );
+
+    Ok(())
+}
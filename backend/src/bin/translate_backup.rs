@@ -765,6 +765,7 @@ fn build_status_row(user_id: &str, status: Option<&Value>) -> Value {
         "main_stage_progress": gs("mainStageProgress"),
         "resume": gs("resume"),
         "friend_num_limit": g("friendNumLimit"),
+        "originite": g("androidDiamond").max(g("iosDiamond")),
     })
 }
 
@@ -9,6 +9,7 @@ use crate::core::gacha_resync::reconcile_rarities;
 use crate::core::gamedata::assets::AssetIndex;
 use crate::core::gamedata::init_game_data;
 use crate::core::gamedata::tables::DataError;
+use crate::core::gamedata_search::SearchIndex;
 use crate::core::hypergryph::constants::Server;
 use crate::core::hypergryph::loaders::reload;
 
@@ -158,15 +159,17 @@ async fn perform_reload(state: &AppState, server: Server) {
     let result = tokio::task::spawn_blocking(move || {
         let game_data = init_game_data(Path::new(&data_dir), Path::new(&assets_dir))?;
         let asset_index = AssetIndex::build(Path::new(&assets_dir));
-        Ok::<_, DataError>((game_data, asset_index))
+        let search_index = SearchIndex::build(&game_data);
+        Ok::<_, DataError>((game_data, asset_index, search_index))
     })
     .await;
 
     match result {
-        Ok(Ok((game_data, asset_index))) => {
+        Ok(Ok((game_data, asset_index, search_index))) => {
             let op_count = game_data.operators.len();
             state.swap_game_data(server, game_data);
             state.swap_asset_index(server, asset_index);
+            state.swap_search_index(server, search_index);
 
             let prefix = if is_default {
                 "static:".to_string()
@@ -0,0 +1,236 @@
+//! Archived auto-deploy replays: the storable form of a decoded
+//! [`BattleReplay`] and its action log aligned to the [`StageMap`] grid.
+//!
+//! Replay positions use the game's bottom-up rows, like level route points;
+//! [`align_actions`] flips them into the stage viewer's screen space
+//! (`y = 0` is the top row) so deploys land on `tiles[y][x]`.
+//!
+//! [`StageMap`]: crate::core::gamedata::types::level::StageMap
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::core::hypergryph::yostar::{BattleReplay, Character};
+
+/// A squad slot as fielded in the replay.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaySquadMember {
+    pub char_id: String,
+    pub skin_id: String,
+    pub skill_id: Option<String>,
+    /// 0-based; -1 when no skill is selected.
+    pub skill_index: i32,
+    pub skill_level: i32,
+    pub elite: i32,
+    pub level: i32,
+    /// 0-based, as in the roster.
+    pub potential: i32,
+    pub module_id: Option<String>,
+    pub module_level: i32,
+    /// Borrowed support unit.
+    pub is_support: bool,
+}
+
+/// One logged input, in the game's grid coordinates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayLogEntry {
+    /// Seconds since battle start.
+    pub time: f64,
+    /// `deploy` / `retreat` / `skill`.
+    pub op: String,
+    pub char_id: String,
+    /// Distinguishes repeat deploys of the same summon/token.
+    pub unique_id: i64,
+    /// `up` / `right` / `down` / `left`; only meaningful for deploys.
+    pub direction: String,
+    pub row: i32,
+    pub col: i32,
+}
+
+/// A decoded replay as archived per user and stage.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayRecord {
+    pub stage_id: String,
+    pub level_id: String,
+    /// `quest` or `campaignV2`, as passed to `getBattleReplay`.
+    pub battle_type: String,
+    pub saved_at: DateTime<Utc>,
+    /// In-game seconds the run took at 1x speed.
+    pub play_time: f64,
+    pub cleared: bool,
+    pub remaining_life_points: i32,
+    pub killed_enemies: i32,
+    pub missed_enemies: i32,
+    pub squad: Vec<ReplaySquadMember>,
+    pub log: Vec<ReplayLogEntry>,
+    pub rune_list: serde_json::Value,
+}
+
+/// `char_002_amiya#1` / `char_1012_skadi2@ghost#1` → the operator id.
+fn char_id_of(c: &Character) -> String {
+    c.tmpl_id
+        .clone()
+        .filter(|t| !t.is_empty())
+        .unwrap_or_else(|| {
+            c.skin_id
+                .split(['@', '#'])
+                .next()
+                .unwrap_or_default()
+                .to_owned()
+        })
+}
+
+impl ReplayRecord {
+    pub fn from_replay(battle_type: &str, replay: &BattleReplay) -> Self {
+        let journal = &replay.journal;
+        let meta = &journal.metadata;
+        Self {
+            stage_id: meta.stage_id.clone(),
+            level_id: meta.level_id.clone(),
+            battle_type: battle_type.to_owned(),
+            saved_at: meta.save_time,
+            play_time: meta.standard_play_time,
+            cleared: meta.game_result,
+            remaining_life_points: meta.remaining_life_point,
+            killed_enemies: meta.killed_enemies_cnt,
+            missed_enemies: meta.missed_enemies_cnt,
+            squad: journal
+                .squad
+                .iter()
+                .map(|c| ReplaySquadMember {
+                    char_id: char_id_of(c),
+                    skin_id: c.skin_id.clone(),
+                    skill_id: c.skill_id.clone().filter(|s| !s.is_empty()),
+                    skill_index: c.skill_index,
+                    skill_level: c.skill_lvl,
+                    elite: c.phase,
+                    level: c.level,
+                    potential: c.potential_rank,
+                    module_id: Some(c.uniequip_id.clone()).filter(|s| !s.is_empty()),
+                    module_level: c.uniequip_level,
+                    is_support: c.is_assist_char,
+                })
+                .collect(),
+            log: journal
+                .logs
+                .iter()
+                .map(|l| ReplayLogEntry {
+                    time: l.timestamp,
+                    op: l.op.to_string(),
+                    char_id: l.signature.char_id.clone(),
+                    unique_id: l.signature.unique_id,
+                    direction: l.direction.to_string(),
+                    row: l.pos.row,
+                    col: l.pos.col,
+                })
+                .collect(),
+            rune_list: journal.rune_list.clone(),
+        }
+    }
+}
+
+/// A logged input on the stage viewer grid.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayAction {
+    pub time: f64,
+    pub op: String,
+    pub char_id: String,
+    pub unique_id: i64,
+    pub direction: String,
+    pub x: i32,
+    pub y: i32,
+}
+
+/// The log in stage-viewer coordinates for a map `height` tiles tall.
+pub fn align_actions(log: &[ReplayLogEntry], height: usize) -> Vec<ReplayAction> {
+    let top = (height as i32 - 1).max(0);
+    log.iter()
+        .map(|l| ReplayAction {
+            time: l.time,
+            op: l.op.clone(),
+            char_id: l.char_id.clone(),
+            unique_id: l.unique_id,
+            direction: l.direction.clone(),
+            x: l.col,
+            y: top - l.row,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay() -> BattleReplay {
+        serde_json::from_value(serde_json::json!({
+            "campaignOnlyVersion": 0,
+            "timestamp": "1700000000",
+            "journal": {
+                "metadata": {
+                    "standardPlayTime": 182.5,
+                    "gameResult": 1,
+                    "saveTime": "2023-11-14T22:13:20Z",
+                    "remainingCost": 12,
+                    "remainingLifePoint": 3,
+                    "killedEnemiesCnt": 40,
+                    "missedEnemiesCnt": 0,
+                    "levelId": "Obt/Main/level_main_01-07",
+                    "stageId": "main_01-07",
+                    "validKilledEnemiesCnt": 40
+                },
+                "squad": [{
+                    "charInstId": 1,
+                    "skinId": "char_1012_skadi2@ghost#1",
+                    "tmplId": null,
+                    "skillId": "skchr_skadi2_3",
+                    "skillIndex": 2,
+                    "skillLvl": 10,
+                    "level": 90,
+                    "phase": 2,
+                    "potentialRank": 0,
+                    "favorBattlePhase": 2,
+                    "isAssistChar": false,
+                    "uniequipId": "",
+                    "uniequipLevel": 0
+                }],
+                "logs": [{
+                    "timestamp": 4.25,
+                    "signiture": { "uniqueId": 7, "charId": "char_1012_skadi2" },
+                    "op": 0,
+                    "direction": 1,
+                    "pos": { "row": 0, "col": 3 }
+                }],
+                "randomSeed": 42,
+                "runeList": []
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn record_from_replay() {
+        let record = ReplayRecord::from_replay("quest", &replay());
+        assert_eq!(record.stage_id, "main_01-07");
+        assert!(record.cleared);
+        let member = &record.squad[0];
+        assert_eq!(member.char_id, "char_1012_skadi2");
+        assert_eq!((member.elite, member.skill_index), (2, 2));
+        assert!(member.module_id.is_none());
+        let entry = &record.log[0];
+        assert_eq!(
+            (entry.op.as_str(), entry.direction.as_str()),
+            ("deploy", "right")
+        );
+    }
+
+    #[test]
+    fn actions_flip_to_screen_rows() {
+        let record = ReplayRecord::from_replay("quest", &replay());
+        let actions = align_actions(&record.log, 8);
+        assert_eq!((actions[0].x, actions[0].y), (3, 7));
+    }
+}
@@ -0,0 +1,205 @@
+//! Community clear statistics for a stage, aggregated from the squads of
+//! archived replays (`core::battle_replay`) shared by users who opted into
+//! stats sharing: most-used operators, squad size, typical investment and
+//! low-rarity clears.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::core::battle_replay::ReplaySquadMember;
+
+/// Fewer cleared replays than this and a stage shows no community stats, so
+/// a single player's squad can't be read back out of them.
+pub const MIN_CLEARS: usize = 3;
+/// Operators listed per stage.
+pub const TOP_OPERATORS: usize = 20;
+/// Highest rarity a squad may field (supports included) and still count as a
+/// low-rarity clear.
+pub const LOW_RARITY_MAX: i16 = 4;
+
+/// Median elite / level / skill level (1-10, 8+ being masteries).
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Investment {
+    pub elite: f64,
+    pub level: f64,
+    pub skill_level: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperatorUsage {
+    pub char_id: String,
+    /// Clears fielding the operator.
+    pub clears: usize,
+    /// `clears` over all clears.
+    pub usage: f64,
+    /// Clears where it was a borrowed support.
+    pub as_support: usize,
+    pub investment: Investment,
+    /// Skill index most often brought (0-based).
+    pub skill_index: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClearSquadStats {
+    pub clears: usize,
+    pub median_squad_size: f64,
+    pub min_squad_size: usize,
+    /// Over every fielded operator.
+    pub median_investment: Investment,
+    /// Clears whose highest rarity is at most [`LOW_RARITY_MAX`].
+    pub low_rarity_clears: usize,
+    /// Lowest highest-rarity seen in a clear.
+    pub min_max_rarity: Option<i16>,
+    pub operators: Vec<OperatorUsage>,
+}
+
+fn median(values: &mut [i32]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        f64::from(values[mid - 1] + values[mid]) / 2.0
+    } else {
+        f64::from(values[mid])
+    }
+}
+
+fn investment(members: &[&ReplaySquadMember]) -> Investment {
+    let pick = |f: fn(&ReplaySquadMember) -> i32| {
+        median(&mut members.iter().map(|m| f(m)).collect::<Vec<_>>())
+    };
+    Investment {
+        elite: pick(|m| m.elite),
+        level: pick(|m| m.level),
+        skill_level: pick(|m| m.skill_level),
+    }
+}
+
+/// Aggregate cleared squads; `None` below [`MIN_CLEARS`]. `rarity` maps an
+/// operator id to its star count.
+pub fn aggregate(
+    squads: &[Vec<ReplaySquadMember>],
+    rarity: impl Fn(&str) -> Option<i16>,
+) -> Option<ClearSquadStats> {
+    if squads.len() < MIN_CLEARS {
+        return None;
+    }
+
+    let mut sizes: Vec<i32> = squads.iter().map(|s| s.len() as i32).collect();
+    let all: Vec<&ReplaySquadMember> = squads.iter().flatten().collect();
+
+    let max_rarities: Vec<i16> = squads
+        .iter()
+        .filter_map(|s| s.iter().filter_map(|m| rarity(&m.char_id)).max())
+        .collect();
+
+    let mut by_operator: HashMap<&str, Vec<&ReplaySquadMember>> = HashMap::new();
+    for squad in squads {
+        // A squad can't field an operator twice, but the support slot can
+        // duplicate an owned one; count the clear once.
+        let mut seen: Vec<&str> = Vec::new();
+        for member in squad {
+            if !seen.contains(&member.char_id.as_str()) {
+                seen.push(&member.char_id);
+                by_operator.entry(&member.char_id).or_default().push(member);
+            }
+        }
+    }
+
+    let mut operators: Vec<OperatorUsage> = by_operator
+        .into_iter()
+        .map(|(char_id, members)| {
+            let mut skills: HashMap<i32, usize> = HashMap::new();
+            for m in members.iter().filter(|m| m.skill_index >= 0) {
+                *skills.entry(m.skill_index).or_default() += 1;
+            }
+            OperatorUsage {
+                char_id: char_id.to_owned(),
+                clears: members.len(),
+                usage: members.len() as f64 / squads.len() as f64,
+                as_support: members.iter().filter(|m| m.is_support).count(),
+                investment: investment(&members),
+                skill_index: skills
+                    .into_iter()
+                    .max_by_key(|&(index, n)| (n, -index))
+                    .map(|(index, _)| index),
+            }
+        })
+        .collect();
+    operators.sort_by(|a, b| b.clears.cmp(&a.clears).then(a.char_id.cmp(&b.char_id)));
+    operators.truncate(TOP_OPERATORS);
+
+    Some(ClearSquadStats {
+        clears: squads.len(),
+        median_squad_size: median(&mut sizes),
+        min_squad_size: squads.iter().map(Vec::len).min().unwrap_or(0),
+        median_investment: investment(&all),
+        low_rarity_clears: max_rarities
+            .iter()
+            .filter(|&&r| r <= LOW_RARITY_MAX)
+            .count(),
+        min_max_rarity: max_rarities.into_iter().min(),
+        operators,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(char_id: &str, elite: i32, level: i32, skill_index: i32) -> ReplaySquadMember {
+        ReplaySquadMember {
+            char_id: char_id.into(),
+            skin_id: format!("{char_id}#1"),
+            skill_id: None,
+            skill_index,
+            skill_level: 7,
+            elite,
+            level,
+            potential: 0,
+            module_id: None,
+            module_level: 0,
+            is_support: false,
+        }
+    }
+
+    #[test]
+    fn aggregates_cleared_squads() {
+        let rarity = |id: &str| match id {
+            "six" => Some(6),
+            "four" => Some(4),
+            "three" => Some(3),
+            _ => None,
+        };
+        let squads = vec![
+            vec![member("six", 2, 90, 2), member("four", 2, 70, 1)],
+            vec![member("six", 2, 60, 2), member("three", 1, 80, 0)],
+            vec![
+                member("four", 2, 50, 0),
+                member("three", 1, 80, 0),
+                member("four", 2, 50, 0),
+            ],
+        ];
+
+        let stats = aggregate(&squads, rarity).unwrap();
+        assert_eq!(stats.clears, 3);
+        assert_eq!((stats.median_squad_size, stats.min_squad_size), (2.0, 2));
+        assert_eq!(stats.low_rarity_clears, 1);
+        assert_eq!(stats.min_max_rarity, Some(4));
+
+        // Ties in clears order by id.
+        let top = &stats.operators[0];
+        assert_eq!((top.char_id.as_str(), top.clears), ("four", 2));
+        let six = stats.operators.iter().find(|o| o.char_id == "six").unwrap();
+        assert_eq!((six.investment.level, six.skill_index), (75.0, Some(2)));
+        assert!((six.usage - 2.0 / 3.0).abs() < 1e-9);
+
+        assert!(aggregate(&squads[..2], rarity).is_none());
+    }
+}
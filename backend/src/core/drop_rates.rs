@@ -0,0 +1,136 @@
+//! Stage drop-rate dataset: the Penguin Statistics drop-matrix export format,
+//! read from disk by `bin/import_drop_rates.rs` and stored per server in
+//! `stage_drop_rates`.
+//!
+//! Penguin ids mostly match `stage_table`; permanent and rerun copies of event
+//! stages carry a suffix, which `stage_table_id` strips.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::core::gamedata::types::stage::Stage;
+
+/// Penguin suffixes for permanent / rerun copies of event stages.
+pub const STAGE_ID_SUFFIXES: [&str; 2] = ["_perm", "_rep"];
+
+/// One `(stage, item)` cell of a Penguin Statistics drop matrix.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DropMatrixEntry {
+    pub stage_id: String,
+    pub item_id: String,
+    /// Items dropped across all recorded runs.
+    pub quantity: u64,
+    /// Recorded runs.
+    pub times: u64,
+}
+
+impl DropMatrixEntry {
+    pub fn per_run(&self) -> f64 {
+        if self.times == 0 {
+            0.0
+        } else {
+            self.quantity as f64 / self.times as f64
+        }
+    }
+}
+
+/// A Penguin Statistics `/result/matrix` export.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DropMatrix {
+    pub matrix: Vec<DropMatrixEntry>,
+}
+
+#[derive(Debug)]
+pub enum DropMatrixError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+}
+
+impl fmt::Display for DropMatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "IO error: {e}"),
+            Self::Parse(e) => write!(f, "Failed to parse drop matrix: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for DropMatrixError {}
+
+/// Read a drop-matrix export, dropping rows without samples and merging
+/// duplicate `(stage, item)` rows (exports split by time window repeat them).
+pub fn load_drop_matrix(path: &Path) -> Result<DropMatrix, DropMatrixError> {
+    let raw = std::fs::read(path).map_err(DropMatrixError::Io)?;
+    let parsed: DropMatrix = serde_json::from_slice(&raw).map_err(DropMatrixError::Parse)?;
+    Ok(merge_rows(parsed))
+}
+
+fn merge_rows(matrix: DropMatrix) -> DropMatrix {
+    let mut merged: HashMap<(String, String), DropMatrixEntry> = HashMap::new();
+    for entry in matrix.matrix.into_iter().filter(|e| e.times > 0) {
+        merged
+            .entry((entry.stage_id.clone(), entry.item_id.clone()))
+            .and_modify(|e| {
+                e.quantity += entry.quantity;
+                e.times += entry.times;
+            })
+            .or_insert(entry);
+    }
+    let mut matrix: Vec<DropMatrixEntry> = merged.into_values().collect();
+    matrix.sort_by(|a, b| (&a.stage_id, &a.item_id).cmp(&(&b.stage_id, &b.item_id)));
+    DropMatrix { matrix }
+}
+
+/// The `stage_table` id a Penguin stage id refers to.
+pub fn stage_table_id<'a>(stage_id: &'a str, stages: &HashMap<String, Stage>) -> Option<&'a str> {
+    if stages.contains_key(stage_id) {
+        return Some(stage_id);
+    }
+    STAGE_ID_SUFFIXES
+        .iter()
+        .filter_map(|suffix| stage_id.strip_suffix(suffix))
+        .find(|id| stages.contains_key(*id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(stage_id: &str, item_id: &str, quantity: u64, times: u64) -> DropMatrixEntry {
+        DropMatrixEntry {
+            stage_id: stage_id.to_owned(),
+            item_id: item_id.to_owned(),
+            quantity,
+            times,
+        }
+    }
+
+    #[test]
+    fn duplicate_rows_merge_and_empty_rows_drop() {
+        let matrix = merge_rows(DropMatrix {
+            matrix: vec![
+                entry("main_01-07", "30012", 50, 100),
+                entry("main_01-07", "30012", 70, 100),
+                entry("main_01-07", "30011", 0, 0),
+            ],
+        });
+        assert_eq!(matrix.matrix.len(), 1);
+        assert!((matrix.matrix[0].per_run() - 0.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn suffixed_ids_resolve_to_the_stage_table() {
+        let stages: HashMap<String, Stage> =
+            HashMap::from([("act11d0_05".to_owned(), Stage::default())]);
+        assert_eq!(
+            stage_table_id("act11d0_05_perm", &stages),
+            Some("act11d0_05")
+        );
+        assert_eq!(stage_table_id("act11d0_05", &stages), Some("act11d0_05"));
+        assert_eq!(stage_table_id("main_01-07", &stages), None);
+    }
+}
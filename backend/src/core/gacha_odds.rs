@@ -0,0 +1,387 @@
+//! Headhunting probability model: the 6★ soft-pity curve, per-banner rate-up
+//! rules keyed on `GachaPoolClient.gacha_rule_type`, and the exact
+//! distributions built on them (6★ counts over N pulls, pulls until a given
+//! featured operator).
+//!
+//! Every banner shares one 6★ curve: 2% base, +2% per pull once 50 pulls have
+//! passed without a 6★, so the 99th pull is certain. What differs per rule is
+//! which banners share a pity counter and how 6★s split between rate-ups:
+//!
+//! | Rule     | `gacha_rule_type`                          | Pity          | Rate-up share | Extra
+//! |----------|--------------------------------------------|---------------|---------------|------------------------------
+//! | Standard | NORMAL, SINGLE                             | shared        | 50%           |
+//! | Joint    | DOUBLE                                     | per banner    | 50%           |
+//! | Kernel   | CLASSIC, `CLASSIC_DOUBLE`, `CLASSIC_ATTAIN`    | shared Kernel | 50%           |
+//! | Limited  | LIMITED, ATTAIN, FESCLASSIC, SPECIAL       | per banner    | 70%           | 300-pull spark
+//! | Linkage  | LINKAGE                                    | per banner    | 50%           | rate-up by pull 120
+//!
+//! The rate-up share is split evenly across the banner's featured 6★s.
+
+use std::collections::HashMap;
+
+/// Pulls without a 6★ before the rate starts climbing.
+pub const SOFT_PITY_AFTER: u32 = 50;
+/// Longest possible run of pulls ending in a 6★.
+pub const HARD_PITY: u32 = 99;
+pub const BASE_SIX_RATE: f64 = 0.02;
+const RATE_STEP: f64 = 0.02;
+
+/// Distributions are walked until this much probability mass is left over.
+const TAIL_EPSILON: f64 = 1e-9;
+/// Give up on a target distribution after this many pulls (spark-less
+/// banners have no upper bound).
+const MAX_TARGET_PULLS: u32 = 5000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BannerRule {
+    Standard,
+    Joint,
+    Kernel,
+    Limited,
+    Linkage,
+}
+
+impl BannerRule {
+    /// From `GachaPoolClient.gacha_rule_type`. `None` for rule types with no
+    /// 6★ model (newbie pools and anything unknown).
+    pub fn from_rule_type(rule_type: &str) -> Option<Self> {
+        match rule_type {
+            "NORMAL" | "SINGLE" => Some(Self::Standard),
+            "DOUBLE" => Some(Self::Joint),
+            "CLASSIC" | "CLASSIC_DOUBLE" | "CLASSIC_ATTAIN" => Some(Self::Kernel),
+            "LIMITED" | "ATTAIN" | "FESCLASSIC" | "SPECIAL" => Some(Self::Limited),
+            "LINKAGE" => Some(Self::Linkage),
+            _ => None,
+        }
+    }
+
+    /// From the coarser `gacha_records.gacha_type`, for pools that are no
+    /// longer in `gacha_table`.
+    pub fn from_gacha_type(gacha_type: &str) -> Option<Self> {
+        match gacha_type {
+            "normal" | "single" => Some(Self::Standard),
+            "classic" => Some(Self::Kernel),
+            "limited" => Some(Self::Limited),
+            "linkage" => Some(Self::Linkage),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Standard => "standard",
+            Self::Joint => "joint",
+            Self::Kernel => "kernel",
+            Self::Limited => "limited",
+            Self::Linkage => "linkage",
+        }
+    }
+
+    /// Whether every banner of this rule draws on one pity counter. The rest
+    /// keep a counter per banner that disappears when it closes.
+    pub fn shares_pity(self) -> bool {
+        matches!(self, Self::Standard | Self::Kernel)
+    }
+
+    /// Share of 6★ results that land on the banner's rate-up operators.
+    pub fn featured_share(self) -> f64 {
+        match self {
+            Self::Limited => 0.7,
+            _ => 0.5,
+        }
+    }
+
+    /// Pulls on one banner after which its rate-up can be taken outright.
+    pub fn spark(self) -> Option<u32> {
+        match self {
+            Self::Limited => Some(300),
+            _ => None,
+        }
+    }
+
+    /// Pulls by which a rate-up 6★ is guaranteed if none has dropped yet.
+    pub fn featured_guarantee(self) -> Option<u32> {
+        match self {
+            Self::Linkage => Some(120),
+            _ => None,
+        }
+    }
+}
+
+/// 6★ chance of the next pull after `pity` pulls without one.
+pub fn six_star_rate(pity: u32) -> f64 {
+    if pity < SOFT_PITY_AFTER {
+        BASE_SIX_RATE
+    } else {
+        (BASE_SIX_RATE + RATE_STEP * f64::from(pity + 1 - SOFT_PITY_AFTER)).min(1.0)
+    }
+}
+
+/// `P(the first 6★ lands on pull n)` for n = 1.., starting from `pity`.
+pub fn first_six_pmf(pity: u32) -> Vec<f64> {
+    let mut pmf = Vec::new();
+    let mut alive = 1.0;
+    let mut p = pity.min(HARD_PITY - 1);
+    while alive > 0.0 {
+        let rate = six_star_rate(p);
+        pmf.push(alive * rate);
+        alive *= 1.0 - rate;
+        p += 1;
+    }
+    pmf
+}
+
+/// Long-run 6★ rate under the soft-pity curve (~2.89%).
+pub fn effective_six_rate() -> f64 {
+    let mean_gap: f64 = first_six_pmf(0)
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (i + 1) as f64 * p)
+        .sum();
+    1.0 / mean_gap
+}
+
+/// Distribution of the number of 6★s in `pulls` pulls starting from `pity`:
+/// index k holds `P(exactly k)`.
+pub fn six_count_pmf(pulls: u32, pity: u32) -> Vec<f64> {
+    const STATES: usize = HARD_PITY as usize;
+    // rows[k][p]: mass with k 6★s so far and pity p. Rows whose total falls
+    // under the epsilon are trimmed from both ends so the walk stays narrow.
+    let mut rows: Vec<[f64; STATES]> = vec![[0.0; STATES]];
+    rows[0][pity.min(HARD_PITY - 1) as usize] = 1.0;
+    let mut lo = 0usize;
+
+    for _ in 0..pulls {
+        let mut next: Vec<[f64; STATES]> = vec![[0.0; STATES]; rows.len() + 1];
+        for (k, row) in rows.iter().enumerate().skip(lo) {
+            for (p, &mass) in row.iter().enumerate() {
+                if mass == 0.0 {
+                    continue;
+                }
+                let rate = six_star_rate(p as u32);
+                next[k + 1][0] += mass * rate;
+                if p + 1 < STATES {
+                    next[k][p + 1] += mass * (1.0 - rate);
+                }
+            }
+        }
+        while next.len() > lo + 1 && next.last().is_some_and(|r| r.iter().sum::<f64>() < 1e-15) {
+            next.pop();
+        }
+        while lo + 1 < next.len() && next[lo].iter().sum::<f64>() < 1e-15 {
+            next[lo] = [0.0; STATES];
+            lo += 1;
+        }
+        rows = next;
+    }
+
+    rows.iter().map(|r| r.iter().sum()).collect()
+}
+
+/// Convolve independent count distributions (separate pity counters).
+pub fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    if a.is_empty() || b.is_empty() {
+        return a.iter().chain(b).copied().collect();
+    }
+    let mut out = vec![0.0; a.len() + b.len() - 1];
+    for (i, &x) in a.iter().enumerate() {
+        if x == 0.0 {
+            continue;
+        }
+        for (j, &y) in b.iter().enumerate() {
+            out[i + j] += x * y;
+        }
+    }
+    out
+}
+
+/// Mid-rank percentile of `observed` under `pmf`: 50 is exactly average,
+/// higher is luckier. Ties count half so a distribution that puts most of its
+/// mass on one value doesn't read as "lucky" for hitting it.
+pub fn luck_percentile(pmf: &[f64], observed: usize) -> f64 {
+    let below: f64 = pmf.iter().take(observed).sum();
+    let at = pmf.get(observed).copied().unwrap_or(0.0);
+    ((below + at / 2.0) * 100.0).clamp(0.0, 100.0)
+}
+
+pub fn mean(pmf: &[f64]) -> f64 {
+    pmf.iter().enumerate().map(|(k, p)| k as f64 * p).sum()
+}
+
+/// Where a player stands on one banner when asking for a rate-up operator.
+#[derive(Debug, Clone, Copy)]
+pub struct TargetState {
+    pub rule: BannerRule,
+    /// Rate-up 6★s on the banner, the target among them.
+    pub featured: u32,
+    pub pity: u32,
+    /// Pulls already made on this banner (spark / guarantee progress).
+    pub banner_pulls: u32,
+    /// A rate-up 6★ already dropped on this banner, so the Linkage
+    /// guarantee is spent.
+    pub featured_seen: bool,
+}
+
+/// Distribution of pulls until the target operator drops.
+#[derive(Debug, Clone)]
+pub struct TargetOdds {
+    /// `cdf[n - 1]` = chance of having the target within n pulls.
+    pub cdf: Vec<f64>,
+    pub expected_pulls: f64,
+}
+
+impl TargetOdds {
+    /// Fewest pulls that reach `chance` (0..=1).
+    pub fn pulls_for(&self, chance: f64) -> Option<u32> {
+        self.cdf
+            .iter()
+            .position(|&c| c >= chance - 1e-12)
+            .map(|i| i as u32 + 1)
+    }
+
+    pub fn chance_within(&self, pulls: u32) -> f64 {
+        match pulls {
+            0 => 0.0,
+            n => self
+                .cdf
+                .get(n as usize - 1)
+                .or(self.cdf.last())
+                .copied()
+                .unwrap_or(0.0),
+        }
+    }
+}
+
+pub fn pulls_to_target(state: TargetState) -> TargetOdds {
+    let featured = state.featured.max(1);
+    let rule = state.rule;
+    let target_share = rule.featured_share() / f64::from(featured);
+    let guarantee = rule
+        .featured_guarantee()
+        .filter(|_| !state.featured_seen)
+        .filter(|&g| state.banner_pulls < g);
+
+    // (pity, guarantee still pending) -> probability of not having the target.
+    let mut states: HashMap<(u32, bool), f64> =
+        HashMap::from([((state.pity.min(HARD_PITY - 1), guarantee.is_some()), 1.0)]);
+    let mut cdf = Vec::new();
+    let mut got = 0.0;
+    let mut expected = 0.0;
+
+    for n in 1..=MAX_TARGET_PULLS {
+        let pull_on_banner = state.banner_pulls + n;
+        let mut next: HashMap<(u32, bool), f64> = HashMap::new();
+        for (&(pity, pending), &mass) in &states {
+            let rate = six_star_rate(pity);
+            let forced = pending && guarantee.is_some_and(|g| pull_on_banner >= g);
+            if forced {
+                // A rate-up is forced; the target is one of `featured`.
+                got += mass / f64::from(featured);
+                *next.entry((0, false)).or_default() += mass * (1.0 - 1.0 / f64::from(featured));
+                continue;
+            }
+            got += mass * rate * target_share;
+            let other_featured = rate * (rule.featured_share() - target_share);
+            let off_banner = rate * (1.0 - rule.featured_share());
+            *next.entry((0, false)).or_default() += mass * other_featured;
+            *next.entry((0, pending)).or_default() += mass * off_banner;
+            if pity + 1 < HARD_PITY {
+                *next.entry((pity + 1, pending)).or_default() += mass * (1.0 - rate);
+            }
+        }
+        next.retain(|_, m| *m > 0.0);
+        if rule.spark().is_some_and(|s| pull_on_banner >= s) {
+            got = 1.0;
+            next.clear();
+        }
+        states = next;
+        expected += 1.0 - cdf.last().copied().unwrap_or(0.0);
+        cdf.push(got.min(1.0));
+        if 1.0 - got < TAIL_EPSILON {
+            break;
+        }
+    }
+
+    TargetOdds {
+        cdf,
+        expected_pulls: expected,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn soft_pity_reaches_certainty_on_pull_99() {
+        assert!((six_star_rate(0) - 0.02).abs() < 1e-12);
+        assert!((six_star_rate(49) - 0.02).abs() < 1e-12);
+        assert!((six_star_rate(50) - 0.04).abs() < 1e-12);
+        assert!((six_star_rate(98) - 1.0).abs() < 1e-12);
+        let pmf = first_six_pmf(0);
+        assert_eq!(pmf.len(), HARD_PITY as usize);
+        assert!((pmf.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!((effective_six_rate() - 0.0289).abs() < 0.0005);
+    }
+
+    #[test]
+    fn count_distribution_matches_the_long_run_rate() {
+        let pmf = six_count_pmf(1000, 0);
+        assert!((pmf.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        let expected = mean(&pmf);
+        assert!((expected - 1000.0 * effective_six_rate()).abs() < 1.5);
+        assert!(luck_percentile(&pmf, 10) < 1.0);
+        assert!(luck_percentile(&pmf, 50) > 99.0);
+        // 99 pulls from zero always hold at least one 6★.
+        assert!(six_count_pmf(99, 0)[0] < 1e-12);
+    }
+
+    #[test]
+    fn limited_spark_caps_the_target_distribution() {
+        let odds = pulls_to_target(TargetState {
+            rule: BannerRule::Limited,
+            featured: 2,
+            pity: 0,
+            banner_pulls: 0,
+            featured_seen: false,
+        });
+        assert_eq!(odds.cdf.len(), 300);
+        assert_eq!(odds.pulls_for(1.0), Some(300));
+        assert!(odds.chance_within(100) > 0.5);
+        assert!(odds.expected_pulls < 300.0);
+    }
+
+    #[test]
+    fn linkage_guarantee_forces_the_sole_rate_up() {
+        let pending = TargetState {
+            rule: BannerRule::Linkage,
+            featured: 1,
+            pity: 0,
+            banner_pulls: 100,
+            featured_seen: false,
+        };
+        let odds = pulls_to_target(pending);
+        assert!((odds.chance_within(20) - 1.0).abs() < 1e-12);
+
+        let spent = pulls_to_target(TargetState {
+            featured_seen: true,
+            ..pending
+        });
+        assert!(spent.chance_within(20) < 0.5);
+    }
+
+    #[test]
+    fn rule_types_map_to_rules() {
+        assert_eq!(
+            BannerRule::from_rule_type("FESCLASSIC"),
+            Some(BannerRule::Limited)
+        );
+        assert_eq!(
+            BannerRule::from_rule_type("CLASSIC_ATTAIN"),
+            Some(BannerRule::Kernel)
+        );
+        assert_eq!(BannerRule::from_gacha_type("boot"), None);
+        assert!(BannerRule::Standard.shares_pity());
+        assert!(!BannerRule::Linkage.shares_pity());
+    }
+}
@@ -328,6 +328,18 @@ pub struct Enemy {
     pub portrait: Option<String>,
 }
 
+impl Enemy {
+    /// Stats at one `enemy_database` level entry; `None` when the enemy has
+    /// no such level.
+    pub fn level_stats(&self, level: i32) -> Option<&EnemyLevelStats> {
+        self.stats
+            .as_ref()?
+            .levels
+            .iter()
+            .find(|l| l.level == level)
+    }
+}
+
 // ============================================================================
 // Container Types (used in GameData)
 // ============================================================================
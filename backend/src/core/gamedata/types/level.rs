@@ -16,7 +16,7 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use super::enemy::{DamageType, Enemy, EnemyHandbook, EnemyLevel};
+use super::enemy::{DamageType, Enemy, EnemyAttributes, EnemyHandbook, EnemyLevel};
 use super::stage::Stage;
 
 // ============================================================================
@@ -188,6 +188,9 @@ struct RawAction {
 #[serde(rename_all = "PascalCase")]
 struct RawDbRef {
     id: String,
+    /// Which `enemy_database` level entry this stage spawns the enemy at.
+    #[serde(default)]
+    level: i32,
 }
 
 // ============================================================================
@@ -275,7 +278,7 @@ pub struct HiddenRoute {
 
 /// A stage-wide modifier (from `Runes`) — CC/IS-style buffs. Frontend renders
 /// `key` as the effect, `difficulty`/`profession` as scope, blackboard as data.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Modifier {
     pub key: String,
@@ -284,7 +287,7 @@ pub struct Modifier {
     pub blackboard: Vec<ModifierValue>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ModifierValue {
     pub key: String,
@@ -316,6 +319,8 @@ pub struct RosterEntry {
     pub motion: String,
     /// Total spawned across all waves (0 = declared/summon-only).
     pub count: u32,
+    /// `enemy_database` level entry this stage uses for the enemy's stats.
+    pub stat_level: i32,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -345,6 +350,74 @@ pub struct StageMap {
     pub duration: f64,
 }
 
+/// Difficulty masks a level rune can be scoped to. `FOUR_STAR` is challenge
+/// mode.
+pub const DIFFICULTIES: [&str; 4] = ["NORMAL", "FOUR_STAR", "EASY", "SIX_STAR"];
+
+/// The [`DIFFICULTIES`] entry `s` names, case-insensitively.
+pub fn parse_difficulty(s: &str) -> Option<&'static str> {
+    DIFFICULTIES
+        .into_iter()
+        .find(|d| d.eq_ignore_ascii_case(s.trim()))
+}
+
+impl Modifier {
+    /// Whether this rune's `difficultyMask` covers `difficulty`.
+    pub fn in_difficulty(&self, difficulty: &str) -> bool {
+        let mask = self.difficulty.trim();
+        mask.is_empty()
+            || mask == "ALL"
+            || mask
+                .split([',', '|'])
+                .any(|m| m.trim().eq_ignore_ascii_case(difficulty))
+    }
+
+    /// Apply this modifier to one enemy's attributes. Only the enemy attribute
+    /// runes (`enemy_attribute_mul` / `enemy_attribute_add`) touch enemy stats;
+    /// an `enemy` blackboard entry (`|`-separated ids) narrows which enemies
+    /// they hit, otherwise they hit every enemy in the stage.
+    pub fn apply_to_enemy(&self, enemy_id: &str, attrs: &mut EnemyAttributes) {
+        let multiply = match self.key.as_str() {
+            "enemy_attribute_mul" => true,
+            "enemy_attribute_add" => false,
+            _ => return,
+        };
+        if !self.targets_enemy(enemy_id) {
+            return;
+        }
+        for entry in &self.blackboard {
+            let apply = |stat: f64| {
+                if multiply {
+                    stat * entry.value
+                } else {
+                    stat + entry.value
+                }
+            };
+            match entry.key.as_str() {
+                "max_hp" => attrs.max_hp = apply(f64::from(attrs.max_hp)).round() as i32,
+                "atk" => attrs.atk = apply(f64::from(attrs.atk)).round() as i32,
+                "def" => attrs.def = apply(f64::from(attrs.def)).round() as i32,
+                "magic_resistance" => attrs.magic_resistance = apply(attrs.magic_resistance),
+                "move_speed" => attrs.move_speed = apply(attrs.move_speed),
+                "attack_speed" => attrs.attack_speed = apply(attrs.attack_speed),
+                "base_attack_time" => attrs.base_attack_time = apply(attrs.base_attack_time),
+                "hp_recovery_per_sec" => {
+                    attrs.hp_recovery_per_sec = apply(attrs.hp_recovery_per_sec)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn targets_enemy(&self, enemy_id: &str) -> bool {
+        self.blackboard
+            .iter()
+            .find(|b| b.key == "enemy")
+            .and_then(|b| b.value_str.as_deref())
+            .is_none_or(|ids| ids.split('|').any(|id| id.trim() == enemy_id))
+    }
+}
+
 // ============================================================================
 // Builder
 // ============================================================================
@@ -429,8 +502,10 @@ pub fn parse_stage_map(
     };
 
     let mut counts: HashMap<String, u32> = HashMap::new();
+    let mut stat_levels: HashMap<String, i32> = HashMap::new();
     for r in &raw.enemy_db_refs {
         counts.entry(r.id.clone()).or_insert(0);
+        stat_levels.insert(r.id.clone(), r.level);
     }
 
     // ── Wave/spawn timeline (visual approximation of the battle scheduler) ─
@@ -528,6 +603,7 @@ pub fn parse_stage_map(
                 attack_type: e.and_then(|e| e.attack_type.clone()),
                 motion: e.and_then(enemy_motion).unwrap_or_else(|| "WALK".into()),
                 count,
+                stat_level: stat_levels.get(&id).copied().unwrap_or(0),
                 enemy_id: id,
             }
         })
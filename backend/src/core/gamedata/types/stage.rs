@@ -38,6 +38,18 @@ pub enum StageDifficulty {
     Unknown,
 }
 
+impl StageDifficulty {
+    /// The level-modifier difficulty (`Modifier::in_difficulty`) the stage
+    /// runs at; unknown modes count as normal.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::FourStar => "FOUR_STAR",
+            Self::SixStar => "SIX_STAR",
+            Self::Normal | Self::Unknown => "NORMAL",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum AppearanceStyle {
@@ -0,0 +1,384 @@
+//! Structured diff between two [`GameData`] loads: a server before and after
+//! a hot reload, or two servers against each other.
+//!
+//! Entities are compared as their serialized JSON, so every field the API
+//! serves is covered without per-type code. Arrays of `{key, ...}` objects
+//! (skill and module blackboards) are matched by `key` rather than index, so a
+//! reordered blackboard doesn't read as a change and paths stay stable:
+//! `levels[9].blackboard[atk_scale].value`.
+
+use std::collections::{BTreeSet, HashMap};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::core::gamedata::types::GameData;
+
+/// Operator fields left out of the operator section: skills and modules have
+/// sections of their own, the rest are art, audio and lore.
+const OPERATOR_IGNORED: [&str; 8] = [
+    "skills", "modules", "audio", "portrait", "skin", "handbook", "profile", "artists",
+];
+
+/// Fields holding localized text, skipped unless [`DiffOptions::text`] is set.
+const TEXT_FIELDS: [&str; 21] = [
+    "ability",
+    "additionalDescription",
+    "appellation",
+    "attackType",
+    "buffName",
+    "dangerLevel",
+    "desc",
+    "description",
+    "itemDesc",
+    "itemObtainApproach",
+    "itemUsage",
+    "name",
+    "obtainApproach",
+    "overrideDescription",
+    "raceName",
+    "subProfessionName",
+    "text",
+    "uniEquipDesc",
+    "uniEquipName",
+    "upgradeDescription",
+    "usage",
+];
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DiffOptions {
+    /// Compare localized text fields ([`TEXT_FIELDS`]). Off between servers,
+    /// where every name and description differs by language; other string
+    /// fields (ids, enums) are always compared.
+    pub text: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldChange {
+    pub path: String,
+    /// `None` when the field is new.
+    pub old: Option<Value>,
+    /// `None` when the field was removed.
+    pub new: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntityRef {
+    pub id: String,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntityChange {
+    pub id: String,
+    pub name: Option<String>,
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SectionDiff {
+    pub added: Vec<EntityRef>,
+    pub removed: Vec<EntityRef>,
+    pub changed: Vec<EntityChange>,
+}
+
+impl SectionDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameDataDiff {
+    pub operators: SectionDiff,
+    pub skills: SectionDiff,
+    /// Module battle data (stat bonuses, trait and talent overrides).
+    pub modules: SectionDiff,
+    pub stages: SectionDiff,
+    pub enemies: SectionDiff,
+    pub items: SectionDiff,
+}
+
+impl GameDataDiff {
+    pub fn is_empty(&self) -> bool {
+        [
+            &self.operators,
+            &self.skills,
+            &self.modules,
+            &self.stages,
+            &self.enemies,
+            &self.items,
+        ]
+        .iter()
+        .all(|s| s.is_empty())
+    }
+}
+
+/// Changes going from `old` to `new`.
+pub fn diff(old: &GameData, new: &GameData, opts: DiffOptions) -> GameDataDiff {
+    let operator_name = |gd: &GameData, id: &str| gd.operators.get(id).map(|o| o.name.clone());
+    let skill_name = |gd: &GameData, id: &str| {
+        gd.skills
+            .get(id)
+            .and_then(|s| s.levels.first())
+            .map(|l| l.name.clone())
+    };
+    let module_name = |gd: &GameData, id: &str| {
+        gd.modules
+            .equip_dict
+            .get(id)
+            .map(|m| m.uni_equip_name.clone())
+    };
+    let stage_name = |gd: &GameData, id: &str| gd.stages.get(id).map(|s| s.code.clone());
+    let enemy_name =
+        |gd: &GameData, id: &str| gd.enemies.enemy_data.get(id).map(|e| e.name.clone());
+    let item_name = |gd: &GameData, id: &str| gd.materials.items.get(id).map(|i| i.name.clone());
+
+    let ctx = Context { old, new, opts };
+    GameDataDiff {
+        operators: ctx.section(
+            &old.operators,
+            &new.operators,
+            &OPERATOR_IGNORED,
+            operator_name,
+        ),
+        skills: ctx.section(&old.skills, &new.skills, &[], skill_name),
+        modules: ctx.section(
+            &old.modules.battle_equip,
+            &new.modules.battle_equip,
+            &[],
+            module_name,
+        ),
+        stages: ctx.section(&old.stages, &new.stages, &[], stage_name),
+        enemies: ctx.section(
+            &old.enemies.enemy_data,
+            &new.enemies.enemy_data,
+            &[],
+            enemy_name,
+        ),
+        items: ctx.section(&old.materials.items, &new.materials.items, &[], item_name),
+    }
+}
+
+struct Context<'a> {
+    old: &'a GameData,
+    new: &'a GameData,
+    opts: DiffOptions,
+}
+
+impl Context<'_> {
+    fn section<T: Serialize>(
+        &self,
+        old: &HashMap<String, T>,
+        new: &HashMap<String, T>,
+        ignored: &[&str],
+        name: impl Fn(&GameData, &str) -> Option<String>,
+    ) -> SectionDiff {
+        let mut out = SectionDiff::default();
+        let ids: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+        for id in ids {
+            match (old.get(id), new.get(id)) {
+                (Some(_), None) => out.removed.push(EntityRef {
+                    id: id.clone(),
+                    name: name(self.old, id),
+                }),
+                (None, Some(_)) => out.added.push(EntityRef {
+                    id: id.clone(),
+                    name: name(self.new, id),
+                }),
+                (Some(a), Some(b)) => {
+                    let (mut a, mut b) = (to_value(a), to_value(b));
+                    for field in ignored {
+                        for v in [&mut a, &mut b] {
+                            if let Value::Object(m) = v {
+                                m.remove(*field);
+                            }
+                        }
+                    }
+                    let mut changes = Vec::new();
+                    walk("", &a, &b, self.opts, &mut changes);
+                    if !changes.is_empty() {
+                        out.changed.push(EntityChange {
+                            id: id.clone(),
+                            name: name(self.new, id),
+                            changes,
+                        });
+                    }
+                }
+                (None, None) => {}
+            }
+        }
+        out
+    }
+}
+
+fn to_value<T: Serialize>(v: &T) -> Value {
+    serde_json::to_value(v).unwrap_or(Value::Null)
+}
+
+/// The `key` of every element, when all elements are objects carrying one.
+fn keyed(items: &[Value]) -> Option<Vec<&str>> {
+    items
+        .iter()
+        .map(|v| v.get("key").and_then(Value::as_str))
+        .collect()
+}
+
+fn child(path: &str, segment: &str) -> String {
+    if path.is_empty() {
+        segment.to_owned()
+    } else {
+        format!("{path}.{segment}")
+    }
+}
+
+fn record(out: &mut Vec<FieldChange>, path: String, old: Option<&Value>, new: Option<&Value>) {
+    out.push(FieldChange {
+        path,
+        old: old.cloned(),
+        new: new.cloned(),
+    });
+}
+
+fn walk(path: &str, old: &Value, new: &Value, opts: DiffOptions, out: &mut Vec<FieldChange>) {
+    match (old, new) {
+        (Value::Object(a), Value::Object(b)) => {
+            let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+            for key in keys {
+                if !opts.text && TEXT_FIELDS.contains(&key.as_str()) {
+                    continue;
+                }
+                let sub = child(path, key);
+                match (a.get(key), b.get(key)) {
+                    (Some(x), Some(y)) => walk(&sub, x, y, opts, out),
+                    (x, y) => {
+                        // A null field and a missing one are the same thing.
+                        if x.is_none_or(Value::is_null) && y.is_none_or(Value::is_null) {
+                            continue;
+                        }
+                        record(out, sub, x, y);
+                    }
+                }
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            if let (Some(ka), Some(kb)) = (keyed(a), keyed(b)) {
+                let keys: BTreeSet<&str> = ka.iter().chain(kb.iter()).copied().collect();
+                for key in keys {
+                    let sub = format!("{path}[{key}]");
+                    let x = ka.iter().position(|k| *k == key).map(|i| &a[i]);
+                    let y = kb.iter().position(|k| *k == key).map(|i| &b[i]);
+                    match (x, y) {
+                        (Some(x), Some(y)) => walk(&sub, x, y, opts, out),
+                        (x, y) => record(out, sub, x, y),
+                    }
+                }
+            } else {
+                for i in 0..a.len().max(b.len()) {
+                    let sub = format!("{path}[{i}]");
+                    match (a.get(i), b.get(i)) {
+                        (Some(x), Some(y)) => walk(&sub, x, y, opts, out),
+                        (x, y) => record(out, sub, x, y),
+                    }
+                }
+            }
+        }
+        (a, b) if a == b => {}
+        (a, b) => record(out, path.to_owned(), Some(a), Some(b)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn changes(old: Value, new: Value, text: bool) -> Vec<(String, Option<Value>, Option<Value>)> {
+        let mut out = Vec::new();
+        walk("", &old, &new, DiffOptions { text }, &mut out);
+        out.into_iter().map(|c| (c.path, c.old, c.new)).collect()
+    }
+
+    #[test]
+    fn blackboards_match_by_key() {
+        let old = json!({
+            "levels": [{
+                "name": "Old",
+                "blackboard": [{"key": "atk", "value": 1.0}, {"key": "def", "value": 0.5}]
+            }]
+        });
+        let new = json!({
+            "levels": [{
+                "name": "New",
+                "blackboard": [
+                    {"key": "def", "value": 0.5},
+                    {"key": "atk", "value": 1.2},
+                    {"key": "hp", "value": 2.0}
+                ]
+            }]
+        });
+
+        let found = changes(old.clone(), new.clone(), false);
+        assert_eq!(
+            found,
+            [
+                (
+                    "levels[0].blackboard[atk].value".to_owned(),
+                    Some(json!(1.0)),
+                    Some(json!(1.2))
+                ),
+                (
+                    "levels[0].blackboard[hp]".to_owned(),
+                    None,
+                    Some(json!({"key": "hp", "value": 2.0}))
+                ),
+            ]
+        );
+
+        let with_text = changes(old, new, true);
+        assert_eq!(with_text.len(), 3);
+        assert_eq!(with_text[2].0, "levels[0].name");
+    }
+
+    #[test]
+    fn non_text_strings_diff_without_text() {
+        let old = json!({"name": "Old", "rangeId": "1-1", "rarity": "TIER_5"});
+        let new = json!({"name": "New", "rangeId": "3-1", "rarity": "TIER_6"});
+
+        let found: Vec<String> = changes(old, new, false)
+            .into_iter()
+            .map(|(path, _, _)| path)
+            .collect();
+        assert_eq!(found, ["rangeId", "rarity"]);
+    }
+
+    #[test]
+    fn sections_report_added_removed_changed() {
+        use crate::core::gamedata::types::material::Item;
+
+        let item = |id: &str, name: &str, sort_id: i32| Item {
+            item_id: id.into(),
+            name: name.into(),
+            sort_id,
+            ..Item::default()
+        };
+        let mut old = GameData::default();
+        let mut new = GameData::default();
+        old.materials.items.insert("a".into(), item("a", "A", 1));
+        old.materials.items.insert("b".into(), item("b", "B", 1));
+        new.materials.items.insert("b".into(), item("b", "B", 2));
+        new.materials.items.insert("c".into(), item("c", "C", 1));
+
+        let d = diff(&old, &new, DiffOptions::default());
+        assert_eq!(d.items.removed[0].id, "a");
+        assert_eq!(d.items.added[0].name.as_deref(), Some("C"));
+        assert_eq!(d.items.changed[0].changes[0].path, "sortId");
+        assert!(d.operators.is_empty() && !d.is_empty());
+    }
+}
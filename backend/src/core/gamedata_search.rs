@@ -0,0 +1,551 @@
+//! In-memory search over one server's [`GameData`]: operator names and
+//! appellations, skill and talent descriptions, item and enemy names, stage
+//! codes and skin names. Built per server on load and on every hot reload, so
+//! each index searches the text in that server's language.
+//!
+//! Names and codes are scored against the whole query (exact, prefix, word
+//! prefix, substring, then a bounded edit distance for typos). Descriptions
+//! go through a token index instead: every query token must appear, the last
+//! one as a prefix so results show up while typing. Latin text tokenizes on
+//! words; CJK runs have no spaces and tokenize into overlapping bigrams.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::gamedata::types::GameData;
+
+/// Hits returned when the caller doesn't ask for a count.
+pub const DEFAULT_LIMIT: usize = 20;
+pub const MAX_LIMIT: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchKind {
+    Operator,
+    Skill,
+    Talent,
+    Item,
+    Enemy,
+    Stage,
+    Skin,
+}
+
+impl SearchKind {
+    pub fn parse(s: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(s.trim().to_lowercase())).ok()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MatchedField {
+    Name,
+    /// Operator appellation (the codename the other servers use).
+    Alias,
+    /// Stage code or enemy index.
+    Code,
+    Description,
+}
+
+impl MatchedField {
+    fn weight(self) -> f64 {
+        match self {
+            Self::Name | Self::Code => 1.0,
+            Self::Alias => 0.95,
+            Self::Description => 0.4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub kind: SearchKind,
+    pub id: String,
+    pub name: String,
+    /// Operator a skill, talent or skin belongs to.
+    pub operator_id: Option<String>,
+    pub field: MatchedField,
+    /// The matched text with markup removed.
+    pub text: String,
+    /// 0-1; 1 is an exact name match.
+    pub score: f64,
+}
+
+struct Field {
+    field: MatchedField,
+    text: String,
+    norm: String,
+}
+
+struct Entry {
+    kind: SearchKind,
+    id: String,
+    name: String,
+    operator_id: Option<String>,
+    fields: Vec<Field>,
+}
+
+#[derive(Default)]
+pub struct SearchIndex {
+    entries: Vec<Entry>,
+    /// Description token -> `(entry, field)` pairs, ordered for prefix scans.
+    tokens: BTreeMap<String, Vec<(u32, u16)>>,
+}
+
+/// Drop `<@tag>` / `</>` markup and `{blackboard:0%}` placeholders.
+fn plain_text(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut close = None;
+    for c in s.chars() {
+        match (close, c) {
+            (None, '<') => close = Some('>'),
+            (None, '{') => close = Some('}'),
+            (None, _) => out.push(c),
+            (Some(end), _) if c == end => close = None,
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Lowercase, punctuation to single spaces.
+fn normalize(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            out.push(c);
+        } else if !out.is_empty() && !out.ends_with(' ') {
+            out.push(' ');
+        }
+    }
+    let len = out.trim_end().len();
+    out.truncate(len);
+    out
+}
+
+/// Scripts written without spaces: CJK ideographs, kana, hangul.
+fn is_cjk(c: char) -> bool {
+    matches!(c, '\u{3040}'..='\u{30ff}' | '\u{3400}'..='\u{9fff}' | '\u{ac00}'..='\u{d7af}' | '\u{f900}'..='\u{faff}')
+}
+
+/// Words of normalized text, with CJK runs split into bigrams (a lone CJK
+/// character stays a unigram).
+fn tokens(norm: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    for word in norm.split(' ') {
+        let chars: Vec<char> = word.chars().collect();
+        for run in chars.chunk_by(|a, b| is_cjk(*a) == is_cjk(*b)) {
+            if is_cjk(run[0]) && run.len() > 1 {
+                out.extend(run.windows(2).map(|w| w.iter().collect::<String>()));
+            } else {
+                out.push(run.iter().collect());
+            }
+        }
+    }
+    out
+}
+
+/// Levenshtein distance, giving up (`None`) once it exceeds `max`.
+fn edit_distance(a: &[char], b: &[char], max: usize) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        cur[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let sub = prev[j] + usize::from(ca != cb);
+            cur[j + 1] = sub.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        if cur.iter().min().is_some_and(|&m| m > max) {
+            return None;
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    Some(prev[b.len()]).filter(|&d| d <= max)
+}
+
+/// Typos tolerated for a query of `len` characters.
+fn max_typos(len: usize) -> usize {
+    match len {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// How well a normalized name matches a normalized query, 0-1.
+fn name_score(query: &str, name: &str) -> Option<f64> {
+    if name.is_empty() {
+        return None;
+    }
+    if name == query {
+        return Some(1.0);
+    }
+    if name.starts_with(query) {
+        return Some(0.9);
+    }
+    let compact = |s: &str| s.replace(' ', "");
+    let (q, n) = (compact(query), compact(name));
+    if n == q {
+        return Some(0.95);
+    }
+    if n.starts_with(&q) {
+        return Some(0.85);
+    }
+    if name.split(' ').any(|w| w.starts_with(query)) {
+        return Some(0.8);
+    }
+    if n.contains(&q) {
+        return Some(0.7);
+    }
+
+    // Typos: against the whole name, then each word and the name's leading
+    // characters (a misspelt prefix of a long name).
+    let q: Vec<char> = q.chars().collect();
+    let max = max_typos(q.len());
+    if max == 0 {
+        return None;
+    }
+    let n: Vec<char> = n.chars().collect();
+    let head = &n[..n.len().min(q.len())];
+    let best = std::iter::once(n.as_slice())
+        .chain(std::iter::once(head))
+        .filter_map(|cand| edit_distance(&q, cand, max))
+        .chain(
+            name.split(' ')
+                .filter_map(|w| edit_distance(&q, &w.chars().collect::<Vec<_>>(), max)),
+        )
+        .min()?;
+    Some(0.6 - 0.1 * best as f64)
+}
+
+impl SearchIndex {
+    pub fn build(gd: &GameData) -> Self {
+        let mut index = Self::default();
+        let mut skill_owner: HashMap<&str, &str> = HashMap::new();
+
+        for (id, op) in &gd.operators {
+            let mut fields = vec![(MatchedField::Name, op.name.as_str())];
+            if !op.appellation.trim().is_empty() && op.appellation != op.name {
+                fields.push((MatchedField::Alias, op.appellation.as_str()));
+            }
+            index.push(SearchKind::Operator, id, &op.name, None, &fields);
+
+            for skill in &op.skills {
+                skill_owner.entry(&skill.skill_id).or_insert(id);
+            }
+            for (i, talent) in op.talents.iter().enumerate() {
+                // The last candidate is the fully unlocked talent.
+                let Some(c) = talent.candidates.last() else {
+                    continue;
+                };
+                let name = c.name.as_deref().unwrap_or_default();
+                let Some(desc) = c.description.as_deref() else {
+                    continue;
+                };
+                index.push(
+                    SearchKind::Talent,
+                    &format!("{id}#{i}"),
+                    name,
+                    Some(id),
+                    &[
+                        (MatchedField::Name, name),
+                        (MatchedField::Description, desc),
+                    ],
+                );
+            }
+        }
+
+        for (id, skill) in &gd.skills {
+            let Some(level) = skill.levels.last() else {
+                continue;
+            };
+            index.push(
+                SearchKind::Skill,
+                id,
+                &level.name,
+                skill_owner.get(id.as_str()).copied(),
+                &[
+                    (MatchedField::Name, &level.name),
+                    (MatchedField::Description, &level.description),
+                ],
+            );
+        }
+
+        for (id, item) in &gd.materials.items {
+            index.push(
+                SearchKind::Item,
+                id,
+                &item.name,
+                None,
+                &[(MatchedField::Name, &item.name)],
+            );
+        }
+
+        for (id, enemy) in &gd.enemies.enemy_data {
+            index.push(
+                SearchKind::Enemy,
+                id,
+                &enemy.name,
+                None,
+                &[
+                    (MatchedField::Name, &enemy.name),
+                    (MatchedField::Code, &enemy.enemy_index),
+                ],
+            );
+        }
+
+        for (id, stage) in &gd.stages {
+            let name = stage.name.as_deref().unwrap_or_default();
+            index.push(
+                SearchKind::Stage,
+                id,
+                &stage.code,
+                None,
+                &[
+                    (MatchedField::Code, &stage.code),
+                    (MatchedField::Name, name),
+                ],
+            );
+        }
+
+        for (id, skin) in &gd.skins.char_skins {
+            // Default outfits carry no skin name.
+            let Some(name) = skin.display_skin.skin_name.as_deref() else {
+                continue;
+            };
+            index.push(
+                SearchKind::Skin,
+                id,
+                name,
+                Some(&skin.char_id),
+                &[(MatchedField::Name, name)],
+            );
+        }
+
+        for postings in index.tokens.values_mut() {
+            postings.dedup();
+        }
+        index
+    }
+
+    fn push(
+        &mut self,
+        kind: SearchKind,
+        id: &str,
+        name: &str,
+        operator_id: Option<&str>,
+        fields: &[(MatchedField, &str)],
+    ) {
+        let entry = self.entries.len() as u32;
+        let fields: Vec<Field> = fields
+            .iter()
+            .map(|&(field, text)| {
+                let text = plain_text(text);
+                Field {
+                    field,
+                    norm: normalize(&text),
+                    text,
+                }
+            })
+            .filter(|f| !f.norm.is_empty())
+            .collect();
+        if fields.is_empty() {
+            return;
+        }
+        for (i, f) in fields.iter().enumerate() {
+            if f.field == MatchedField::Description {
+                for token in tokens(&f.norm) {
+                    self.tokens
+                        .entry(token)
+                        .or_default()
+                        .push((entry, i as u16));
+                }
+            }
+        }
+        self.entries.push(Entry {
+            kind,
+            id: id.to_owned(),
+            name: plain_text(name),
+            operator_id: operator_id.map(str::to_owned),
+            fields,
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Description fields holding every query token (the last as a prefix).
+    fn description_matches(&self, query: &str) -> Vec<(u32, u16)> {
+        let mut tokens = tokens(query);
+        let Some(last) = tokens.pop() else {
+            return Vec::new();
+        };
+        let mut found: Vec<(u32, u16)> = self
+            .tokens
+            .range(last.clone()..)
+            .take_while(|(t, _)| t.starts_with(&last))
+            .flat_map(|(_, p)| p.iter().copied())
+            .collect();
+        found.sort_unstable();
+        found.dedup();
+        for token in &tokens {
+            let Some(postings) = self.tokens.get(token) else {
+                return Vec::new();
+            };
+            found.retain(|p| postings.binary_search(p).is_ok());
+        }
+        found
+    }
+
+    /// Best hits for `query`, optionally restricted to `kinds`; one hit per
+    /// entity, on its best-scoring field.
+    pub fn search(&self, query: &str, kinds: &[SearchKind], limit: usize) -> Vec<SearchHit> {
+        let query = normalize(&plain_text(query));
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let wanted = |kind| kinds.is_empty() || kinds.contains(&kind);
+
+        let mut best: HashMap<u32, (f64, u16)> = HashMap::new();
+        for (i, entry) in self.entries.iter().enumerate() {
+            if !wanted(entry.kind) {
+                continue;
+            }
+            for (f, field) in entry.fields.iter().enumerate() {
+                if field.field == MatchedField::Description {
+                    continue;
+                }
+                if let Some(s) = name_score(&query, &field.norm) {
+                    let s = s * field.field.weight();
+                    let slot = best.entry(i as u32).or_insert((0.0, 0));
+                    if s > slot.0 {
+                        *slot = (s, f as u16);
+                    }
+                }
+            }
+        }
+        for (i, f) in self.description_matches(&query) {
+            if !wanted(self.entries[i as usize].kind) {
+                continue;
+            }
+            let s = MatchedField::Description.weight();
+            let slot = best.entry(i).or_insert((0.0, f));
+            if s > slot.0 {
+                *slot = (s, f);
+            }
+        }
+
+        let mut ranked: Vec<(u32, f64, u16)> =
+            best.into_iter().map(|(i, (s, f))| (i, s, f)).collect();
+        ranked.sort_by(|a, b| {
+            let (ea, eb) = (&self.entries[a.0 as usize], &self.entries[b.0 as usize]);
+            b.1.total_cmp(&a.1)
+                .then(ea.kind.cmp(&eb.kind))
+                .then(ea.name.len().cmp(&eb.name.len()))
+                .then(ea.id.cmp(&eb.id))
+        });
+        ranked
+            .into_iter()
+            .take(limit)
+            .map(|(i, score, f)| {
+                let entry = &self.entries[i as usize];
+                let field = &entry.fields[f as usize];
+                SearchHit {
+                    kind: entry.kind,
+                    id: entry.id.clone(),
+                    name: entry.name.clone(),
+                    operator_id: entry.operator_id.clone(),
+                    field: field.field,
+                    text: field.text.clone(),
+                    score: (score * 1000.0).round() / 1000.0,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::gamedata::types::material::Item;
+    use crate::core::gamedata::types::operator::Operator;
+    use crate::core::gamedata::types::skill::{Skill, SkillLevel};
+
+    fn index() -> SearchIndex {
+        let mut gd = GameData::default();
+        gd.operators.insert(
+            "char_1016_agoat2".into(),
+            Operator {
+                name: "Eyjafjalla the Hvít Aska".into(),
+                appellation: "Eyjafjalla".into(),
+                ..Operator::default()
+            },
+        );
+        gd.operators.insert(
+            "char_180_amgoat".into(),
+            Operator {
+                name: "Eyjafjalla".into(),
+                appellation: "Eyjafjalla".into(),
+                ..Operator::default()
+            },
+        );
+        gd.skills.insert(
+            "skchr_amgoat_2".into(),
+            Skill {
+                levels: vec![SkillLevel {
+                    name: "Volcano".into(),
+                    description: "<@ba.vup>ATK</> +{atk:0%}, attacks deal <@ba.vup>Arts damage</> to 攻击范围内 enemies".into(),
+                    ..SkillLevel::default()
+                }],
+                ..Skill::default()
+            },
+        );
+        gd.materials.items.insert(
+            "30013".into(),
+            Item {
+                name: "Orirock Cube".into(),
+                ..Item::default()
+            },
+        );
+        SearchIndex::build(&gd)
+    }
+
+    #[test]
+    fn names_rank_exact_then_prefix_then_typos() {
+        let index = index();
+        let hits = index.search("eyjafjalla", &[], 10);
+        assert_eq!(hits[0].id, "char_180_amgoat");
+        assert_eq!(hits[0].score, 1.0);
+        assert_eq!(hits[1].id, "char_1016_agoat2");
+
+        let typo = index.search("orirok cube", &[], 10);
+        assert_eq!(typo[0].id, "30013");
+        assert!(typo[0].score < 0.7);
+
+        let only_items = index.search("eyja", &[SearchKind::Item], 10);
+        assert!(only_items.is_empty());
+    }
+
+    #[test]
+    fn descriptions_match_every_token() {
+        let index = index();
+        let hits = index.search("arts dam", &[], 10);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].kind, SearchKind::Skill);
+        assert_eq!(hits[0].field, MatchedField::Description);
+        assert!(!hits[0].text.contains("<@"));
+
+        assert_eq!(index.search("范围", &[], 10).len(), 1);
+        assert!(index.search("arts heal", &[], 10).is_empty());
+    }
+}
@@ -722,7 +722,10 @@ pub fn morale_recovery(building: &UserBuilding) -> f64 {
 /// A staggered rotation only keeps its workers fresh if the dorms can hold everyone
 /// who needs to rest at a time (the "you can only rest 20, so you run 3 overlapping
 /// sets" reality); when they can't, sustained output is throttled.
-fn total_dorm_capacity(building: &UserBuilding, building_data: &BuildingDataFile) -> i32 {
+pub(crate) fn total_dorm_capacity(
+    building: &UserBuilding,
+    building_data: &BuildingDataFile,
+) -> i32 {
     building
         .rooms
         .iter()
@@ -821,7 +824,10 @@ fn cc_morale_boost(
 
 /// An operator's total morale drain per hour (base + its skills' modifiers).
 /// Lower = works longer before resting = better for sustained rotation.
-fn op_morale_drain(op: &OperatorBaseProfile, morale_drains: &HashMap<String, f64>) -> f64 {
+pub(crate) fn op_morale_drain(
+    op: &OperatorBaseProfile,
+    morale_drains: &HashMap<String, f64>,
+) -> f64 {
     let modifier: f64 = op
         .available_buffs
         .iter()
@@ -854,6 +860,10 @@ pub(crate) fn shared_bench_size(rest_demand: f64) -> usize {
 /// out (~a daily rotation); faster-draining operators last proportionally fewer.
 const ROTATION_BASE_HOURS: f64 = 24.0;
 
+/// A fully-rested operator's morale on this model's scale: what a neutral-drain
+/// operator burns over `ROTATION_BASE_HOURS`.
+pub(crate) const FULL_MORALE: f64 = ROTATION_BASE_HOURS * BASE_MORALE_DRAIN;
+
 /// Approximate hours an operator works before needing a rotation, rounded to the
 /// hour - inversely proportional to its morale drain.
 fn op_lasts_hours(op: &OperatorBaseProfile, morale_drains: &HashMap<String, f64>) -> f64 {
@@ -1457,7 +1467,7 @@ const BASE_DRONE_CAPACITY: usize = 100;
 /// Per-level max-Drone grant of a Power Plant (L1/L2/L3) - client-side game constants.
 const POWER_PLANT_DRONE_CAPACITY: [usize; 3] = [20, 30, 45];
 /// Synthetic `facility_counts` key holding the base's max Drone capacity.
-const DRONE_CAPACITY: &str = "DRONE_CAPACITY";
+pub(crate) const DRONE_CAPACITY: &str = "DRONE_CAPACITY";
 
 /// Synthetic `facility_counts` key holding the number of DISTINCT recipe types the factories run
 /// (not the factory count) - the quantity Quartz's recipe-type trading scaler reads.
@@ -3625,7 +3635,7 @@ const SUSTAINED_AFK_DAYS: f64 = 1.5;
 const GOLD_PRODUCTS_PER_DAY_BASE: f64 = 20.0;
 
 /// A factory level's base product-buffer capacity (`OutputCapacity` in gamedata).
-const fn gold_base_capacity(level: i32) -> f64 {
+pub(crate) const fn gold_base_capacity(level: i32) -> f64 {
     match level {
         1 => 24.0,
         2 => 36.0,
@@ -0,0 +1,674 @@
+//! Hour-by-hour simulation of a shift rotation over 24-72 hours.
+//!
+//! The optimizers work on steady-state figures (`op_uptime`, `morale_recovery`,
+//! `yield_model`): they assume every operator's rest is perfectly interleaved
+//! with its work. This steps a `ShiftRotation` through real time instead, so a
+//! recommendation can be checked against the player's actual dorms:
+//!
+//! - Working operators burn morale at their drain rate; at 0 they are exhausted
+//!   and sit out until fully rested. Operators off shift rest in the dorms,
+//!   lowest morale first, only as many as the dorms have beds for. Operators a
+//!   morale-swap manager sustains stay at full morale.
+//! - Factories feed a shared gold stock capped by their product buffers (a
+//!   full buffer stalls them); trading posts sell out of that stock.
+//! - Drones regenerate (faster with staffed power plants), are capped by the
+//!   base's drone capacity, and are spent at each 12h login on the best room of
+//!   the chosen type.
+//!
+//! Everything starts fresh at hour 0: full morale, no gold, no drones.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::core::gamedata::types::building::BuildingDataFile;
+
+use super::{
+    assignment::{
+        DRONE_CAPACITY, FULL_MORALE, build_op_index, compute_team_efficiency,
+        effective_facility_counts, gold_base_capacity, morale_recovery, op_morale_drain,
+        total_dorm_capacity,
+    },
+    buff_registry::BuffResolutionStrategy,
+    shift_rotation::ShiftRotation,
+    types::{OperatorBaseProfile, UserBuilding},
+    yield_model::{GOLD_BAR_LMD, room_yield},
+};
+
+/// Hours per shift (two logins a day).
+const SHIFT_HOURS: usize = 12;
+/// Drones regenerated per hour with no power-plant bonus - a client-side game
+/// constant (one every six minutes).
+const BASE_DRONES_PER_HOUR: f64 = 10.0;
+/// Hours of production one drone skips ahead (3 minutes).
+const DRONE_HOURS: f64 = 3.0 / 60.0;
+
+pub const MIN_SIMULATION_HOURS: usize = 24;
+pub const MAX_SIMULATION_HOURS: usize = 72;
+
+/// Which room type drones are spent on at each login.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DroneTarget {
+    #[default]
+    Trading,
+    Manufacture,
+    None,
+}
+
+impl DroneTarget {
+    fn room_type(self) -> Option<&'static str> {
+        match self {
+            Self::Trading => Some("TRADING"),
+            Self::Manufacture => Some("MANUFACTURE"),
+            Self::None => None,
+        }
+    }
+}
+
+pub struct SimConfig {
+    pub hours: usize,
+    /// Per-hour morale recovery in a dorm bed.
+    pub recovery: f64,
+    /// Dorm beds available at once.
+    pub dorm_capacity: usize,
+    pub drone_capacity: f64,
+    pub drone_target: DroneTarget,
+}
+
+/// One room's crew for one shift.
+pub struct SimCell {
+    pub crew: Vec<String>,
+    /// False when the rotation leaves the room dark this shift.
+    pub active: bool,
+    /// Efficiency % the room gets from outside its crew (Control-Center globals).
+    pub bonus_pct: f64,
+}
+
+pub struct SimRoom {
+    pub slot_id: String,
+    pub room_type: String,
+    pub formula: Option<String>,
+    pub level: i32,
+    /// One cell per rotation shift.
+    pub shifts: Vec<SimCell>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdleReason {
+    /// The rotation leaves the room empty this shift.
+    Unstaffed,
+    /// Everyone scheduled is out of morale.
+    Exhausted,
+    /// Factory buffer full - the trading posts can't keep up.
+    Stalled,
+    /// Trading post with no gold to sell.
+    NoGold,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IdleWindow {
+    pub start_hour: usize,
+    /// Exclusive.
+    pub end_hour: usize,
+    pub reason: IdleReason,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoomTimeline {
+    pub slot_id: String,
+    pub room_type: String,
+    pub formula: Option<String>,
+    pub idle: Vec<IdleWindow>,
+    pub gold: f64,
+    pub exp: f64,
+    pub lmd: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OperatorTimeline {
+    pub char_id: String,
+    /// Morale at the end of each hour.
+    pub morale: Vec<f64>,
+    pub lowest_morale: f64,
+    pub worked_hours: usize,
+    pub exhausted_hours: usize,
+    /// First hour the operator was scheduled but had no morale left to work.
+    pub first_exhausted_hour: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HourSnapshot {
+    pub hour: usize,
+    /// 1-indexed rotation shift.
+    pub shift: usize,
+    pub lmd: f64,
+    pub gold_produced: f64,
+    pub gold_sold: f64,
+    /// Unsold gold at the end of the hour.
+    pub gold_stock: f64,
+    pub exp: f64,
+    /// Drones on hand at the end of the hour.
+    pub drones: f64,
+    pub drones_used: f64,
+    pub resting: usize,
+    /// Operators off shift and below full morale with no dorm bed.
+    pub waiting_for_bed: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BaseSimulation {
+    pub hours: Vec<HourSnapshot>,
+    pub rooms: Vec<RoomTimeline>,
+    pub operators: Vec<OperatorTimeline>,
+    pub total_lmd: f64,
+    pub total_exp: f64,
+    pub drones_generated: f64,
+    pub drones_used: f64,
+    /// Drones lost to a full drone pool.
+    pub drones_wasted: f64,
+    pub dorm_capacity: usize,
+    /// No operator was ever too tired to work a shift it was scheduled for.
+    pub holds_up: bool,
+}
+
+/// Simulate `rotation` on `building`, with crews from the recommendation or,
+/// when `use_presets`, the player's saved presets.
+#[allow(clippy::too_many_arguments)]
+pub fn simulate_rotation(
+    rotation: &ShiftRotation,
+    use_presets: bool,
+    operators: &[OperatorBaseProfile],
+    building: &UserBuilding,
+    building_data: &BuildingDataFile,
+    registry: &HashMap<String, BuffResolutionStrategy>,
+    morale_drains: &HashMap<String, f64>,
+    hours: usize,
+    drone_target: DroneTarget,
+) -> BaseSimulation {
+    let op_index = build_op_index(operators);
+    let facility_counts = effective_facility_counts(building, operators, registry);
+    let total_dorm_levels = building.total_dorm_levels();
+    let efficiency = |room_type: &str, formula: Option<&str>, crew: &[String]| {
+        compute_team_efficiency(
+            crew,
+            room_type,
+            formula,
+            &op_index,
+            registry,
+            building_data,
+            &facility_counts,
+            total_dorm_levels,
+            morale_drains,
+            &[],
+        )
+    };
+
+    let rooms = rooms_from_rotation(rotation, building, use_presets, &|room, crew| {
+        efficiency(&room.room_type, room.formula.as_deref(), crew).0
+    });
+    let drains: HashMap<String, f64> = operators
+        .iter()
+        .map(|o| (o.char_id.clone(), op_morale_drain(o, morale_drains)))
+        .collect();
+    let sustained: HashSet<String> = rotation.sustained.iter().cloned().collect();
+    let config = SimConfig {
+        hours,
+        recovery: morale_recovery(building),
+        dorm_capacity: total_dorm_capacity(building, building_data).max(0) as usize,
+        drone_capacity: facility_counts.get(DRONE_CAPACITY).copied().unwrap_or(0) as f64,
+        drone_target,
+    };
+
+    let mut cache: HashMap<(String, Vec<String>), (f64, f64)> = HashMap::new();
+    run(&rooms, &drains, &sustained, &config, &mut |room, crew| {
+        *cache
+            .entry((room.slot_id.clone(), crew.to_vec()))
+            .or_insert_with(|| efficiency(&room.room_type, room.formula.as_deref(), crew))
+    })
+}
+
+/// Regroup the rotation's shift-major cells into per-room timelines. A
+/// recommended cell's displayed efficiency includes Control-Center globals the
+/// crew alone doesn't; the difference becomes the cell's `bonus_pct`.
+fn rooms_from_rotation(
+    rotation: &ShiftRotation,
+    building: &UserBuilding,
+    use_presets: bool,
+    crew_speed: &dyn Fn(&SimRoom, &[String]) -> f64,
+) -> Vec<SimRoom> {
+    let mut rooms: Vec<SimRoom> = Vec::new();
+    for shift in &rotation.shifts {
+        for cell in &shift.rooms {
+            let idx = match rooms.iter().position(|r| r.slot_id == cell.slot_id) {
+                Some(i) => i,
+                None => {
+                    rooms.push(SimRoom {
+                        slot_id: cell.slot_id.clone(),
+                        room_type: cell.room_type.clone(),
+                        formula: cell.formula_type.clone(),
+                        level: building
+                            .rooms
+                            .iter()
+                            .find(|r| r.slot_id == cell.slot_id)
+                            .map_or(1, |r| r.level),
+                        shifts: Vec::new(),
+                    });
+                    rooms.len() - 1
+                }
+            };
+            let room = &rooms[idx];
+            let (crew, active) = if use_presets {
+                (cell.current.clone(), !cell.current.is_empty())
+            } else {
+                (cell.recommended.clone(), cell.active)
+            };
+            let bonus_pct = match cell.efficiency {
+                Some(shown) if !use_presets && active && !crew.is_empty() => {
+                    (shown - crew_speed(room, &crew)).max(0.0)
+                }
+                _ => 0.0,
+            };
+            rooms[idx].shifts.push(SimCell {
+                crew,
+                active,
+                bonus_pct,
+            });
+        }
+    }
+    rooms
+}
+
+/// Per-room output for one hour at `speed`/`value` %.
+struct HourYield {
+    gold: f64,
+    exp: f64,
+    /// Gold bars the post can sell.
+    sell: f64,
+}
+
+fn hourly_yield(room: &SimRoom, speed: f64, value: f64) -> HourYield {
+    let y = room_yield(
+        &room.room_type,
+        room.formula.as_deref(),
+        room.level,
+        speed,
+        value,
+    );
+    HourYield {
+        gold: y.gold_per_day / 24.0,
+        exp: y.exp_per_day / 24.0,
+        sell: y.lmd_per_day / GOLD_BAR_LMD / 24.0,
+    }
+}
+
+struct OpState {
+    morale: f64,
+    exhausted: bool,
+    timeline: OperatorTimeline,
+}
+
+/// Records idle hours for one room, merging consecutive hours with the same
+/// reason into one window.
+fn mark_idle(windows: &mut Vec<IdleWindow>, hour: usize, reason: IdleReason) {
+    if let Some(last) = windows.last_mut()
+        && last.end_hour == hour
+        && last.reason == reason
+    {
+        last.end_hour = hour + 1;
+        return;
+    }
+    windows.push(IdleWindow {
+        start_hour: hour,
+        end_hour: hour + 1,
+        reason,
+    });
+}
+
+/// A room's (speed %, order value %) for the crew actually present.
+pub type RoomEval<'a> = dyn FnMut(&SimRoom, &[String]) -> (f64, f64) + 'a;
+
+/// Step `rooms` hour by hour, evaluating crews with `eval`.
+pub fn run(
+    rooms: &[SimRoom],
+    drains: &HashMap<String, f64>,
+    sustained: &HashSet<String>,
+    config: &SimConfig,
+    eval: &mut RoomEval<'_>,
+) -> BaseSimulation {
+    let mut ops: Vec<OpState> = Vec::new();
+    let mut op_idx: HashMap<String, usize> = HashMap::new();
+    for crew in rooms.iter().flat_map(|r| r.shifts.iter().map(|c| &c.crew)) {
+        for id in crew {
+            op_idx.entry(id.clone()).or_insert_with(|| {
+                ops.push(OpState {
+                    morale: FULL_MORALE,
+                    exhausted: false,
+                    timeline: OperatorTimeline {
+                        char_id: id.clone(),
+                        morale: Vec::with_capacity(config.hours),
+                        lowest_morale: FULL_MORALE,
+                        worked_hours: 0,
+                        exhausted_hours: 0,
+                        first_exhausted_hour: None,
+                    },
+                });
+                ops.len() - 1
+            });
+        }
+    }
+
+    let gold_cap: f64 = rooms
+        .iter()
+        .filter(|r| r.room_type == "MANUFACTURE" && r.formula.as_deref() == Some("F_GOLD"))
+        .map(|r| gold_base_capacity(r.level))
+        .sum();
+
+    let mut timelines: Vec<RoomTimeline> = rooms
+        .iter()
+        .map(|r| RoomTimeline {
+            slot_id: r.slot_id.clone(),
+            room_type: r.room_type.clone(),
+            formula: r.formula.clone(),
+            idle: Vec::new(),
+            gold: 0.0,
+            exp: 0.0,
+            lmd: 0.0,
+        })
+        .collect();
+    let mut snapshots = Vec::with_capacity(config.hours);
+    let (mut gold_stock, mut drones) = (0.0_f64, 0.0_f64);
+    let (mut drones_generated, mut drones_used_total, mut drones_wasted) = (0.0, 0.0, 0.0);
+
+    for hour in 0..config.hours {
+        let shift_count = rooms
+            .iter()
+            .map(|r| r.shifts.len())
+            .max()
+            .unwrap_or(1)
+            .max(1);
+        let shift = (hour / SHIFT_HOURS) % shift_count;
+
+        // Who is actually working each room this hour.
+        let mut working: HashSet<usize> = HashSet::new();
+        let mut present: Vec<Option<Vec<String>>> = Vec::with_capacity(rooms.len());
+        for (i, room) in rooms.iter().enumerate() {
+            let Some(cell) = room
+                .shifts
+                .get(shift)
+                .filter(|c| c.active && !c.crew.is_empty())
+            else {
+                mark_idle(&mut timelines[i].idle, hour, IdleReason::Unstaffed);
+                present.push(None);
+                continue;
+            };
+            let mut crew: Vec<String> = Vec::with_capacity(cell.crew.len());
+            for id in &cell.crew {
+                let Some(&o) = op_idx.get(id) else { continue };
+                if ops[o].exhausted {
+                    ops[o].timeline.first_exhausted_hour.get_or_insert(hour);
+                } else {
+                    crew.push(id.clone());
+                }
+            }
+            if crew.is_empty() {
+                mark_idle(&mut timelines[i].idle, hour, IdleReason::Exhausted);
+                present.push(None);
+                continue;
+            }
+            working.extend(crew.iter().filter_map(|id| op_idx.get(id).copied()));
+            present.push(Some(crew));
+        }
+
+        // Output, factories first so the posts can sell this hour's gold.
+        let mut speeds: Vec<Option<(f64, f64)>> = vec![None; rooms.len()];
+        for (i, room) in rooms.iter().enumerate() {
+            if let Some(crew) = &present[i] {
+                let (speed, value) = eval(room, crew);
+                speeds[i] = Some((speed + room.shifts[shift].bonus_pct, value));
+            }
+        }
+        let mut yields: Vec<Option<HourYield>> = rooms
+            .iter()
+            .zip(&speeds)
+            .map(|(room, s)| s.map(|(speed, value)| hourly_yield(room, speed, value)))
+            .collect();
+
+        let mut drones_used = 0.0;
+        if hour % SHIFT_HOURS == 0
+            && drones > 0.0
+            && let Some(target) = config.drone_target.room_type()
+        {
+            let best = rooms
+                .iter()
+                .enumerate()
+                .filter(|(i, r)| r.room_type == target && yields[*i].is_some())
+                .max_by(|(a, _), (b, _)| {
+                    let score =
+                        |i: usize| yields[i].as_ref().map_or(0.0, |y| y.gold + y.exp + y.sell);
+                    score(*a).total_cmp(&score(*b))
+                })
+                .map(|(i, _)| i);
+            if let Some(i) = best
+                && let Some(y) = yields[i].as_mut()
+            {
+                // Skipping ahead `drones * DRONE_HOURS` hours of this room's work.
+                let extra = 1.0 + drones * DRONE_HOURS;
+                y.gold *= extra;
+                y.exp *= extra;
+                y.sell *= extra;
+                drones_used = drones;
+                drones = 0.0;
+            }
+        }
+
+        let (mut gold_produced, mut exp, mut gold_sold, mut lmd) = (0.0, 0.0, 0.0, 0.0);
+        for (i, room) in rooms.iter().enumerate() {
+            let Some(y) = &yields[i] else { continue };
+            if room.room_type != "MANUFACTURE" {
+                continue;
+            }
+            if y.gold > 0.0 {
+                let made = y.gold.min((gold_cap - gold_stock).max(0.0));
+                if made <= 0.0 {
+                    mark_idle(&mut timelines[i].idle, hour, IdleReason::Stalled);
+                }
+                gold_stock += made;
+                gold_produced += made;
+                timelines[i].gold += made;
+            }
+            exp += y.exp;
+            timelines[i].exp += y.exp;
+        }
+        for (i, room) in rooms.iter().enumerate() {
+            let Some(y) = &yields[i] else { continue };
+            if room.room_type != "TRADING" {
+                continue;
+            }
+            let sold = y.sell.min(gold_stock);
+            if sold <= 0.0 {
+                mark_idle(&mut timelines[i].idle, hour, IdleReason::NoGold);
+            }
+            gold_stock -= sold;
+            gold_sold += sold;
+            lmd += sold * GOLD_BAR_LMD;
+            timelines[i].lmd += sold * GOLD_BAR_LMD;
+        }
+
+        // Drones: staffed power plants speed regeneration.
+        let power_bonus: f64 = rooms
+            .iter()
+            .zip(&speeds)
+            .filter(|(r, _)| r.room_type == "POWER")
+            .filter_map(|(_, s)| s.map(|(speed, _)| speed))
+            .sum();
+        let regen = BASE_DRONES_PER_HOUR * (1.0 + power_bonus / 100.0);
+        drones_generated += regen;
+        drones_used_total += drones_used;
+        let overflow = (drones + regen - config.drone_capacity).max(0.0);
+        drones_wasted += overflow;
+        drones = (drones + regen).min(config.drone_capacity);
+
+        // Morale: workers drain, the rest take dorm beds lowest-morale first.
+        let mut wants_rest: Vec<usize> = Vec::new();
+        for (o, op) in ops.iter_mut().enumerate() {
+            if sustained.contains(&op.timeline.char_id) {
+                op.morale = FULL_MORALE;
+                if working.contains(&o) {
+                    op.timeline.worked_hours += 1;
+                }
+            } else if working.contains(&o) {
+                op.timeline.worked_hours += 1;
+                op.morale -= drains.get(&op.timeline.char_id).copied().unwrap_or(0.0);
+                if op.morale <= 0.0 {
+                    op.morale = 0.0;
+                    op.exhausted = true;
+                }
+            } else if op.morale < FULL_MORALE {
+                wants_rest.push(o);
+            }
+        }
+        wants_rest.sort_by(|&a, &b| ops[a].morale.total_cmp(&ops[b].morale));
+        let resting = wants_rest.len().min(config.dorm_capacity);
+        for &o in &wants_rest[..resting] {
+            let op = &mut ops[o];
+            op.morale = (op.morale + config.recovery).min(FULL_MORALE);
+            if op.morale >= FULL_MORALE {
+                op.exhausted = false;
+            }
+        }
+        for op in &mut ops {
+            if op.exhausted {
+                op.timeline.exhausted_hours += 1;
+            }
+            op.timeline.morale.push(op.morale);
+            op.timeline.lowest_morale = op.timeline.lowest_morale.min(op.morale);
+        }
+
+        snapshots.push(HourSnapshot {
+            hour,
+            shift: shift + 1,
+            lmd,
+            gold_produced,
+            gold_sold,
+            gold_stock,
+            exp,
+            drones,
+            drones_used,
+            resting,
+            waiting_for_bed: wants_rest.len() - resting,
+        });
+    }
+
+    let operators: Vec<OperatorTimeline> = ops.into_iter().map(|o| o.timeline).collect();
+    BaseSimulation {
+        total_lmd: snapshots.iter().map(|h| h.lmd).sum(),
+        total_exp: snapshots.iter().map(|h| h.exp).sum(),
+        hours: snapshots,
+        holds_up: operators.iter().all(|o| o.first_exhausted_hour.is_none()),
+        rooms: timelines,
+        operators,
+        drones_generated,
+        drones_used: drones_used_total,
+        drones_wasted,
+        dorm_capacity: config.dorm_capacity,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn room(slot_id: &str, room_type: &str, formula: Option<&str>, crews: [&[&str]; 3]) -> SimRoom {
+        SimRoom {
+            slot_id: slot_id.to_string(),
+            room_type: room_type.to_string(),
+            formula: formula.map(str::to_string),
+            level: 3,
+            shifts: crews
+                .iter()
+                .map(|crew| SimCell {
+                    crew: crew.iter().map(|s| (*s).to_string()).collect(),
+                    active: !crew.is_empty(),
+                    bonus_pct: 0.0,
+                })
+                .collect(),
+        }
+    }
+
+    fn config(dorm_capacity: usize) -> SimConfig {
+        SimConfig {
+            hours: 48,
+            recovery: 1.0,
+            dorm_capacity,
+            drone_capacity: 200.0,
+            drone_target: DroneTarget::None,
+        }
+    }
+
+    fn drains(ids: &[&str], drain: f64) -> HashMap<String, f64> {
+        ids.iter().map(|id| ((*id).to_string(), drain)).collect()
+    }
+
+    #[test]
+    fn alternating_teams_hold_up_with_enough_beds() {
+        let rooms = [room(
+            "f1",
+            "MANUFACTURE",
+            Some("F_EXP"),
+            [&["a"], &["b"], &["a"]],
+        )];
+        let sim = run(
+            &rooms,
+            &drains(&["a", "b"], 0.5),
+            &HashSet::new(),
+            &config(1),
+            &mut |_, _| (0.0, 0.0),
+        );
+        assert!(sim.holds_up);
+        assert!(sim.rooms[0].idle.is_empty());
+        // 48h of an L3 EXP factory at +0%.
+        assert!((sim.total_exp - 8000.0 * 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn fast_drain_exhausts_and_idles_the_room() {
+        // 1.5/hr burns the 12-morale bar in 8h of a 24h block.
+        let rooms = [room(
+            "f1",
+            "MANUFACTURE",
+            Some("F_EXP"),
+            [&["a"], &["a"], &[]],
+        )];
+        let sim = run(
+            &rooms,
+            &drains(&["a"], 1.5),
+            &HashSet::new(),
+            &config(1),
+            &mut |_, _| (0.0, 0.0),
+        );
+        assert!(!sim.holds_up);
+        assert_eq!(sim.operators[0].first_exhausted_hour, Some(8));
+        let first = &sim.rooms[0].idle[0];
+        assert_eq!(first.reason, IdleReason::Exhausted);
+        assert_eq!(first.start_hour, 8);
+    }
+
+    #[test]
+    fn trading_post_without_gold_goes_idle() {
+        let rooms = [room("t1", "TRADING", None, [&["a"], &["a"], &["a"]])];
+        let sustained: HashSet<String> = HashSet::from(["a".to_string()]);
+        let sim = run(
+            &rooms,
+            &drains(&["a"], 0.5),
+            &sustained,
+            &config(0),
+            &mut |_, _| (0.0, 0.0),
+        );
+        assert!(sim.holds_up);
+        assert_eq!(sim.rooms[0].idle.len(), 1);
+        assert_eq!(sim.rooms[0].idle[0].reason, IdleReason::NoGold);
+        assert_eq!(sim.rooms[0].idle[0].end_hour, 48);
+    }
+}
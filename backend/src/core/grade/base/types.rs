@@ -3,13 +3,14 @@ use std::collections::HashMap;
 use crate::core::gamedata::types::building::BuildingDataFile;
 use crate::{core::gamedata::types::building::BuildingChar, database::models::roster::RosterEntry};
 
+#[derive(Clone)]
 pub struct UserBuilding {
     /// How many factories, trading posts, power plants, and their levels.
     /// Key = `slot_id` (e.g. "`slot_1`"), Value = room info.
     pub rooms: Vec<UserRoom>,
 }
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct UserRoom {
     pub slot_id: String,
     pub room_type: String, // "MANUFACTURE", "TRADING", "POWER", "DORMITORY", etc.
@@ -183,15 +184,33 @@ impl OperatorBaseProfile {
         faction_tags: Vec<String>,
         rarity: i16,
         building_data: &BuildingDataFile,
+    ) -> Self {
+        Self::build_at(
+            roster.elite,
+            roster.level,
+            building_char,
+            faction_tags,
+            rarity,
+            building_data,
+        )
+    }
+
+    /// Profile for an operator at `elite`/`level`, whether or not it's owned -
+    /// the what-if optimizer uses this for operators the player plans to get.
+    pub fn build_at(
+        elite: i16,
+        level: i16,
+        building_char: &BuildingChar,
+        faction_tags: Vec<String>,
+        rarity: i16,
+        building_data: &BuildingDataFile,
     ) -> Self {
         let mut available_buffs = Vec::new();
 
         for slot in &building_char.buff_char {
             let mut best: Option<&str> = None;
             for entry in &slot.buff_data {
-                if i32::from(roster.elite) >= entry.cond.elite()
-                    && i32::from(roster.level) >= entry.cond.level
-                {
+                if i32::from(elite) >= entry.cond.elite() && i32::from(level) >= entry.cond.level {
                     best = Some(&entry.buff_id);
                 }
             }
@@ -207,7 +226,7 @@ impl OperatorBaseProfile {
             faction_tags,
             match_tags,
             rarity,
-            elite: roster.elite,
+            elite,
         }
     }
 }
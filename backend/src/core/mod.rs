@@ -1,12 +1,25 @@
 pub mod asset_watcher;
 pub mod auth;
+pub mod battle_replay;
+pub mod clear_squads;
 pub mod dps_watcher;
+pub mod drop_rates;
+pub mod gacha_odds;
 pub mod gacha_resync;
 pub mod gamedata;
+pub mod gamedata_diff;
+pub mod gamedata_search;
 pub mod grade;
 pub mod hypergryph;
 pub mod leaderboard_snapshot_job;
 pub mod medal_ownership_job;
 pub mod operator_ownership_job;
+pub mod operator_query;
+pub mod recruitment;
 pub mod regrade_job;
+pub mod stage_metrics;
+pub mod stage_modifiers;
+#[cfg(test)]
+pub(crate) mod test_support;
 pub mod trending_job;
+pub mod wave_simulation;
@@ -0,0 +1,543 @@
+//! Structured operator filtering over `GameData.operators`: class, branch,
+//! rarity, faction, position, SP recovery, damage type, blackboard effects,
+//! modules and base skills, sorted by stats at a chosen elite and level.
+//!
+//! Lists of alternatives (`professions`, `rarities`, ...) match when any entry
+//! matches; `tags`, `effects` and `talentKeys` must all hold. An empty list
+//! doesn't filter.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::gamedata::types::module::ModuleType;
+use crate::core::gamedata::types::operator::{
+    Blackboard, Operator, OperatorPosition, OperatorProfession,
+};
+use crate::dps::operator_data::is_physical;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpType {
+    /// `INCREASE_WITH_TIME`
+    Auto,
+    /// `INCREASE_WHEN_ATTACK`
+    Offensive,
+    /// `INCREASE_WHEN_TAKEN_DAMAGE`
+    Defensive,
+}
+
+impl SpType {
+    fn raw(self) -> &'static str {
+        match self {
+            Self::Auto => "INCREASE_WITH_TIME",
+            Self::Offensive => "INCREASE_WHEN_ATTACK",
+            Self::Defensive => "INCREASE_WHEN_TAKEN_DAMAGE",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DamageKind {
+    Physical,
+    Arts,
+    Healing,
+}
+
+/// What an operator's normal attacks do: the DPS calculator's rule
+/// ([`is_physical`]), with medics split out as healers.
+pub fn damage_kind(op: &Operator) -> DamageKind {
+    if op.profession == OperatorProfession::Medic {
+        DamageKind::Healing
+    } else if is_physical(op) {
+        DamageKind::Physical
+    } else {
+        DamageKind::Arts
+    }
+}
+
+/// Effects recognised from blackboard keys in the trait, talents and skills.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Effect {
+    /// Negative `def` or `def_penetrate*`.
+    DefShred,
+    /// Negative `magic_resistance` or `magic_resist_penetrate*`.
+    ResShred,
+    /// Negative `move_speed`.
+    Slow,
+    Stun,
+    Sleep,
+    Bind,
+    Levitate,
+    /// Positive `sp` / `sp_recovery_per_sec`.
+    SpGain,
+    /// Positive `cost` (DP).
+    DpGain,
+}
+
+impl Effect {
+    fn matches(self, key: &str, value: f64) -> bool {
+        match self {
+            Self::DefShred => (key == "def" && value < 0.0) || key.starts_with("def_penetrate"),
+            Self::ResShred => {
+                (key == "magic_resistance" && value < 0.0)
+                    || key.starts_with("magic_resist_penetrate")
+            }
+            Self::Slow => key == "move_speed" && value < 0.0,
+            Self::Stun => key == "stun",
+            Self::Sleep => key == "sleep",
+            Self::Bind => key == "binding",
+            Self::Levitate => key == "levitate",
+            Self::SpGain => matches!(key, "sp" | "sp_recovery_per_sec") && value > 0.0,
+            Self::DpGain => key == "cost" && value > 0.0,
+        }
+    }
+}
+
+/// `attack@def_penetrate_fixed` -> `def_penetrate_fixed`.
+fn bare_key(key: &str) -> String {
+    key.rsplit('@').next().unwrap_or(key).to_lowercase()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SortStat {
+    Hp,
+    Atk,
+    Def,
+    Res,
+    Cost,
+    Block,
+    /// Base attack interval in seconds.
+    AttackInterval,
+    RedeployTime,
+    Rarity,
+    Name,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuerySort {
+    pub stat: SortStat,
+    /// 0-2; clamped to the operator's last elite. Defaults to the last.
+    pub elite: Option<usize>,
+    /// Clamped to the phase's max level. Defaults to the max.
+    pub level: Option<i32>,
+    /// Highest first unless set.
+    #[serde(default)]
+    pub ascending: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OperatorQuery {
+    pub professions: Vec<OperatorProfession>,
+    /// Sub-profession ids (`fastshot`, `slower`, ...).
+    pub branches: Vec<String>,
+    /// Star counts, 1-6.
+    pub rarities: Vec<i16>,
+    pub positions: Vec<OperatorPosition>,
+    /// Nation, group or team ids.
+    pub factions: Vec<String>,
+    /// Recruitment tags, all required.
+    pub tags: Vec<String>,
+    /// Any skill recovering SP this way.
+    pub sp_types: Vec<SpType>,
+    pub damage_types: Vec<DamageKind>,
+    pub effects: Vec<Effect>,
+    /// Raw talent blackboard keys, all required.
+    pub talent_keys: Vec<String>,
+    /// Has (or lacks) an advanced module.
+    pub has_module: Option<bool>,
+    /// Base skill room types (`MANUFACTURE`, `TRADING`, ...).
+    pub base_rooms: Vec<String>,
+    /// Base skill buff ids, or a part of the buff name.
+    pub base_skills: Vec<String>,
+    /// Also list unobtainable units (summons, traps, collab stand-ins).
+    pub include_unobtainable: bool,
+    pub sort: Option<QuerySort>,
+}
+
+/// Base stats at an elite and level.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatLine {
+    pub elite: usize,
+    pub level: i32,
+    pub hp: i32,
+    pub atk: i32,
+    pub def: i32,
+    pub res: f64,
+    pub cost: i32,
+    pub block: i32,
+    pub attack_interval: f64,
+    pub redeploy_time: i32,
+}
+
+/// Stats interpolated between the phase's attribute key frames, with `elite`
+/// and `level` clamped to what the operator has. `None` without phases.
+pub fn stats_at(op: &Operator, elite: Option<usize>, level: Option<i32>) -> Option<StatLine> {
+    let last = op.phases.len().checked_sub(1)?;
+    let elite = elite.unwrap_or(last).min(last);
+    let phase = &op.phases[elite];
+    let level = level
+        .unwrap_or(phase.max_level)
+        .clamp(1, phase.max_level.max(1));
+    let frames = &phase.attributes_key_frames;
+    let lo = frames
+        .iter()
+        .rev()
+        .find(|f| f.level <= level)
+        .or(frames.first())?;
+    let hi = frames.iter().find(|f| f.level >= level).unwrap_or(lo);
+
+    let t = if hi.level > lo.level {
+        f64::from(level - lo.level) / f64::from(hi.level - lo.level)
+    } else {
+        0.0
+    };
+    let lerp = |a: f64, b: f64| a + (b - a) * t;
+    let lerp_i = |a: i32, b: i32| lerp(f64::from(a), f64::from(b)).round() as i32;
+    let (a, b) = (&lo.data, &hi.data);
+    Some(StatLine {
+        elite,
+        level,
+        hp: lerp_i(a.max_hp, b.max_hp),
+        atk: lerp_i(a.atk, b.atk),
+        def: lerp_i(a.def, b.def),
+        res: lerp(a.magic_resistance, b.magic_resistance),
+        cost: lerp_i(a.cost, b.cost),
+        block: lerp_i(a.block_cnt, b.block_cnt),
+        attack_interval: lerp(a.base_attack_time, b.base_attack_time),
+        redeploy_time: lerp_i(a.respawn_time, b.respawn_time),
+    })
+}
+
+/// Trait (final candidate), every talent candidate and each skill's top level.
+fn blackboards(op: &Operator) -> impl Iterator<Item = (String, f64)> + '_ {
+    let traits = op
+        .trait_data
+        .iter()
+        .filter_map(|t| t.candidates.last())
+        .flat_map(|c| c.blackboard.iter());
+    let talents = talent_blackboards(op);
+    let skills = op
+        .skills
+        .iter()
+        .filter_map(|s| s.static_data.as_ref()?.levels.last())
+        .flat_map(|l| l.blackboard.iter().map(|b| (bare_key(&b.key), b.value)));
+    traits
+        .chain(talents)
+        .map(|b: &Blackboard| (bare_key(&b.key), b.value))
+        .chain(skills)
+}
+
+fn talent_blackboards(op: &Operator) -> impl Iterator<Item = &Blackboard> {
+    op.talents
+        .iter()
+        .flat_map(|t| t.candidates.iter())
+        .flat_map(|c| c.blackboard.iter())
+}
+
+impl OperatorQuery {
+    pub fn matches(&self, op: &Operator) -> bool {
+        fn any_or_empty<T>(wanted: &[T], pred: impl Fn(&T) -> bool) -> bool {
+            wanted.is_empty() || wanted.iter().any(pred)
+        }
+
+        if !self.include_unobtainable && op.is_not_obtainable {
+            return false;
+        }
+        let factions = [
+            Some(&op.nation_id),
+            op.group_id.as_ref(),
+            op.team_id.as_ref(),
+        ];
+        let simple = any_or_empty(&self.professions, |p| *p == op.profession)
+            && any_or_empty(&self.branches, |b| {
+                b.eq_ignore_ascii_case(&op.sub_profession_id)
+            })
+            && any_or_empty(&self.rarities, |r| *r == op.rarity.to_star_int())
+            && any_or_empty(&self.positions, |p| *p == op.position)
+            && any_or_empty(&self.factions, |f| {
+                factions
+                    .iter()
+                    .flatten()
+                    .any(|id| id.eq_ignore_ascii_case(f))
+            })
+            && self.tags.iter().all(|t| op.tag_list.contains(t))
+            && any_or_empty(&self.damage_types, |d| *d == damage_kind(op));
+        if !simple {
+            return false;
+        }
+
+        let sp_ok = any_or_empty(&self.sp_types, |sp| {
+            op.skills
+                .iter()
+                .filter_map(|s| s.static_data.as_ref()?.levels.first())
+                .any(|l| l.sp_data.sp_type == sp.raw())
+        });
+        let module_ok = self.has_module.is_none_or(|want| {
+            op.modules
+                .iter()
+                .any(|m| m.module.module_type == ModuleType::Advanced)
+                == want
+        });
+        let base_ok = any_or_empty(&self.base_rooms, |room| {
+            op.base_skills
+                .iter()
+                .any(|s| s.room_type.eq_ignore_ascii_case(room))
+        }) && any_or_empty(&self.base_skills, |wanted| {
+            let wanted = wanted.to_lowercase();
+            op.base_skills.iter().any(|s| {
+                s.buff_id.to_lowercase() == wanted || s.buff_name.to_lowercase().contains(&wanted)
+            })
+        });
+        if !(sp_ok && module_ok && base_ok) {
+            return false;
+        }
+
+        let talent_keys: Vec<String> = talent_blackboards(op).map(|b| bare_key(&b.key)).collect();
+        let keys_ok = self.talent_keys.iter().all(|k| {
+            let k = k.to_lowercase();
+            talent_keys.contains(&bare_key(&k))
+        });
+        keys_ok
+            && (self.effects.is_empty() || {
+                let boards: Vec<(String, f64)> = blackboards(op).collect();
+                self.effects
+                    .iter()
+                    .all(|e| boards.iter().any(|(k, v)| e.matches(k, *v)))
+            })
+    }
+}
+
+/// A matching operator id with its stats at the requested elite/level (the
+/// max when no sort is given).
+#[derive(Debug, Clone)]
+pub struct QueryMatch {
+    pub id: String,
+    pub stats: Option<StatLine>,
+}
+
+/// Operators matching `query`, sorted by `query.sort` or else by rarity
+/// (highest first) and name.
+pub fn run(operators: &HashMap<String, Operator>, query: &OperatorQuery) -> Vec<QueryMatch> {
+    let (elite, level) = query
+        .sort
+        .as_ref()
+        .map_or((None, None), |s| (s.elite, s.level));
+    let mut found: Vec<(&Operator, QueryMatch)> = operators
+        .iter()
+        .filter(|(_, op)| query.matches(op))
+        .map(|(id, op)| {
+            (
+                op,
+                QueryMatch {
+                    id: id.clone(),
+                    stats: stats_at(op, elite, level),
+                },
+            )
+        })
+        .collect();
+
+    let by_name = |a: &(&Operator, QueryMatch), b: &(&Operator, QueryMatch)| {
+        a.0.name.cmp(&b.0.name).then_with(|| a.1.id.cmp(&b.1.id))
+    };
+    let by_rarity = |a: &(&Operator, QueryMatch), b: &(&Operator, QueryMatch)| {
+        a.0.rarity.to_star_int().cmp(&b.0.rarity.to_star_int())
+    };
+    match &query.sort {
+        None => found.sort_by(|a, b| by_rarity(b, a).then_with(|| by_name(a, b))),
+        Some(sort) => {
+            let key = |m: &QueryMatch| -> f64 {
+                let Some(s) = m.stats else {
+                    return f64::NAN;
+                };
+                match sort.stat {
+                    SortStat::Hp => f64::from(s.hp),
+                    SortStat::Atk => f64::from(s.atk),
+                    SortStat::Def => f64::from(s.def),
+                    SortStat::Res => s.res,
+                    SortStat::Cost => f64::from(s.cost),
+                    SortStat::Block => f64::from(s.block),
+                    SortStat::AttackInterval => s.attack_interval,
+                    SortStat::RedeployTime => f64::from(s.redeploy_time),
+                    SortStat::Rarity | SortStat::Name => 0.0,
+                }
+            };
+            found.sort_by(|a, b| {
+                let primary = match sort.stat {
+                    SortStat::Name => by_name(a, b),
+                    SortStat::Rarity => by_rarity(a, b),
+                    _ => {
+                        let (x, y) = (key(&a.1), key(&b.1));
+                        // Operators without stats sort last either way.
+                        match (x.is_nan(), y.is_nan()) {
+                            (true, true) => Ordering::Equal,
+                            (true, false) => return Ordering::Greater,
+                            (false, true) => return Ordering::Less,
+                            _ => x.total_cmp(&y),
+                        }
+                    }
+                };
+                let primary = if sort.ascending {
+                    primary
+                } else {
+                    primary.reverse()
+                };
+                primary.then_with(|| by_name(a, b))
+            });
+        }
+    }
+    found.into_iter().map(|(_, m)| m).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::gamedata::types::operator::{
+        AttributeData, AttributeKeyFrame, OperatorRarity, Phase, Talent, TalentCandidate,
+    };
+
+    fn frame(level: i32, atk: i32) -> AttributeKeyFrame {
+        AttributeKeyFrame {
+            level,
+            data: AttributeData {
+                atk,
+                max_hp: atk * 2,
+                ..AttributeData::default()
+            },
+        }
+    }
+
+    fn operator(
+        name: &str,
+        position: OperatorPosition,
+        atk: i32,
+        talent: &[(&str, f64)],
+    ) -> Operator {
+        Operator {
+            name: name.into(),
+            position,
+            rarity: OperatorRarity::FiveStar,
+            profession: OperatorProfession::Supporter,
+            phases: vec![Phase {
+                max_level: 50,
+                attributes_key_frames: vec![frame(1, atk / 2), frame(50, atk)],
+                ..Phase::default()
+            }],
+            talents: vec![Talent {
+                candidates: vec![TalentCandidate {
+                    blackboard: talent
+                        .iter()
+                        .map(|&(key, value)| Blackboard {
+                            key: key.into(),
+                            value,
+                            value_str: None,
+                        })
+                        .collect(),
+                    ..TalentCandidate::default()
+                }],
+            }],
+            ..Operator::default()
+        }
+    }
+
+    fn roster() -> HashMap<String, Operator> {
+        HashMap::from([
+            (
+                "slower".into(),
+                operator(
+                    "Istina",
+                    OperatorPosition::Ranged,
+                    400,
+                    &[("move_speed", -0.2)],
+                ),
+            ),
+            (
+                "sp".into(),
+                operator(
+                    "Ptilopsis",
+                    OperatorPosition::Ranged,
+                    300,
+                    &[("sp_recovery_per_sec", 0.3)],
+                ),
+            ),
+            (
+                "melee".into(),
+                operator(
+                    "Blaze",
+                    OperatorPosition::Melee,
+                    600,
+                    &[("attack@move_speed", -0.5)],
+                ),
+            ),
+        ])
+    }
+
+    #[test]
+    fn damage_kind_follows_the_dps_rule() {
+        let op = |profession, branch: &str| Operator {
+            profession,
+            sub_profession_id: branch.into(),
+            ..Operator::default()
+        };
+        let kinds = [
+            op(OperatorProfession::Medic, "physician"),
+            op(OperatorProfession::Caster, "corecaster"),
+            op(OperatorProfession::Guard, "artsfghter"),
+            op(OperatorProfession::Guard, "fighter"),
+        ]
+        .map(|o| damage_kind(&o));
+        assert_eq!(
+            kinds,
+            [
+                DamageKind::Healing,
+                DamageKind::Arts,
+                DamageKind::Arts,
+                DamageKind::Physical
+            ]
+        );
+    }
+
+    #[test]
+    fn filters_by_position_and_effect() {
+        let query = OperatorQuery {
+            positions: vec![OperatorPosition::Ranged],
+            effects: vec![Effect::Slow],
+            ..OperatorQuery::default()
+        };
+        let ids: Vec<String> = run(&roster(), &query).into_iter().map(|m| m.id).collect();
+        assert_eq!(ids, ["slower"]);
+
+        let query = OperatorQuery {
+            talent_keys: vec!["move_speed".into()],
+            damage_types: vec![DamageKind::Arts],
+            ..OperatorQuery::default()
+        };
+        assert_eq!(run(&roster(), &query).len(), 2);
+    }
+
+    #[test]
+    fn sorts_by_interpolated_stats() {
+        let query = OperatorQuery {
+            sort: Some(QuerySort {
+                stat: SortStat::Atk,
+                elite: Some(2),
+                level: Some(25),
+                ascending: false,
+            }),
+            ..OperatorQuery::default()
+        };
+        let found = run(&roster(), &query);
+        assert_eq!(found[0].id, "melee");
+        let stats = found[0].stats.unwrap();
+        // Elite clamps to the only phase; level 25 sits halfway-ish: 300 + 300 * 24/49.
+        assert_eq!((stats.elite, stats.level, stats.atk), (0, 25, 447));
+        assert_eq!(found[2].id, "sp");
+    }
+}
@@ -0,0 +1,364 @@
+//! Recruitment tag solver: the recruitable pool from `gacha_table.recruitDetail`
+//! and every tag combination's possible outcomes.
+//!
+//! Matching follows the game: position, class and qualification tags are
+//! identified by `tagId` (names are localized), affix tags match the
+//! operator's `tagList` by name. 6★ operators only appear when Top Operator is
+//! selected.
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::core::gamedata::types::GameData;
+use crate::core::gamedata::types::gacha::GachaTag;
+use crate::core::gamedata::types::operator::{Operator, OperatorPosition, OperatorProfession};
+
+pub const TOP_OPERATOR_TAG_ID: i32 = 11;
+pub const SENIOR_OPERATOR_TAG_ID: i32 = 14;
+pub const STARTER_TAG_ID: i32 = 17;
+pub const ROBOT_TAG_ID: i32 = 28;
+
+/// Tags offered per recruitment slot.
+pub const MAX_OFFERED_TAGS: usize = 5;
+/// Tags that can be selected at once.
+pub const MAX_SELECTED_TAGS: usize = 3;
+
+/// "Best outcome first": 6 > 5 > 4 > Robot(1) > 3 > 2. Robots rank above 3★
+/// because a guaranteed robot is a valuable pull.
+fn rarity_priority(rarity: i16) -> u8 {
+    match rarity {
+        6 => 0,
+        5 => 1,
+        4 => 2,
+        1 => 3,
+        3 => 4,
+        _ => 5,
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Recruitable {
+    pub char_id: String,
+    pub name: String,
+    pub rarity: i16,
+    pub profession: OperatorProfession,
+    pub position: OperatorPosition,
+    pub tag_list: Vec<String>,
+}
+
+/// Operator names listed in `recruitDetail`. The text groups names by rarity
+/// under `★` headers, separated by ` / ` and wrapped in rich-text tags;
+/// titles, notes and rule lines are skipped.
+pub fn parse_recruit_detail(detail: &str) -> HashSet<String> {
+    let mut names = HashSet::new();
+    for line in detail.lines().map(str::trim) {
+        if line.is_empty()
+            || line.starts_with("<@rc.title>")
+            || line.starts_with("<@rc.subtitle>")
+            || line.starts_with("<@rc.em>")
+            || line.starts_with("---")
+            || line.chars().all(|c| c == '★')
+        {
+            continue;
+        }
+        let stripped = strip_rich_text(line);
+        names.extend(
+            stripped
+                .split('/')
+                .map(|n| n.trim_matches(|c: char| c == '★' || c.is_whitespace()))
+                .filter(|n| !n.is_empty())
+                .map(str::to_owned),
+        );
+    }
+    names
+}
+
+/// Drop `<@tag>` / `</>` markup.
+fn strip_rich_text(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut in_tag = false;
+    for c in s.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Operators currently obtainable from recruitment on this server.
+pub fn recruitable_pool(gd: &GameData) -> Vec<Recruitable> {
+    let names = parse_recruit_detail(&gd.gacha.recruit_detail);
+    let mut pool: Vec<Recruitable> = gd
+        .operators
+        .iter()
+        .filter(|(id, op)| id.starts_with("char_") && names.contains(&op.name))
+        .map(|(id, op)| recruitable(id, op))
+        .collect();
+    pool.sort_by(|a, b| a.char_id.cmp(&b.char_id));
+    pool
+}
+
+fn recruitable(id: &str, op: &Operator) -> Recruitable {
+    Recruitable {
+        char_id: id.to_owned(),
+        name: op.name.clone(),
+        rarity: op.rarity.to_star_int(),
+        profession: op.profession.clone(),
+        position: op.position.clone(),
+        tag_list: op.tag_list.clone(),
+    }
+}
+
+fn profession_for_tag(tag_id: i32) -> Option<OperatorProfession> {
+    Some(match tag_id {
+        1 => OperatorProfession::Guard,
+        2 => OperatorProfession::Sniper,
+        3 => OperatorProfession::Defender,
+        4 => OperatorProfession::Medic,
+        5 => OperatorProfession::Supporter,
+        6 => OperatorProfession::Caster,
+        7 => OperatorProfession::Specialist,
+        8 => OperatorProfession::Vanguard,
+        _ => return None,
+    })
+}
+
+fn matches_tag(op: &Recruitable, tag: &GachaTag) -> bool {
+    match tag.tag_id {
+        9 => op.position == OperatorPosition::Melee,
+        10 => op.position == OperatorPosition::Ranged,
+        TOP_OPERATOR_TAG_ID => op.rarity == 6,
+        SENIOR_OPERATOR_TAG_ID => op.rarity == 5,
+        STARTER_TAG_ID => op.rarity == 2,
+        ROBOT_TAG_ID => op.rarity == 1,
+        id => match profession_for_tag(id) {
+            Some(profession) => op.profession == profession,
+            None => op.tag_list.contains(&tag.tag_name),
+        },
+    }
+}
+
+/// Outcome rarities to leave out, mirroring the in-game recruitment timer:
+/// 1★ and 2★ only roll on short timers, 3★ is avoided past 7:40.
+#[derive(Debug, Clone, Copy)]
+pub struct SolveOptions {
+    pub include_robots: bool,
+    pub include_two_stars: bool,
+    pub include_three_stars: bool,
+}
+
+impl Default for SolveOptions {
+    fn default() -> Self {
+        Self {
+            include_robots: true,
+            include_two_stars: true,
+            include_three_stars: true,
+        }
+    }
+}
+
+impl SolveOptions {
+    fn allows(&self, rarity: i16) -> bool {
+        match rarity {
+            1 => self.include_robots,
+            2 => self.include_two_stars,
+            3 => self.include_three_stars,
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TagCombination<'a> {
+    pub tags: Vec<&'a GachaTag>,
+    /// Possible outcomes, best rarity first.
+    pub operators: Vec<&'a Recruitable>,
+    /// Lowest rarity the combination can roll.
+    pub guaranteed_rarity: i16,
+    pub max_rarity: i16,
+}
+
+impl TagCombination<'_> {
+    /// Share of outcomes that are 5★ or better, treating outcomes as equally
+    /// likely within the guaranteed floor.
+    pub fn five_star_chance(&self) -> f64 {
+        if self.guaranteed_rarity >= 5 {
+            return 1.0;
+        }
+        let hits = self.operators.iter().filter(|op| op.rarity >= 5).count();
+        hits as f64 / self.operators.len() as f64
+    }
+
+    /// Worst outcome on the [`rarity_priority`] scale.
+    fn floor_priority(&self) -> u8 {
+        self.operators
+            .iter()
+            .map(|op| rarity_priority(op.rarity))
+            .max()
+            .unwrap_or(u8::MAX)
+    }
+}
+
+/// Every non-empty combination of up to [`MAX_SELECTED_TAGS`] of `tags` that
+/// can roll anything, best first: highest 5★+ chance, then best worst case,
+/// then fewest outcomes.
+pub fn solve<'a>(
+    pool: &'a [Recruitable],
+    tags: &'a [GachaTag],
+    options: SolveOptions,
+) -> Vec<TagCombination<'a>> {
+    let mut results = Vec::new();
+    for combo in combinations(tags.len(), MAX_SELECTED_TAGS) {
+        let combo_tags: Vec<&GachaTag> = combo.iter().map(|&i| &tags[i]).collect();
+        let has_top = combo_tags.iter().any(|t| t.tag_id == TOP_OPERATOR_TAG_ID);
+        let mut operators: Vec<&Recruitable> = pool
+            .iter()
+            .filter(|op| (has_top || op.rarity < 6) && options.allows(op.rarity))
+            .filter(|op| combo_tags.iter().all(|t| matches_tag(op, t)))
+            .collect();
+        if operators.is_empty() {
+            continue;
+        }
+        operators.sort_by(|a, b| {
+            rarity_priority(a.rarity)
+                .cmp(&rarity_priority(b.rarity))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        let guaranteed_rarity = operators.iter().map(|op| op.rarity).min().unwrap_or(0);
+        let max_rarity = operators.iter().map(|op| op.rarity).max().unwrap_or(0);
+        results.push(TagCombination {
+            tags: combo_tags,
+            operators,
+            guaranteed_rarity,
+            max_rarity,
+        });
+    }
+
+    results.sort_by(|a, b| {
+        b.five_star_chance()
+            .total_cmp(&a.five_star_chance())
+            .then_with(|| a.floor_priority().cmp(&b.floor_priority()))
+            .then_with(|| b.max_rarity.cmp(&a.max_rarity))
+            .then_with(|| a.operators.len().cmp(&b.operators.len()))
+    });
+    results
+}
+
+/// Index sets of size `1..=max_size` over `0..n`, in lexicographic order.
+fn combinations(n: usize, max_size: usize) -> Vec<Vec<usize>> {
+    fn extend(
+        start: usize,
+        n: usize,
+        max_size: usize,
+        cur: &mut Vec<usize>,
+        out: &mut Vec<Vec<usize>>,
+    ) {
+        if !cur.is_empty() {
+            out.push(cur.clone());
+        }
+        if cur.len() == max_size {
+            return;
+        }
+        for i in start..n {
+            cur.push(i);
+            extend(i + 1, n, max_size, cur, out);
+            cur.pop();
+        }
+    }
+    let mut out = Vec::new();
+    extend(0, n, max_size, &mut Vec::new(), &mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn op(name: &str, rarity: i16, profession: OperatorProfession, tags: &[&str]) -> Recruitable {
+        Recruitable {
+            char_id: format!("char_{name}"),
+            name: name.to_owned(),
+            rarity,
+            profession,
+            position: OperatorPosition::Ranged,
+            tag_list: tags.iter().map(|t| (*t).to_owned()).collect(),
+        }
+    }
+
+    fn tag(tag_id: i32, tag_name: &str) -> GachaTag {
+        GachaTag {
+            tag_id,
+            tag_name: tag_name.to_owned(),
+            tag_group: 0,
+        }
+    }
+
+    #[test]
+    fn recruit_detail_names() {
+        let detail = "<@rc.title>Recruitment Rules</>\n<@rc.em>※ note</>\n\n★\n<@rc.eml>Lancet-2</> / Castle-3\n--------------------\n★★★★★★\nExusiai / <@rc.eml>Siege</>\n";
+        let names = parse_recruit_detail(detail);
+        let mut names: Vec<_> = names.into_iter().collect();
+        names.sort();
+        assert_eq!(names, ["Castle-3", "Exusiai", "Lancet-2", "Siege"]);
+    }
+
+    #[test]
+    fn top_operator_gates_six_stars() {
+        let pool = [
+            op("Exusiai", 6, OperatorProfession::Sniper, &["DPS"]),
+            op("Platinum", 5, OperatorProfession::Sniper, &["DPS"]),
+            op("Kroos", 3, OperatorProfession::Sniper, &["DPS"]),
+        ];
+        let tags = [tag(2, "Sniper"), tag(TOP_OPERATOR_TAG_ID, "Top Operator")];
+        let results = solve(&pool, &tags, SolveOptions::default());
+
+        let sniper_only = results
+            .iter()
+            .find(|r| r.tags.len() == 1 && r.tags[0].tag_id == 2)
+            .unwrap();
+        assert!(sniper_only.operators.iter().all(|o| o.rarity < 6));
+        assert_eq!(sniper_only.guaranteed_rarity, 3);
+
+        let best = &results[0];
+        assert_eq!(best.guaranteed_rarity, 6);
+        assert_eq!(best.operators[0].name, "Exusiai");
+    }
+
+    #[test]
+    fn affix_tags_and_ranking() {
+        let pool = [
+            op("Platinum", 5, OperatorProfession::Sniper, &["DPS"]),
+            op("Kroos", 3, OperatorProfession::Sniper, &["DPS"]),
+            op("Lancet-2", 1, OperatorProfession::Medic, &["Healing"]),
+            op("Ansel", 3, OperatorProfession::Medic, &["Healing"]),
+        ];
+        let tags = [
+            tag(2, "Sniper"),
+            tag(1001, "DPS"),
+            tag(ROBOT_TAG_ID, "Robot"),
+        ];
+        let results = solve(&pool, &tags, SolveOptions::default());
+        assert!(
+            results.iter().all(|r| r.tags.iter().all(|t| t.tag_id != 2)
+                || r.tags.iter().all(|t| t.tag_id != ROBOT_TAG_ID)),
+            "Sniper + Robot matches nobody"
+        );
+        let robot = results.iter().find(|r| r.max_rarity == 1).unwrap();
+        assert_eq!(robot.operators.len(), 1);
+        assert_eq!(results[0].operators.len(), 2);
+        let no_three = solve(
+            &pool,
+            &tags,
+            SolveOptions {
+                include_three_stars: false,
+                ..SolveOptions::default()
+            },
+        );
+        assert_eq!(no_three[0].guaranteed_rarity, 5);
+    }
+}
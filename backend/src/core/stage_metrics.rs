@@ -0,0 +1,317 @@
+//! Stage difficulty metrics from a parsed [`StageMap`] and the enemy handbook:
+//! enemy HP per wave, peak simultaneous enemies, damage mix, threat counts
+//! and deployable tiles. Served with the stage detail and, precomputed per
+//! server, as a sortable index.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::gamedata::types::enemy::{DamageType, EnemyAttributes, EnemyHandbook};
+use crate::core::gamedata::types::level::StageMap;
+use crate::core::wave_simulation::leak_timeline;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WaveLoad {
+    /// 1-based, as in `schedule[].wave`.
+    pub wave: u32,
+    pub enemies: u32,
+    pub total_hp: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageMetrics {
+    pub enemies: u32,
+    /// Sum of every spawned enemy's max HP, stage modifiers applied.
+    pub total_hp: f64,
+    pub waves: Vec<WaveLoad>,
+    /// Most enemies on the board at once with nothing killing them.
+    pub peak_enemies: u32,
+    pub peak_at: f64,
+    /// Share of enemy ATK dealt as physical / arts damage (sums to 1 unless
+    /// the stage has no attackers).
+    pub physical_share: f64,
+    pub arts_share: f64,
+    pub flying: u32,
+    pub elite: u32,
+    pub boss: u32,
+    /// Tiles accepting melee / ranged operators (`ALL` tiles count for both).
+    pub melee_tiles: u32,
+    pub ranged_tiles: u32,
+    pub life_points: i64,
+    pub char_limit: i64,
+    /// Seconds until the last enemy would leak.
+    pub duration: f64,
+}
+
+/// Per-enemy stats as fielded in this stage.
+struct Fielded {
+    attributes: EnemyAttributes,
+    damage_types: Vec<DamageType>,
+    flying: bool,
+    level: String,
+}
+
+/// Metrics for `map` played at `difficulty` (see `Modifier::in_difficulty`):
+/// stage modifiers for other modes are left out.
+pub fn analyze(map: &StageMap, enemies: &EnemyHandbook, difficulty: &str) -> StageMetrics {
+    let fielded: HashMap<&str, Fielded> = map
+        .roster
+        .iter()
+        .map(|r| {
+            let enemy = enemies.enemy_data.get(&r.enemy_id);
+            let mut attributes = enemy
+                .and_then(|e| e.level_stats(r.stat_level))
+                .map(|l| l.attributes.clone())
+                .unwrap_or_default();
+            for modifier in map.modifiers.iter().filter(|m| m.in_difficulty(difficulty)) {
+                modifier.apply_to_enemy(&r.enemy_id, &mut attributes);
+            }
+            let fielded = Fielded {
+                attributes,
+                damage_types: enemy.map(|e| e.damage_type.clone()).unwrap_or_default(),
+                flying: r.motion == "FLY",
+                level: r.level.clone(),
+            };
+            (r.enemy_id.as_str(), fielded)
+        })
+        .collect();
+
+    let mut metrics = StageMetrics {
+        life_points: map.options.max_life,
+        char_limit: map.options.char_limit,
+        ..StageMetrics::default()
+    };
+
+    let mut waves: HashMap<u32, WaveLoad> = HashMap::new();
+    let (mut physical_atk, mut arts_atk) = (0.0, 0.0);
+    for entry in &map.schedule {
+        let Some(enemy) = fielded.get(entry.enemy_id.as_str()) else {
+            continue;
+        };
+        let count = entry.count;
+        let hp = f64::from(enemy.attributes.max_hp) * f64::from(count);
+        let wave = waves.entry(entry.wave).or_insert(WaveLoad {
+            wave: entry.wave,
+            enemies: 0,
+            total_hp: 0.0,
+        });
+        wave.enemies += count;
+        wave.total_hp += hp;
+        metrics.enemies += count;
+        metrics.total_hp += hp;
+
+        if enemy.flying {
+            metrics.flying += count;
+        }
+        match enemy.level.as_str() {
+            "ELITE" => metrics.elite += count,
+            "BOSS" => metrics.boss += count,
+            _ => {}
+        }
+
+        let atk = f64::from(enemy.attributes.atk) * f64::from(count);
+        let physical = enemy.damage_types.contains(&DamageType::Physic);
+        let arts = enemy.damage_types.contains(&DamageType::Magic);
+        match (physical, arts) {
+            (true, true) => {
+                physical_atk += atk / 2.0;
+                arts_atk += atk / 2.0;
+            }
+            (true, false) => physical_atk += atk,
+            (false, true) => arts_atk += atk,
+            (false, false) => {}
+        }
+    }
+    let mut waves: Vec<WaveLoad> = waves.into_values().collect();
+    waves.sort_by_key(|w| w.wave);
+    metrics.waves = waves;
+    if physical_atk + arts_atk > 0.0 {
+        metrics.physical_share = physical_atk / (physical_atk + arts_atk);
+        metrics.arts_share = arts_atk / (physical_atk + arts_atk);
+    }
+
+    let leaks = leak_timeline(map);
+    metrics.duration = leaks.iter().map(|l| l.leaked_at).fold(0.0, f64::max);
+    // Sweep spawn (+1) / leak (-1) events; leaks sort first on ties.
+    let mut events: Vec<(f64, i32)> = leaks
+        .iter()
+        .flat_map(|l| [(l.spawned_at, 1), (l.leaked_at, -1)])
+        .collect();
+    events.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+    let mut on_board = 0;
+    for (t, delta) in events {
+        on_board += delta;
+        if on_board > metrics.peak_enemies as i32 {
+            metrics.peak_enemies = on_board as u32;
+            metrics.peak_at = t;
+        }
+    }
+
+    for tile in map.tiles.iter().flatten() {
+        match tile.buildable.as_str() {
+            "MELEE" => metrics.melee_tiles += 1,
+            "RANGED" => metrics.ranged_tiles += 1,
+            "ALL" => {
+                metrics.melee_tiles += 1;
+                metrics.ranged_tiles += 1;
+            }
+            _ => {}
+        }
+    }
+
+    metrics
+}
+
+/// Index sort keys, as accepted by `?sort=`.
+pub const SORT_KEYS: [&str; 9] = [
+    "totalHp",
+    "enemies",
+    "peakEnemies",
+    "artsShare",
+    "flying",
+    "elite",
+    "boss",
+    "deployableTiles",
+    "duration",
+];
+
+impl StageMetrics {
+    /// Value for one of [`SORT_KEYS`]; `None` for an unknown key.
+    pub fn sort_value(&self, key: &str) -> Option<f64> {
+        Some(match key {
+            "totalHp" => self.total_hp,
+            "enemies" => f64::from(self.enemies),
+            "peakEnemies" => f64::from(self.peak_enemies),
+            "artsShare" => self.arts_share,
+            "flying" => f64::from(self.flying),
+            "elite" => f64::from(self.elite),
+            "boss" => f64::from(self.boss),
+            "deployableTiles" => f64::from(self.melee_tiles.max(self.ranged_tiles)),
+            "duration" => self.duration,
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::gamedata::types::enemy::Enemy;
+    use crate::core::gamedata::types::level::{
+        Modifier, ModifierValue, Point, RouteOut, ScheduleEntry, TileCell,
+    };
+    use crate::core::test_support::{enemy, roster, spawn, stage_map};
+
+    fn typed(id: &str, hp: i32, atk: i32, damage: DamageType) -> Enemy {
+        Enemy {
+            damage_type: vec![damage],
+            ..enemy(id, hp, atk)
+        }
+    }
+
+    fn tile(buildable: &str) -> TileCell {
+        TileCell {
+            kind: "floor".into(),
+            tile_key: String::new(),
+            height_type: String::new(),
+            buildable: buildable.into(),
+            passable: true,
+        }
+    }
+
+    fn schedule(enemy_id: &str, wave: u32, count: u32) -> ScheduleEntry {
+        ScheduleEntry {
+            index: 0,
+            order: [0, 0],
+            enemy_id: enemy_id.into(),
+            route: 0,
+            t0: 0.0,
+            wave_time: 0.0,
+            interval: 0.0,
+            count,
+            wave,
+        }
+    }
+
+    /// `enemy_b` max HP ×2, in `difficulty` only.
+    fn double_b(difficulty: &str) -> Modifier {
+        Modifier {
+            key: "enemy_attribute_mul".into(),
+            difficulty: difficulty.into(),
+            profession: String::new(),
+            blackboard: vec![
+                ModifierValue {
+                    key: "max_hp".into(),
+                    value: 2.0,
+                    value_str: None,
+                },
+                ModifierValue {
+                    key: "enemy".into(),
+                    value: 0.0,
+                    value_str: Some("enemy_b".into()),
+                },
+            ],
+        }
+    }
+
+    fn handbook() -> EnemyHandbook {
+        let mut handbook = EnemyHandbook::default();
+        for e in [
+            typed("enemy_a", 1000, 100, DamageType::Physic),
+            typed("enemy_b", 3000, 300, DamageType::Magic),
+        ] {
+            handbook.enemy_data.insert(e.enemy_id.clone(), e);
+        }
+        handbook
+    }
+
+    fn small_stage(modifiers: Vec<Modifier>) -> StageMap {
+        let mut map = stage_map();
+        map.width = 3;
+        map.height = 1;
+        map.tiles = vec![vec![tile("MELEE"), tile("ALL"), tile("NONE")]];
+        map.routes = vec![RouteOut {
+            motion: "FLY".into(),
+            points: vec![Point { x: 0.0, y: 0.0 }, Point { x: 2.0, y: 0.0 }],
+        }];
+        // 2 tiles at 1.0 × 1.0 tiles/sec: on board for 2s each.
+        map.spawns = vec![
+            spawn("enemy_a", 0.0),
+            spawn("enemy_a", 1.0),
+            spawn("enemy_b", 5.0),
+        ];
+        map.schedule = vec![schedule("enemy_a", 1, 2), schedule("enemy_b", 2, 1)];
+        map.roster = vec![
+            roster("enemy_a", "WALK", "NORMAL"),
+            roster("enemy_b", "FLY", "ELITE"),
+        ];
+        map.modifiers = modifiers;
+        map
+    }
+
+    #[test]
+    fn metrics_from_a_small_stage() {
+        let m = analyze(&small_stage(vec![double_b("")]), &handbook(), "NORMAL");
+        assert_eq!(m.enemies, 3);
+        assert!((m.total_hp - 8000.0).abs() < 1e-9);
+        assert_eq!(m.waves.len(), 2);
+        assert!((m.waves[1].total_hp - 6000.0).abs() < 1e-9);
+        assert_eq!((m.peak_enemies, m.peak_at), (2, 1.0));
+        assert!((m.arts_share - 0.6).abs() < 1e-9);
+        assert_eq!((m.flying, m.elite, m.boss), (1, 1, 0));
+        assert_eq!((m.melee_tiles, m.ranged_tiles), (2, 1));
+        assert!((m.duration - 7.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn challenge_modifiers_only_count_in_challenge_mode() {
+        let map = small_stage(vec![double_b("FOUR_STAR")]);
+        let normal = analyze(&map, &handbook(), "NORMAL");
+        let challenge = analyze(&map, &handbook(), "FOUR_STAR");
+        assert!((normal.total_hp - 5000.0).abs() < 1e-9);
+        assert!((challenge.total_hp - 8000.0).abs() < 1e-9);
+    }
+}
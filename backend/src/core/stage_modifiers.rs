@@ -0,0 +1,532 @@
+//! Effective enemy stats for a stage under a risk selection: the level's own
+//! `Runes` (filtered by difficulty) plus any picked CC rune packs, applied to
+//! each roster enemy at the stat level the stage fields it at.
+//!
+//! Rune packs come from `crisis_v2_table.RecalRuneData` (the permanent CC
+//! stages) only. A running season's rune tables ship outside `crisis_v2_table`
+//! and aren't loaded, so seasonal stages list no selectable runes. Only
+//! enemy-facing effects are evaluated — attribute buffs and enemy
+//! replacement; everything else is passed through as `otherEffects`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::core::gamedata::types::enemy::{EnemyAttributes, EnemyHandbook};
+use crate::core::gamedata::types::level::{Modifier, ModifierValue, StageMap};
+
+/// Source label for the level's own runes.
+pub const STAGE_SOURCE: &str = "stage";
+/// The one table selectable rune packs are read from.
+pub const RUNE_TABLE: &str = "crisis_v2_table.RecalRuneData";
+
+/// A modifier plus the enemy selector CC runes carry beside the blackboard.
+/// Empty filters match everything.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopedModifier {
+    #[serde(flatten)]
+    pub modifier: Modifier,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub enemy_ids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_enemy_ids: Vec<String>,
+    /// `NORMAL` / `ELITE` / `BOSS`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub enemy_levels: Vec<String>,
+}
+
+impl ScopedModifier {
+    fn unscoped(modifier: Modifier) -> Self {
+        Self {
+            modifier,
+            enemy_ids: Vec::new(),
+            exclude_enemy_ids: Vec::new(),
+            enemy_levels: Vec::new(),
+        }
+    }
+
+    fn selects(&self, enemy_id: &str, level: &str) -> bool {
+        (self.enemy_ids.is_empty() || self.enemy_ids.iter().any(|id| id == enemy_id))
+            && !self.exclude_enemy_ids.iter().any(|id| id == enemy_id)
+            && (self.enemy_levels.is_empty() || self.enemy_levels.iter().any(|l| l == level))
+    }
+
+    fn touches_enemies(&self) -> bool {
+        matches!(
+            self.modifier.key.as_str(),
+            "enemy_attribute_mul" | "enemy_attribute_add" | "level_enemy_replace"
+        )
+    }
+}
+
+/// A selectable CC rune (a packed group of modifiers) for one stage.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunePack {
+    pub id: String,
+    /// Risk the rune adds.
+    pub points: f64,
+    pub score: i64,
+    /// Always active on this stage.
+    pub essential: bool,
+    /// Runes sharing a group are mutually exclusive.
+    pub exclusive_group: Option<String>,
+    pub description: String,
+    pub modifiers: Vec<ScopedModifier>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnemyStatSheet {
+    pub enemy_id: String,
+    pub name: String,
+    pub level: String,
+    pub stat_level: i32,
+    pub count: u32,
+    /// Enemy fielded instead, when a rune replaces this one.
+    pub replaced_by: Option<String>,
+    /// Handbook stats at `stat_level`.
+    pub base: EnemyAttributes,
+    pub effective: EnemyAttributes,
+    /// Sources (`stage` or a rune id) whose modifiers changed this enemy.
+    pub sources: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourcedModifier {
+    pub source: String,
+    #[serde(flatten)]
+    pub modifier: ScopedModifier,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageStatSheet {
+    pub stage_id: String,
+    pub difficulty: String,
+    /// Sum of the selected runes' points.
+    pub risk: f64,
+    pub runes: Vec<String>,
+    pub enemies: Vec<EnemyStatSheet>,
+    /// Active modifiers that don't act on enemy stats (operator debuffs,
+    /// tile effects, …).
+    pub other_effects: Vec<SourcedModifier>,
+}
+
+/// `from -> to` of a `level_enemy_replace` rune.
+fn replacement(modifier: &Modifier) -> Option<(&str, &str)> {
+    let value = |key: &str| {
+        modifier
+            .blackboard
+            .iter()
+            .find(|b| b.key == key)
+            .and_then(|b| b.value_str.as_deref())
+    };
+    Some((value("key")?, value("value")?))
+}
+
+/// Apply the stage's runes at `difficulty` plus `packs` to every roster enemy.
+pub fn evaluate(
+    map: &StageMap,
+    enemies: &EnemyHandbook,
+    difficulty: &str,
+    packs: &[&RunePack],
+) -> StageStatSheet {
+    let active: Vec<(&str, ScopedModifier)> = map
+        .modifiers
+        .iter()
+        .filter(|m| m.in_difficulty(difficulty))
+        .map(|m| (STAGE_SOURCE, ScopedModifier::unscoped(m.clone())))
+        .chain(
+            packs
+                .iter()
+                .flat_map(|p| p.modifiers.iter().map(|m| (p.id.as_str(), m.clone()))),
+        )
+        .collect();
+
+    let sheets = map
+        .roster
+        .iter()
+        .map(|r| {
+            let mut sources: Vec<String> = Vec::new();
+            let mut note = |source: &str| {
+                if !sources.iter().any(|s| s == source) {
+                    sources.push(source.to_owned());
+                }
+            };
+
+            let stats_of = |id: &str| {
+                enemies
+                    .enemy_data
+                    .get(id)
+                    .and_then(|e| e.level_stats(r.stat_level))
+                    .map(|l| l.attributes.clone())
+                    .unwrap_or_default()
+            };
+            let base = stats_of(&r.enemy_id);
+
+            let mut fielded = r.enemy_id.as_str();
+            for (source, scoped) in &active {
+                if scoped.modifier.key == "level_enemy_replace"
+                    && let Some((from, to)) = replacement(&scoped.modifier)
+                    && from == fielded
+                {
+                    fielded = to;
+                    note(source);
+                }
+            }
+            let mut effective = if fielded == r.enemy_id {
+                base.clone()
+            } else {
+                stats_of(fielded)
+            };
+
+            for (source, scoped) in &active {
+                if !scoped.selects(fielded, &r.level) {
+                    continue;
+                }
+                let before = effective.clone();
+                scoped.modifier.apply_to_enemy(fielded, &mut effective);
+                if !same_stats(&before, &effective) {
+                    note(source);
+                }
+            }
+
+            EnemyStatSheet {
+                enemy_id: r.enemy_id.clone(),
+                name: r.name.clone(),
+                level: r.level.clone(),
+                stat_level: r.stat_level,
+                count: r.count,
+                replaced_by: (fielded != r.enemy_id).then(|| fielded.to_owned()),
+                base,
+                effective,
+                sources,
+            }
+        })
+        .collect();
+
+    let other_effects = active
+        .iter()
+        .filter(|(_, m)| !m.touches_enemies())
+        .map(|(source, m)| SourcedModifier {
+            source: (*source).to_owned(),
+            modifier: m.clone(),
+        })
+        .collect();
+
+    StageStatSheet {
+        stage_id: map.stage_id.clone(),
+        difficulty: difficulty.to_owned(),
+        risk: packs.iter().map(|p| p.points).sum(),
+        runes: packs.iter().map(|p| p.id.clone()).collect(),
+        enemies: sheets,
+        other_effects,
+    }
+}
+
+fn same_stats(a: &EnemyAttributes, b: &EnemyAttributes) -> bool {
+    a.max_hp == b.max_hp
+        && a.atk == b.atk
+        && a.def == b.def
+        && a.magic_resistance == b.magic_resistance
+        && a.move_speed == b.move_speed
+        && a.attack_speed == b.attack_speed
+        && a.base_attack_time == b.base_attack_time
+        && a.hp_recovery_per_sec == b.hp_recovery_per_sec
+}
+
+// ============================================================================
+// crisis_v2_table
+// ============================================================================
+
+/// `name` in PascalCase (as the unpacker writes it) or camelCase.
+fn field<'a>(v: &'a Value, name: &str) -> Option<&'a Value> {
+    v.get(name).or_else(|| {
+        let mut chars = name.chars();
+        let camel: String = chars
+            .next()
+            .map(|c| c.to_ascii_lowercase())
+            .into_iter()
+            .chain(chars)
+            .collect();
+        v.get(camel)
+    })
+}
+
+fn str_field<'a>(v: &'a Value, name: &str) -> Option<&'a str> {
+    field(v, name).and_then(Value::as_str)
+}
+
+fn strings(v: Option<&Value>) -> Vec<String> {
+    v.and_then(Value::as_array)
+        .map(|a| {
+            a.iter()
+                .filter_map(Value::as_str)
+                .map(str::to_owned)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// A FlatBuffer-style map (array of `{key, value}`) or a plain object.
+fn entries(v: Option<&Value>) -> Vec<(&str, &Value)> {
+    match v {
+        Some(Value::Object(m)) => m.iter().map(|(k, v)| (k.as_str(), v)).collect(),
+        Some(Value::Array(a)) => a
+            .iter()
+            .filter_map(|it| Some((it.get("key")?.as_str()?, it.get("value")?)))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn parse_rune(v: &Value) -> Option<ScopedModifier> {
+    let key = str_field(v, "Key")?.to_owned();
+    let blackboard = field(v, "Blackboard")
+        .and_then(Value::as_array)
+        .map(|a| {
+            a.iter()
+                .filter_map(|b| {
+                    Some(ModifierValue {
+                        key: str_field(b, "Key")?.to_owned(),
+                        value: field(b, "Value").and_then(Value::as_f64).unwrap_or(0.0),
+                        value_str: str_field(b, "ValueStr")
+                            .filter(|s| !s.is_empty())
+                            .map(str::to_owned),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let selector = field(v, "Selector");
+    let list = |name: &str| strings(selector.and_then(|s| field(s, name)));
+    Some(ScopedModifier {
+        modifier: Modifier {
+            key,
+            difficulty: String::new(),
+            profession: selector
+                .and_then(|s| str_field(s, "ProfessionMask"))
+                .unwrap_or_default()
+                .to_owned(),
+            blackboard,
+        },
+        enemy_ids: list("EnemyIdFilter"),
+        exclude_enemy_ids: list("EnemyIdExcludeFilter"),
+        enemy_levels: list("EnemyLevelTypeFilter"),
+    })
+}
+
+/// Selectable runes for `stage_id` across every recal-rune season, sorted by
+/// id. Empty when the stage isn't a permanent CC stage.
+pub fn recal_rune_packs(table: &Value, stage_id: &str) -> Vec<RunePack> {
+    let Some(recal) = field(table, "RecalRuneData") else {
+        return Vec::new();
+    };
+    let mut packs: Vec<RunePack> = entries(field(recal, "Seasons"))
+        .into_iter()
+        .flat_map(|(_, season)| entries(field(season, "Stages")))
+        .filter(|(key, stage)| *key == stage_id || str_field(stage, "StageId") == Some(stage_id))
+        .flat_map(|(_, stage)| entries(field(stage, "Runes")))
+        .filter_map(|(key, rune)| {
+            let packed = field(rune, "PackedRune");
+            let exclusive_group = str_field(rune, "ExclusiveGroupId")
+                .or_else(|| packed.and_then(|p| str_field(p, "MutexGroupKey")))
+                .filter(|s| !s.is_empty())
+                .map(str::to_owned);
+            Some(RunePack {
+                id: str_field(rune, "RuneId").unwrap_or(key).to_owned(),
+                points: packed
+                    .and_then(|p| field(p, "Points"))
+                    .and_then(Value::as_f64)
+                    .unwrap_or(0.0),
+                score: field(rune, "Score").and_then(Value::as_i64).unwrap_or(0),
+                essential: field(rune, "Essential")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false),
+                exclusive_group,
+                description: packed
+                    .and_then(|p| str_field(p, "Description"))
+                    .unwrap_or_default()
+                    .to_owned(),
+                modifiers: field(packed?, "Runes")?
+                    .as_array()?
+                    .iter()
+                    .filter_map(parse_rune)
+                    .collect(),
+            })
+        })
+        .collect();
+    packs.sort_by(|a, b| a.id.cmp(&b.id));
+    packs
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::core::test_support::{enemy, roster, stage_map};
+
+    fn value(key: &str, value: f64) -> ModifierValue {
+        ModifierValue {
+            key: key.into(),
+            value,
+            value_str: None,
+        }
+    }
+
+    fn text(key: &str, value: &str) -> ModifierValue {
+        ModifierValue {
+            key: key.into(),
+            value: 0.0,
+            value_str: Some(value.into()),
+        }
+    }
+
+    fn modifier(key: &str, difficulty: &str, blackboard: Vec<ModifierValue>) -> Modifier {
+        Modifier {
+            key: key.into(),
+            difficulty: difficulty.into(),
+            profession: String::new(),
+            blackboard,
+        }
+    }
+
+    fn map(modifiers: Vec<Modifier>) -> StageMap {
+        let mut map = stage_map();
+        map.stage_id = "crisis_v2_s".into();
+        map.roster = vec![
+            roster("enemy_a", "WALK", "NORMAL"),
+            roster("enemy_b", "WALK", "BOSS"),
+        ];
+        map.modifiers = modifiers;
+        map
+    }
+
+    #[test]
+    fn stage_runes_and_packs_stack() {
+        let mut handbook = EnemyHandbook::default();
+        for e in [
+            enemy("enemy_a", 1000, 100),
+            enemy("enemy_b", 5000, 500),
+            enemy("enemy_a_2", 2000, 200),
+        ] {
+            handbook.enemy_data.insert(e.enemy_id.clone(), e);
+        }
+
+        let map = map(vec![
+            modifier("enemy_attribute_mul", "ALL", vec![value("max_hp", 2.0)]),
+            // Challenge mode only.
+            modifier("enemy_attribute_mul", "FOUR_STAR", vec![value("atk", 3.0)]),
+        ]);
+        let bosses = RunePack {
+            id: "rune_boss".into(),
+            points: 3.0,
+            score: 0,
+            essential: false,
+            exclusive_group: None,
+            description: String::new(),
+            modifiers: vec![
+                ScopedModifier {
+                    enemy_levels: vec!["BOSS".into()],
+                    ..ScopedModifier::unscoped(modifier(
+                        "enemy_attribute_add",
+                        "",
+                        vec![value("def", 300.0)],
+                    ))
+                },
+                ScopedModifier::unscoped(modifier("char_attribute_mul", "", Vec::new())),
+            ],
+        };
+        let replace = RunePack {
+            id: "rune_replace".into(),
+            points: 2.0,
+            modifiers: vec![ScopedModifier::unscoped(modifier(
+                "level_enemy_replace",
+                "",
+                vec![text("key", "enemy_a"), text("value", "enemy_a_2")],
+            ))],
+            ..bosses.clone()
+        };
+
+        let sheet = evaluate(&map, &handbook, "NORMAL", &[&bosses, &replace]);
+        assert!((sheet.risk - 5.0).abs() < 1e-9);
+        let (a, b) = (&sheet.enemies[0], &sheet.enemies[1]);
+
+        assert_eq!(a.replaced_by.as_deref(), Some("enemy_a_2"));
+        assert_eq!((a.base.max_hp, a.effective.max_hp), (1000, 4000));
+        assert_eq!((a.effective.atk, a.effective.def), (200, 0));
+        assert_eq!(a.sources, ["rune_replace", "stage"]);
+
+        assert_eq!((b.effective.max_hp, b.effective.atk), (10000, 500));
+        assert_eq!(b.effective.def, 300);
+        assert_eq!(b.sources, ["stage", "rune_boss"]);
+
+        assert_eq!(sheet.other_effects.len(), 1);
+        assert_eq!(
+            sheet.other_effects[0].modifier.modifier.key,
+            "char_attribute_mul"
+        );
+
+        let challenge = evaluate(&map, &handbook, "FOUR_STAR", &[]);
+        assert_eq!(challenge.enemies[1].effective.atk, 1500);
+    }
+
+    #[test]
+    fn packs_from_crisis_table() {
+        let table = json!({
+            "RecalRuneData": {
+                "Seasons": [{
+                    "key": "recalrune_1",
+                    "value": {
+                        "Stages": [{
+                            "key": "crisis_v2_s",
+                            "value": {
+                                "StageId": "crisis_v2_s",
+                                "Runes": [{
+                                    "key": "rune_1",
+                                    "value": {
+                                        "RuneId": "rune_1",
+                                        "Score": 10,
+                                        "Essential": false,
+                                        "ExclusiveGroupId": "hp",
+                                        "PackedRune": {
+                                            "Id": "rune_1",
+                                            "Points": 2.0,
+                                            "Description": "Enemy HP +30%",
+                                            "Runes": [{
+                                                "Key": "enemy_attribute_mul",
+                                                "Selector": {
+                                                    "EnemyIdFilter": ["enemy_a"],
+                                                    "EnemyLevelTypeFilter": []
+                                                },
+                                                "Blackboard": [
+                                                    {"Key": "max_hp", "Value": 1.3, "ValueStr": ""}
+                                                ]
+                                            }]
+                                        }
+                                    }
+                                }]
+                            }
+                        }]
+                    }
+                }]
+            }
+        });
+
+        let packs = recal_rune_packs(&table, "crisis_v2_s");
+        assert_eq!(packs.len(), 1);
+        let pack = &packs[0];
+        assert_eq!(
+            (pack.id.as_str(), pack.points, pack.score),
+            ("rune_1", 2.0, 10)
+        );
+        assert_eq!(pack.exclusive_group.as_deref(), Some("hp"));
+        let rune = &pack.modifiers[0];
+        assert_eq!(rune.enemy_ids, ["enemy_a"]);
+        assert!(rune.modifier.blackboard[0].value_str.is_none());
+        assert!(recal_rune_packs(&table, "main_01-07").is_empty());
+    }
+}
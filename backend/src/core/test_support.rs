@@ -0,0 +1,79 @@
+//! Shared fixtures for the stage-analysis tests (`wave_simulation`,
+//! `stage_metrics`, `stage_modifiers`).
+
+use crate::core::gamedata::types::enemy::{Enemy, EnemyAttributes, EnemyLevelStats, EnemyStats};
+use crate::core::gamedata::types::level::{MapOptions, RosterEntry, SpawnOut, StageMap};
+
+/// An empty stage: no tiles, routes or enemies, stock deployment options.
+pub fn stage_map() -> StageMap {
+    StageMap {
+        stage_id: "test".into(),
+        level_id: String::new(),
+        code: String::new(),
+        name: None,
+        width: 0,
+        height: 0,
+        tiles: Vec::new(),
+        routes: Vec::new(),
+        waves: Vec::new(),
+        spawns: Vec::new(),
+        schedule: Vec::new(),
+        roster: Vec::new(),
+        options: MapOptions {
+            char_limit: 8,
+            max_life: 3,
+            initial_cost: 10,
+            max_cost: 99,
+            cost_increase_time: 1.0,
+            move_multiplier: 1.0,
+            is_training: false,
+        },
+        hidden_routes: Vec::new(),
+        modifiers: Vec::new(),
+        duration: 0.0,
+    }
+}
+
+/// A `NORMAL`-level spawn on route 0 at speed 1.
+pub fn spawn(enemy_id: &str, t0: f64) -> SpawnOut {
+    SpawnOut {
+        route: 0,
+        enemy_id: enemy_id.into(),
+        t0,
+        speed: 1.0,
+        level: "NORMAL".into(),
+    }
+}
+
+/// A handbook enemy with one stat level.
+pub fn enemy(id: &str, hp: i32, atk: i32) -> Enemy {
+    Enemy {
+        enemy_id: id.into(),
+        stats: Some(EnemyStats {
+            levels: vec![EnemyLevelStats {
+                attributes: EnemyAttributes {
+                    max_hp: hp,
+                    atk,
+                    move_speed: 1.0,
+                    ..EnemyAttributes::default()
+                },
+                ..EnemyLevelStats::default()
+            }],
+        }),
+        ..Enemy::default()
+    }
+}
+
+/// A stage roster entry fielding `id` once at stat level 0.
+pub fn roster(id: &str, motion: &str, level: &str) -> RosterEntry {
+    RosterEntry {
+        enemy_id: id.into(),
+        name: id.into(),
+        level: level.into(),
+        damage_type: Vec::new(),
+        attack_type: None,
+        motion: motion.into(),
+        count: 1,
+        stat_level: 0,
+    }
+}
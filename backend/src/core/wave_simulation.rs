@@ -0,0 +1,455 @@
+//! Undefended wave replay for the Stage Viewer: every spawn in a [`StageMap`]
+//! walks its route at its own speed with nothing blocking it, sampled at a
+//! fixed step, plus when each enemy leaks and which enemy reaches each tile
+//! first.
+//!
+//! Walking routes follow the tile grid between checkpoints (8-directional,
+//! no corner cutting, like the in-game pathfinder); flying routes go straight.
+//! Speeds are `move_speed × options.moveMultiplier` tiles/sec. `WAIT_*`
+//! checkpoints aren't in the map's routes, so timings are a lower bound for
+//! routes that pause.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use serde::Serialize;
+
+use crate::core::gamedata::types::level::{Point, StageMap, TileCell};
+
+pub const DEFAULT_STEP: f64 = 1.0;
+/// Smallest step accepted.
+pub const MIN_STEP: f64 = 0.1;
+/// Most frames returned; long stages are sampled more coarsely than asked.
+const MAX_FRAMES: usize = 1500;
+/// Sampling resolution (tiles) when tracing which tiles a path crosses.
+const TRACE_RESOLUTION: f64 = 0.1;
+const MIN_SPEED: f64 = 0.05;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnemyPosition {
+    /// 1-based spawn ordinal, as in the map's `schedule[].order`.
+    pub ordinal: u32,
+    pub enemy_id: String,
+    pub x: f64,
+    pub y: f64,
+    /// `[x, y]` of the tile the enemy stands on.
+    pub tile: [i32; 2],
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Frame {
+    pub t: f64,
+    pub enemies: Vec<EnemyPosition>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Leak {
+    pub ordinal: u32,
+    pub enemy_id: String,
+    pub route: usize,
+    pub spawned_at: f64,
+    pub leaked_at: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TileReach {
+    pub x: i32,
+    pub y: i32,
+    /// Seconds from stage start.
+    pub at: f64,
+    pub ordinal: u32,
+    pub enemy_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WaveSimulation {
+    pub stage_id: String,
+    pub step: f64,
+    pub move_multiplier: f64,
+    /// Time the last enemy leaks.
+    pub duration: f64,
+    /// Each route as actually walked: tile centres for grid paths.
+    pub paths: Vec<Vec<Point>>,
+    pub frames: Vec<Frame>,
+    /// In leak order.
+    pub leaks: Vec<Leak>,
+    /// First enemy onto each tile, earliest first.
+    pub first_reach: Vec<TileReach>,
+}
+
+/// A path with cumulative distances, for position-at-distance lookups.
+struct Track {
+    points: Vec<Point>,
+    cumulative: Vec<f64>,
+}
+
+impl Track {
+    fn new(points: Vec<Point>) -> Self {
+        let mut cumulative = Vec::with_capacity(points.len());
+        let mut total = 0.0;
+        for (i, p) in points.iter().enumerate() {
+            if i > 0 {
+                total += distance(points[i - 1], *p);
+            }
+            cumulative.push(total);
+        }
+        Self { points, cumulative }
+    }
+
+    fn length(&self) -> f64 {
+        self.cumulative.last().copied().unwrap_or(0.0)
+    }
+
+    fn position(&self, d: f64) -> Point {
+        let Some(&first) = self.points.first() else {
+            return Point { x: 0.0, y: 0.0 };
+        };
+        let i = self.cumulative.partition_point(|&c| c <= d);
+        if i == 0 {
+            return first;
+        }
+        if i >= self.points.len() {
+            return self.points[self.points.len() - 1];
+        }
+        let (a, b) = (self.points[i - 1], self.points[i]);
+        let span = self.cumulative[i] - self.cumulative[i - 1];
+        let f = if span > 0.0 {
+            (d - self.cumulative[i - 1]) / span
+        } else {
+            0.0
+        };
+        Point {
+            x: a.x + (b.x - a.x) * f,
+            y: a.y + (b.y - a.y) * f,
+        }
+    }
+}
+
+fn distance(a: Point, b: Point) -> f64 {
+    ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt()
+}
+
+fn tile_of(p: Point) -> [i32; 2] {
+    [p.x.round() as i32, p.y.round() as i32]
+}
+
+fn walkable(tiles: &[Vec<TileCell>], x: i32, y: i32) -> bool {
+    usize::try_from(y)
+        .ok()
+        .and_then(|y| tiles.get(y))
+        .and_then(|row| usize::try_from(x).ok().and_then(|x| row.get(x)))
+        .is_some_and(|t| t.passable || matches!(t.kind.as_str(), "start" | "end"))
+}
+
+/// Shortest 8-directional tile path from `from` to `to` (both included), with
+/// diagonals only where both adjacent orthogonal tiles are walkable. `None`
+/// when unreachable.
+fn grid_path(tiles: &[Vec<TileCell>], from: [i32; 2], to: [i32; 2]) -> Option<Vec<[i32; 2]>> {
+    const STRAIGHT: u32 = 10;
+    const DIAGONAL: u32 = 14;
+    let open = |[x, y]: [i32; 2]| walkable(tiles, x, y) || [x, y] == from || [x, y] == to;
+
+    let mut best: HashMap<[i32; 2], u32> = HashMap::from([(from, 0)]);
+    let mut came_from: HashMap<[i32; 2], [i32; 2]> = HashMap::new();
+    let mut heap = BinaryHeap::from([Reverse((0, from))]);
+    while let Some(Reverse((cost, at))) = heap.pop() {
+        if at == to {
+            let mut path = vec![to];
+            let mut cur = to;
+            while let Some(&prev) = came_from.get(&cur) {
+                path.push(prev);
+                cur = prev;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        if best.get(&at).is_some_and(|&b| cost > b) {
+            continue;
+        }
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                if dx == 0 && dy == 0 {
+                    continue;
+                }
+                let next = [at[0] + dx, at[1] + dy];
+                if !open(next) {
+                    continue;
+                }
+                let diagonal = dx != 0 && dy != 0;
+                if diagonal && !(open([at[0] + dx, at[1]]) && open([at[0], at[1] + dy])) {
+                    continue;
+                }
+                let next_cost = cost + if diagonal { DIAGONAL } else { STRAIGHT };
+                if best.get(&next).is_none_or(|&b| next_cost < b) {
+                    best.insert(next, next_cost);
+                    came_from.insert(next, at);
+                    heap.push(Reverse((next_cost, next)));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// The route as walked. Unreachable legs (a checkpoint on a blocked tile)
+/// fall back to a straight line.
+fn route_path(map: &StageMap, route: usize) -> Vec<Point> {
+    let Some(r) = map.routes.get(route) else {
+        return Vec::new();
+    };
+    if r.motion != "WALK" {
+        return r.points.clone();
+    }
+    let mut out: Vec<Point> = Vec::new();
+    for leg in r.points.windows(2) {
+        let tiles = grid_path(&map.tiles, tile_of(leg[0]), tile_of(leg[1]));
+        let leg_points: Vec<Point> = match tiles {
+            Some(tiles) => tiles
+                .into_iter()
+                .map(|[x, y]| Point {
+                    x: f64::from(x),
+                    y: f64::from(y),
+                })
+                .collect(),
+            None => leg.to_vec(),
+        };
+        let skip = usize::from(!out.is_empty());
+        out.extend(leg_points.into_iter().skip(skip));
+    }
+    if out.is_empty() {
+        out.clone_from(&r.points);
+    }
+    out
+}
+
+/// One spawned enemy on its walk to the goal.
+struct Runner<'a> {
+    ordinal: u32,
+    enemy_id: &'a str,
+    route: usize,
+    t0: f64,
+    speed: f64,
+    leak_at: f64,
+}
+
+fn move_multiplier(map: &StageMap) -> f64 {
+    if map.options.move_multiplier > 0.0 {
+        map.options.move_multiplier
+    } else {
+        1.0
+    }
+}
+
+fn runners<'a>(map: &'a StageMap, tracks: &[Track]) -> Vec<Runner<'a>> {
+    let multiplier = move_multiplier(map);
+    map.spawns
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.route < tracks.len())
+        .map(|(i, s)| {
+            let speed = (s.speed * multiplier).max(MIN_SPEED);
+            Runner {
+                ordinal: i as u32 + 1,
+                enemy_id: &s.enemy_id,
+                route: s.route,
+                t0: s.t0,
+                speed,
+                leak_at: s.t0 + tracks[s.route].length() / speed,
+            }
+        })
+        .collect()
+}
+
+fn leaks_of(runners: &[Runner]) -> Vec<Leak> {
+    let mut leaks: Vec<Leak> = runners
+        .iter()
+        .map(|r| Leak {
+            ordinal: r.ordinal,
+            enemy_id: r.enemy_id.to_owned(),
+            route: r.route,
+            spawned_at: r.t0,
+            leaked_at: r.leak_at,
+        })
+        .collect();
+    leaks.sort_by(|a, b| a.leaked_at.total_cmp(&b.leaked_at));
+    leaks
+}
+
+/// When each enemy spawns and leaks, in leak order, without the per-step
+/// frames of [`simulate`].
+pub fn leak_timeline(map: &StageMap) -> Vec<Leak> {
+    let tracks: Vec<Track> = (0..map.routes.len())
+        .map(|r| Track::new(route_path(map, r)))
+        .collect();
+    leaks_of(&runners(map, &tracks))
+}
+
+/// Replay `map`'s spawns with no defense, sampling every `step` seconds
+/// (widened so no more than [`MAX_FRAMES`] frames come back).
+pub fn simulate(map: &StageMap, step: f64) -> WaveSimulation {
+    let paths: Vec<Vec<Point>> = (0..map.routes.len()).map(|r| route_path(map, r)).collect();
+    let tracks: Vec<Track> = paths.iter().cloned().map(Track::new).collect();
+    let runners = runners(map, &tracks);
+    let duration = runners.iter().map(|r| r.leak_at).fold(0.0_f64, f64::max);
+    let step = step.max(MIN_STEP).max(duration / (MAX_FRAMES - 1) as f64);
+
+    let frame_count = ((duration / step).ceil() as usize + 1).min(MAX_FRAMES);
+    let frames: Vec<Frame> = (0..frame_count)
+        .map(|i| {
+            let t = i as f64 * step;
+            let enemies = runners
+                .iter()
+                .filter(|r| r.t0 <= t && t < r.leak_at)
+                .map(|r| {
+                    let p = tracks[r.route].position((t - r.t0) * r.speed);
+                    EnemyPosition {
+                        ordinal: r.ordinal,
+                        enemy_id: r.enemy_id.to_owned(),
+                        x: p.x,
+                        y: p.y,
+                        tile: tile_of(p),
+                    }
+                })
+                .collect();
+            Frame { t, enemies }
+        })
+        .collect();
+
+    let leaks = leaks_of(&runners);
+
+    // Tile entry distances per route, then the earliest runner onto each tile.
+    let entries: Vec<Vec<([i32; 2], f64)>> = tracks
+        .iter()
+        .map(|track| {
+            let mut seen: Vec<([i32; 2], f64)> = Vec::new();
+            let samples = (track.length() / TRACE_RESOLUTION).ceil() as usize;
+            for k in 0..=samples {
+                let d = (k as f64 * TRACE_RESOLUTION).min(track.length());
+                let tile = tile_of(track.position(d));
+                if !seen.iter().any(|(t, _)| *t == tile) {
+                    seen.push((tile, d));
+                }
+            }
+            seen
+        })
+        .collect();
+    let mut reach: HashMap<[i32; 2], TileReach> = HashMap::new();
+    for r in &runners {
+        for &(tile, d) in &entries[r.route] {
+            let at = r.t0 + d / r.speed;
+            if reach.get(&tile).is_none_or(|prev| at < prev.at) {
+                reach.insert(
+                    tile,
+                    TileReach {
+                        x: tile[0],
+                        y: tile[1],
+                        at,
+                        ordinal: r.ordinal,
+                        enemy_id: r.enemy_id.to_owned(),
+                    },
+                );
+            }
+        }
+    }
+    let mut first_reach: Vec<TileReach> = reach.into_values().collect();
+    first_reach.sort_by(|a, b| {
+        a.at.total_cmp(&b.at)
+            .then_with(|| (a.y, a.x).cmp(&(b.y, b.x)))
+    });
+
+    WaveSimulation {
+        stage_id: map.stage_id.clone(),
+        step,
+        move_multiplier: move_multiplier(map),
+        duration,
+        paths,
+        frames,
+        leaks,
+        first_reach,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::gamedata::types::level::{RouteOut, SpawnOut};
+    use crate::core::test_support::{spawn, stage_map};
+
+    fn cell(passable: bool) -> TileCell {
+        TileCell {
+            kind: if passable { "road" } else { "forbidden" }.to_owned(),
+            tile_key: String::new(),
+            height_type: String::new(),
+            buildable: String::new(),
+            passable,
+        }
+    }
+
+    /// 5×3 board with a wall in the middle column except the bottom row.
+    fn map(motion: &str, speed: f64) -> StageMap {
+        let tiles = (0..3)
+            .map(|y| (0..5).map(|x| cell(x != 2 || y == 2)).collect())
+            .collect();
+        let mut map = stage_map();
+        map.width = 5;
+        map.height = 3;
+        map.tiles = tiles;
+        map.routes = vec![RouteOut {
+            motion: motion.into(),
+            points: vec![Point { x: 0.0, y: 0.0 }, Point { x: 4.0, y: 0.0 }],
+        }];
+        map.spawns = vec![
+            SpawnOut {
+                speed,
+                ..spawn("enemy_a", 0.0)
+            },
+            SpawnOut {
+                speed,
+                ..spawn("enemy_b", 2.0)
+            },
+        ];
+        map.options.move_multiplier = 0.5;
+        map
+    }
+
+    #[test]
+    fn long_waves_cap_the_frame_count() {
+        let mut map = map("FLY", 1.0);
+        map.spawns[1].t0 = 1000.0;
+        let sim = simulate(&map, MIN_STEP);
+        assert!(sim.frames.len() <= MAX_FRAMES);
+        assert!(sim.step > MIN_STEP);
+        assert!(sim.frames.last().unwrap().t >= sim.duration - 1e-9);
+    }
+
+    #[test]
+    fn walkers_detour_around_walls() {
+        let path = route_path(&map("WALK", 1.0), 0);
+        let tiles: Vec<[i32; 2]> = path.iter().map(|p| tile_of(*p)).collect();
+        assert!(tiles.contains(&[2, 2]), "must pass the gap: {tiles:?}");
+        assert!(!tiles.contains(&[2, 0]) && !tiles.contains(&[2, 1]));
+    }
+
+    #[test]
+    fn fliers_go_straight_and_leak_in_order() {
+        let sim = simulate(&map("FLY", 1.0), 1.0);
+        assert_eq!(sim.paths[0].len(), 2);
+        // 4 tiles at 1.0 × 0.5 tiles/sec.
+        assert_eq!(sim.leaks[0].ordinal, 1);
+        assert!((sim.leaks[0].leaked_at - 8.0).abs() < 1e-9);
+        assert!((sim.duration - 10.0).abs() < 1e-9);
+        assert_eq!(sim.frames.len(), 11);
+        assert_eq!(sim.frames[3].enemies.len(), 2);
+        let reach = sim
+            .first_reach
+            .iter()
+            .find(|r| [r.x, r.y] == [4, 0])
+            .unwrap();
+        assert_eq!(reach.ordinal, 1);
+    }
+}
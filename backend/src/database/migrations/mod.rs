@@ -31,6 +31,22 @@ const MIGRATIONS: &[(&str, &str)] = &[
         "v009_profile_sync_ts",
         include_str!("v009_profile_sync_ts.sql"),
     ),
+    (
+        "v010_stage_drop_rates",
+        include_str!("v010_stage_drop_rates.sql"),
+    ),
+    (
+        "v011_roster_history",
+        include_str!("v011_roster_history.sql"),
+    ),
+    (
+        "v012_status_originite",
+        include_str!("v012_status_originite.sql"),
+    ),
+    (
+        "v013_battle_replays",
+        include_str!("v013_battle_replays.sql"),
+    ),
 ];
 
 pub async fn run_migrations(pool: &PgPool) -> Result<(), sqlx::Error> {
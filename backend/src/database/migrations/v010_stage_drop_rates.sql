@@ -0,0 +1,15 @@
+-- Per-server stage drop samples imported from a Penguin Statistics drop-matrix
+-- export (bin/import_drop_rates.rs). stage_table only carries qualitative drop
+-- bands; these are the observed counts behind expected drops per run. Each
+-- import replaces the server's rows wholesale.
+--
+
+CREATE TABLE public.stage_drop_rates (
+    server_id smallint NOT NULL,
+    stage_id character varying(64) NOT NULL,
+    item_id character varying(64) NOT NULL,
+    quantity bigint NOT NULL,
+    times bigint NOT NULL,
+    imported_at timestamp with time zone DEFAULT now() NOT NULL,
+    PRIMARY KEY (server_id, stage_id, item_id)
+);
@@ -0,0 +1,35 @@
+-- Roster investment history. sp_sync_user_data overwrites user_operators on
+-- every sync, so the refresh path (app/services/roster.rs) diffs the roster
+-- before and after and records what changed here, plus one aggregate row per
+-- sync for time-series charts. The first sync of an account records no
+-- events: there is no "before" to diff against.
+--
+
+CREATE TABLE public.user_roster_events (
+    id bigserial PRIMARY KEY,
+    user_id uuid NOT NULL REFERENCES public.users(id) ON DELETE CASCADE,
+    operator_id character varying(50) NOT NULL,
+    -- obtained / elite / level / skill_level / mastery / module / potential
+    kind character varying(16) NOT NULL,
+    -- Skill index (mastery) or module id (module); NULL otherwise.
+    target character varying(50),
+    from_value smallint,
+    to_value smallint NOT NULL,
+    synced_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+CREATE INDEX idx_user_roster_events_user_time
+    ON public.user_roster_events USING btree (user_id, synced_at DESC);
+
+CREATE TABLE public.user_roster_snapshots (
+    user_id uuid NOT NULL REFERENCES public.users(id) ON DELETE CASCADE,
+    synced_at timestamp with time zone DEFAULT now() NOT NULL,
+    operators integer NOT NULL,
+    elite2 integer NOT NULL,
+    -- Sum of mastery levels across every skill.
+    masteries integer NOT NULL,
+    mastery3 integer NOT NULL,
+    -- Sum of unlocked module levels.
+    modules integer NOT NULL,
+    PRIMARY KEY (user_id, synced_at)
+);
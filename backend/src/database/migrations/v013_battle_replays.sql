@@ -0,0 +1,22 @@
+-- Archived auto-deploy replays. The game keeps one saved replay per stage, so
+-- the archive does too: an opt-in refresh (`POST /refresh?replays=...`)
+-- fetches each saved replay via `getBattleReplay` and overwrites the row.
+-- `replay` is the decoded `core::battle_replay::ReplayRecord`; the columns
+-- beside it are copies for listing without reading the blob.
+
+CREATE TABLE public.user_battle_replays (
+    user_id uuid NOT NULL REFERENCES public.users(id) ON DELETE CASCADE,
+    stage_id character varying(100) NOT NULL,
+    -- quest / campaignV2
+    battle_type character varying(16) NOT NULL,
+    saved_at timestamp with time zone NOT NULL,
+    cleared boolean NOT NULL,
+    -- Operator ids in squad order.
+    squad character varying(50)[] NOT NULL,
+    replay jsonb NOT NULL,
+    fetched_at timestamp with time zone DEFAULT now() NOT NULL,
+    PRIMARY KEY (user_id, stage_id)
+);
+
+CREATE INDEX idx_user_battle_replays_stage
+    ON public.user_battle_replays USING btree (stage_id);
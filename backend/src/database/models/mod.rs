@@ -2,6 +2,7 @@ pub mod audit;
 pub mod gacha;
 pub mod operator_notes;
 pub mod planner;
+pub mod replay;
 pub mod roster;
 pub mod score;
 pub mod tier_list;
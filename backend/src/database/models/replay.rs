@@ -0,0 +1,16 @@
+use serde::Serialize;
+use sqlx::types::chrono::{DateTime, Utc};
+
+/// A `user_battle_replays` row without the decoded replay.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaySummary {
+    pub stage_id: String,
+    /// `quest` / `campaignV2`.
+    pub battle_type: String,
+    pub saved_at: DateTime<Utc>,
+    pub cleared: bool,
+    /// Operator ids in squad order.
+    pub squad: Vec<String>,
+    pub fetched_at: DateTime<Utc>,
+}
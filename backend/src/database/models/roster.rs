@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
-use sqlx::types::Uuid;
+use sqlx::types::{
+    Uuid,
+    chrono::{DateTime, Utc},
+};
 
 /// One entry in a Doctor's published support roster (slot 0..2).
 #[derive(Debug, Clone, Serialize, sqlx::FromRow)]
@@ -40,3 +43,59 @@ pub struct RosterEntry {
     pub masteries: serde_json::Value, // jsonb_agg result
     pub modules: serde_json::Value,   // jsonb_agg result
 }
+
+/// One recorded roster change (`user_roster_events`).
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct RosterEvent {
+    pub id: i64,
+    pub operator_id: String,
+    /// `obtained` / `elite` / `level` / `skill_level` / `mastery` / `module` / `potential`.
+    pub kind: String,
+    /// Skill index for `mastery`, module id for `module`.
+    pub target: Option<String>,
+    pub from_value: Option<i16>,
+    pub to_value: i16,
+    pub synced_at: DateTime<Utc>,
+}
+
+/// Roster totals at one sync (`user_roster_snapshots`).
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct RosterSnapshot {
+    pub synced_at: DateTime<Utc>,
+    pub operators: i32,
+    pub elite2: i32,
+    pub masteries: i32,
+    pub mastery3: i32,
+    pub modules: i32,
+}
+
+/// A roster change found by diffing two syncs, before it's stored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewRosterEvent {
+    pub operator_id: String,
+    pub kind: &'static str,
+    pub target: Option<String>,
+    pub from_value: Option<i16>,
+    pub to_value: i16,
+}
+
+/// Investment made in one calendar month (UTC), summed from roster events.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct MonthlyProgress {
+    /// `YYYY-MM`.
+    pub month: String,
+    pub obtained: i64,
+    pub promotions: i64,
+    pub elite2: i64,
+    pub levels: i64,
+    pub skill_levels: i64,
+    pub mastery_levels: i64,
+    pub mastery3: i64,
+    pub module_levels: i64,
+    pub potentials: i64,
+    /// Distinct operators with any recorded change.
+    pub operators_invested: i64,
+}
@@ -45,6 +45,7 @@ pub struct UserProfile {
     pub skin_count: Option<i64>,
     pub non_default_skin_count: Option<i64>,
     pub updated_at: DateTime<Utc>,
+    pub originite: Option<i32>,
 }
 
 /// users table
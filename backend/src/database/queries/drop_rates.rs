@@ -0,0 +1,122 @@
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+
+use crate::core::drop_rates::{DropMatrix, DropMatrixEntry, STAGE_ID_SUFFIXES};
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DropRateRow {
+    pub stage_id: String,
+    pub item_id: String,
+    pub quantity: i64,
+    pub times: i64,
+}
+
+impl From<DropRateRow> for DropMatrixEntry {
+    fn from(row: DropRateRow) -> Self {
+        Self {
+            stage_id: row.stage_id,
+            item_id: row.item_id,
+            quantity: row.quantity.max(0) as u64,
+            times: row.times.max(0) as u64,
+        }
+    }
+}
+
+/// Replace a server's stored drop samples with `matrix`, atomically. Returns
+/// the number of rows written.
+pub async fn replace_drop_rates(
+    pool: &PgPool,
+    server_id: i16,
+    matrix: &DropMatrix,
+) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM stage_drop_rates WHERE server_id = $1")
+        .bind(server_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let stage_ids: Vec<&str> = matrix.matrix.iter().map(|e| e.stage_id.as_str()).collect();
+    let item_ids: Vec<&str> = matrix.matrix.iter().map(|e| e.item_id.as_str()).collect();
+    let quantities: Vec<i64> = matrix
+        .matrix
+        .iter()
+        .map(|e| i64::try_from(e.quantity).unwrap_or(i64::MAX))
+        .collect();
+    let times: Vec<i64> = matrix
+        .matrix
+        .iter()
+        .map(|e| i64::try_from(e.times).unwrap_or(i64::MAX))
+        .collect();
+
+    let inserted = sqlx::query(
+        r"
+        INSERT INTO stage_drop_rates (server_id, stage_id, item_id, quantity, times)
+        SELECT $1, * FROM UNNEST($2::VARCHAR[], $3::VARCHAR[], $4::BIGINT[], $5::BIGINT[])
+        ",
+    )
+    .bind(server_id)
+    .bind(&stage_ids)
+    .bind(&item_ids)
+    .bind(&quantities)
+    .bind(&times)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    tx.commit().await?;
+    Ok(inserted)
+}
+
+/// Every stored drop sample for a server, as a drop matrix.
+pub async fn get_drop_matrix(pool: &PgPool, server_id: i16) -> Result<DropMatrix, sqlx::Error> {
+    let rows = sqlx::query_as::<_, DropRateRow>(
+        "SELECT stage_id, item_id, quantity, times \
+         FROM stage_drop_rates WHERE server_id = $1",
+    )
+    .bind(server_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(DropMatrix {
+        matrix: rows.into_iter().map(Into::into).collect(),
+    })
+}
+
+/// Drop samples for one `stage_table` stage, including rows stored under its
+/// Penguin permanent/rerun id. Where both exist for an item, the larger sample
+/// wins.
+pub async fn get_stage_drop_rates(
+    pool: &PgPool,
+    server_id: i16,
+    stage_id: &str,
+) -> Result<Vec<DropRateRow>, sqlx::Error> {
+    let ids: Vec<String> = std::iter::once(stage_id.to_owned())
+        .chain(STAGE_ID_SUFFIXES.iter().map(|s| format!("{stage_id}{s}")))
+        .collect();
+
+    sqlx::query_as::<_, DropRateRow>(
+        r"
+        SELECT DISTINCT ON (item_id) $3 AS stage_id, item_id, quantity, times
+        FROM stage_drop_rates
+        WHERE server_id = $1 AND stage_id = ANY($2)
+        ORDER BY item_id, times DESC
+        ",
+    )
+    .bind(server_id)
+    .bind(&ids)
+    .bind(stage_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// When the server's drop samples were last imported, `None` if never.
+pub async fn drop_rates_imported_at(
+    pool: &PgPool,
+    server_id: i16,
+) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+    sqlx::query_scalar("SELECT MAX(imported_at) FROM stage_drop_rates WHERE server_id = $1")
+        .bind(server_id)
+        .fetch_one(pool)
+        .await
+}
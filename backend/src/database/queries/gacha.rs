@@ -209,3 +209,37 @@ pub async fn update_gacha_flags(
     .fetch_one(pool)
     .await
 }
+
+/// Insert imported pulls, skipping any already stored under the same
+/// `(char_id, pull_timestamp, batch_index)`, whatever pool they were stored
+/// under. Unlike `insert_batch` this never replaces existing rows. Returns
+/// the number of rows inserted.
+pub async fn insert_imported(
+    pool: &PgPool,
+    user_id: Uuid,
+    records: &serde_json::Value,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "INSERT INTO gacha_records
+            (user_id, char_id, pool_id, rarity, pull_timestamp, pool_name, gacha_type, batch_index)
+         SELECT DISTINCT ON (r.char_id, r.pull_timestamp, r.batch_index)
+            $1, r.char_id, r.pool_id, r.rarity, r.pull_timestamp, r.pool_name, r.gacha_type, r.batch_index
+         FROM jsonb_to_recordset($2) AS r(
+            char_id TEXT, pool_id TEXT, rarity SMALLINT, pull_timestamp BIGINT,
+            pool_name TEXT, gacha_type TEXT, batch_index SMALLINT
+         )
+         WHERE NOT EXISTS (
+            SELECT 1 FROM gacha_records g
+            WHERE g.user_id = $1
+              AND g.char_id = r.char_id
+              AND g.pull_timestamp = r.pull_timestamp
+              AND g.batch_index = r.batch_index
+         )
+         ON CONFLICT DO NOTHING",
+    )
+    .bind(user_id)
+    .bind(records)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
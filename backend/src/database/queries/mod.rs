@@ -1,4 +1,5 @@
 pub mod building;
+pub mod drop_rates;
 pub mod enemies;
 pub mod gacha;
 pub mod items;
@@ -7,6 +8,7 @@ pub mod medals;
 pub mod operator_notes;
 pub mod operator_ownership;
 pub mod planner;
+pub mod replays;
 pub mod roguelike;
 pub mod roster;
 pub mod sandbox;
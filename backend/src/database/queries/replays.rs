@@ -0,0 +1,97 @@
+use sqlx::PgPool;
+use sqlx::types::Json;
+use uuid::Uuid;
+
+use crate::core::battle_replay::ReplayRecord;
+use crate::database::models::replay::ReplaySummary;
+
+/// Store harvested replays, replacing any earlier replay of the same stage.
+pub async fn upsert_replays(
+    pool: &PgPool,
+    user_id: Uuid,
+    replays: &[ReplayRecord],
+) -> Result<(), sqlx::Error> {
+    if replays.is_empty() {
+        return Ok(());
+    }
+    sqlx::query(
+        r"
+        INSERT INTO user_battle_replays
+            (user_id, stage_id, battle_type, saved_at, cleared, squad, replay)
+        SELECT $1, r->>'stageId', r->>'battleType', (r->>'savedAt')::TIMESTAMPTZ,
+               (r->>'cleared')::BOOLEAN,
+               ARRAY(SELECT m->>'charId' FROM jsonb_array_elements(r->'squad') AS m),
+               r
+        FROM jsonb_array_elements($2) AS r
+        ON CONFLICT (user_id, stage_id) DO UPDATE SET
+            battle_type = EXCLUDED.battle_type, saved_at = EXCLUDED.saved_at,
+            cleared = EXCLUDED.cleared, squad = EXCLUDED.squad,
+            replay = EXCLUDED.replay, fetched_at = now()
+        ",
+    )
+    .bind(user_id)
+    .bind(Json(replays))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Stages with an archived replay, most recently saved first.
+pub async fn list_replays(pool: &PgPool, user_id: Uuid) -> Result<Vec<ReplaySummary>, sqlx::Error> {
+    sqlx::query_as::<_, ReplaySummary>(
+        r"
+        SELECT stage_id, battle_type, saved_at, cleared, squad, fetched_at
+        FROM user_battle_replays
+        WHERE user_id = $1
+        ORDER BY saved_at DESC
+        ",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Stage ids with an archived replay.
+pub async fn archived_stage_ids(pool: &PgPool, user_id: Uuid) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar("SELECT stage_id FROM user_battle_replays WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+}
+
+/// The decoded replay (a `ReplayRecord`) for one stage.
+pub async fn get_replay(
+    pool: &PgPool,
+    user_id: Uuid,
+    stage_id: &str,
+) -> Result<Option<serde_json::Value>, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT replay FROM user_battle_replays WHERE user_id = $1 AND stage_id = $2",
+    )
+    .bind(user_id)
+    .bind(stage_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Squads (`ReplayRecord.squad`) of every cleared replay of `stage_id` on a
+/// server, from users sharing their stats.
+pub async fn shared_clear_squads(
+    pool: &PgPool,
+    server_id: i16,
+    stage_id: &str,
+) -> Result<Vec<serde_json::Value>, sqlx::Error> {
+    sqlx::query_scalar(
+        r"
+        SELECT r.replay->'squad'
+        FROM user_battle_replays r
+        JOIN users u         ON u.id = r.user_id
+        JOIN user_settings s ON s.user_id = r.user_id
+        WHERE r.stage_id = $2 AND r.cleared AND u.server_id = $1 AND s.share_stats = true
+        ",
+    )
+    .bind(server_id)
+    .bind(stage_id)
+    .fetch_all(pool)
+    .await
+}
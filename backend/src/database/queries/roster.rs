@@ -1,8 +1,10 @@
+use chrono::{DateTime, Utc};
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::database::models::roster::RosterEntry;
-use crate::database::models::roster::SupportUnit;
+use crate::database::models::roster::{
+    MonthlyProgress, NewRosterEvent, RosterEntry, RosterEvent, RosterSnapshot, SupportUnit,
+};
 
 /// Get full roster for a user
 pub async fn get_roster(pool: &PgPool, user_id: Uuid) -> Result<Vec<RosterEntry>, sqlx::Error> {
@@ -117,3 +119,147 @@ pub async fn get_supports(pool: &PgPool, user_id: Uuid) -> Result<Vec<SupportUni
     .fetch_all(pool)
     .await
 }
+
+/// Store one sync's roster changes and totals together.
+pub async fn insert_roster_history(
+    pool: &PgPool,
+    user_id: Uuid,
+    events: &[NewRosterEvent],
+    snapshot: &RosterSnapshot,
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    if !events.is_empty() {
+        let operator_ids: Vec<&str> = events.iter().map(|e| e.operator_id.as_str()).collect();
+        let kinds: Vec<&str> = events.iter().map(|e| e.kind).collect();
+        let targets: Vec<Option<&str>> = events.iter().map(|e| e.target.as_deref()).collect();
+        let from_values: Vec<Option<i16>> = events.iter().map(|e| e.from_value).collect();
+        let to_values: Vec<i16> = events.iter().map(|e| e.to_value).collect();
+        sqlx::query(
+            r"
+            INSERT INTO user_roster_events
+                (user_id, operator_id, kind, target, from_value, to_value, synced_at)
+            SELECT $1, t.*, $7
+            FROM UNNEST($2::VARCHAR[], $3::VARCHAR[], $4::VARCHAR[], $5::SMALLINT[], $6::SMALLINT[]) AS t
+            ",
+        )
+        .bind(user_id)
+        .bind(&operator_ids)
+        .bind(&kinds)
+        .bind(&targets)
+        .bind(&from_values)
+        .bind(&to_values)
+        .bind(snapshot.synced_at)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    sqlx::query(
+        r"
+        INSERT INTO user_roster_snapshots
+            (user_id, synced_at, operators, elite2, masteries, mastery3, modules)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (user_id, synced_at) DO NOTHING
+        ",
+    )
+    .bind(user_id)
+    .bind(snapshot.synced_at)
+    .bind(snapshot.operators)
+    .bind(snapshot.elite2)
+    .bind(snapshot.masteries)
+    .bind(snapshot.mastery3)
+    .bind(snapshot.modules)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await
+}
+
+/// Roster events newest first, optionally filtered, keyset-paginated by id.
+#[allow(clippy::too_many_arguments)]
+pub async fn list_roster_events(
+    pool: &PgPool,
+    user_id: Uuid,
+    operator_id: Option<&str>,
+    kinds: &[String],
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    before_id: Option<i64>,
+    limit: i64,
+) -> Result<Vec<RosterEvent>, sqlx::Error> {
+    sqlx::query_as::<_, RosterEvent>(
+        r"
+        SELECT id, operator_id, kind, target, from_value, to_value, synced_at
+        FROM user_roster_events
+        WHERE user_id = $1
+          AND ($2::VARCHAR IS NULL OR operator_id = $2)
+          AND (CARDINALITY($3::VARCHAR[]) = 0 OR kind = ANY($3))
+          AND ($4::TIMESTAMPTZ IS NULL OR synced_at >= $4)
+          AND ($5::TIMESTAMPTZ IS NULL OR synced_at < $5)
+          AND ($6::BIGINT IS NULL OR id < $6)
+        ORDER BY id DESC
+        LIMIT $7
+        ",
+    )
+    .bind(user_id)
+    .bind(operator_id)
+    .bind(kinds)
+    .bind(from)
+    .bind(to)
+    .bind(before_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Per-sync roster totals since `since`, oldest first.
+pub async fn list_roster_snapshots(
+    pool: &PgPool,
+    user_id: Uuid,
+    since: DateTime<Utc>,
+) -> Result<Vec<RosterSnapshot>, sqlx::Error> {
+    sqlx::query_as::<_, RosterSnapshot>(
+        r"
+        SELECT synced_at, operators, elite2, masteries, mastery3, modules
+        FROM user_roster_snapshots
+        WHERE user_id = $1 AND synced_at >= $2
+        ORDER BY synced_at
+        ",
+    )
+    .bind(user_id)
+    .bind(since)
+    .fetch_all(pool)
+    .await
+}
+
+/// Roster events since `since` summed per UTC calendar month, oldest first.
+pub async fn monthly_roster_progress(
+    pool: &PgPool,
+    user_id: Uuid,
+    since: DateTime<Utc>,
+) -> Result<Vec<MonthlyProgress>, sqlx::Error> {
+    sqlx::query_as::<_, MonthlyProgress>(
+        r"
+        SELECT
+            to_char(date_trunc('month', synced_at AT TIME ZONE 'UTC'), 'YYYY-MM') AS month,
+            COUNT(*) FILTER (WHERE kind = 'obtained') AS obtained,
+            COUNT(*) FILTER (WHERE kind = 'elite') AS promotions,
+            COUNT(*) FILTER (WHERE kind = 'elite' AND to_value = 2) AS elite2,
+            COALESCE(SUM(to_value - from_value) FILTER (WHERE kind = 'level'), 0)::BIGINT AS levels,
+            COALESCE(SUM(to_value - from_value) FILTER (WHERE kind = 'skill_level'), 0)::BIGINT AS skill_levels,
+            COALESCE(SUM(to_value - COALESCE(from_value, 0)) FILTER (WHERE kind = 'mastery'), 0)::BIGINT AS mastery_levels,
+            COUNT(*) FILTER (WHERE kind = 'mastery' AND to_value = 3) AS mastery3,
+            COALESCE(SUM(to_value - COALESCE(from_value, 0)) FILTER (WHERE kind = 'module'), 0)::BIGINT AS module_levels,
+            COALESCE(SUM(to_value - from_value) FILTER (WHERE kind = 'potential'), 0)::BIGINT AS potentials,
+            COUNT(DISTINCT operator_id) AS operators_invested
+        FROM user_roster_events
+        WHERE user_id = $1 AND synced_at >= $2
+        GROUP BY 1
+        ORDER BY 1
+        ",
+    )
+    .bind(user_id)
+    .bind(since)
+    .fetch_all(pool)
+    .await
+}
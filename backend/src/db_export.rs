@@ -22,6 +22,9 @@ pub const TABLES: &[&str] = &[
     "user_checkin",
     "user_scores",
     "user_support_units",
+    "user_roster_events",
+    "user_roster_snapshots",
+    "user_battle_replays",
     "gacha_records",
     "tier_list_flairs",
     "tier_lists",
@@ -36,6 +39,7 @@ pub const TABLES: &[&str] = &[
     "operator_notes_audit_log",
     "leaderboard_snapshots",
     "leaderboard_snapshot_entries",
+    "stage_drop_rates",
     "audit_log",
 ];
 
@@ -43,6 +47,7 @@ pub const TABLES: &[&str] = &[
 /// future inserts don't collide with restored ids.
 pub const SERIAL_COLUMNS: &[(&str, &str)] = &[
     ("gacha_records", "id"),
+    ("user_roster_events", "id"),
     ("tier_list_flairs", "id"),
     ("tier_list_view_events", "id"),
     ("operator_notes_audit_log", "id"),
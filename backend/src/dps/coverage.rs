@@ -0,0 +1,312 @@
+//! Formula coverage and golden-value snapshots for the DPS engine.
+//!
+//! Walks every released operator in `GameData`, records which ones (and which
+//! of their skills / modules) have no formula, and evaluates every formula
+//! skill x module x potential combination against a fixed set of enemy
+//! profiles. The values form a snapshot that `tests/dps_snapshot_test.rs`
+//! diffs against, so a formula or game-data change that moves numbers shows
+//! up as a test failure rather than a user report.
+
+use std::collections::BTreeMap;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::engine::{calculate_dps, get_formula, get_heal_formula};
+use super::operator_unit::{EnemyStats, OperatorParams};
+use crate::core::gamedata::types::GameData;
+use crate::core::gamedata::types::module::ModuleType;
+use crate::core::gamedata::types::operator::{Operator, OperatorProfession};
+
+/// Enemy (DEF, RES) profiles every combination is evaluated against.
+pub const SNAPSHOT_ENEMIES: [(f64, f64); 2] = [(0.0, 0.0), (1000.0, 50.0)];
+
+/// Relative drift allowed before a snapshot value counts as changed.
+pub const SNAPSHOT_TOLERANCE: f64 = 1e-3;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotValue {
+    pub skill_dps: f64,
+    pub average_dps: f64,
+}
+
+/// Combination key -> value. A `BTreeMap` so the file is stably ordered.
+pub type Snapshot = BTreeMap<String, SnapshotValue>;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingOperator {
+    pub id: String,
+    pub name: String,
+    pub rarity: i16,
+    pub profession: OperatorProfession,
+    /// Medics and some supporters are covered by the HPS engine instead.
+    pub has_heal_formula: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingSlot {
+    pub operator_id: String,
+    pub name: String,
+    /// 1-based skill slots the formula doesn't declare.
+    pub skills: Vec<i32>,
+    /// Advanced module ids beyond the formula's declared modules.
+    pub modules: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverageReport {
+    /// Released, playable operators considered.
+    pub operators_total: usize,
+    pub operators_covered: usize,
+    pub combinations: usize,
+    pub missing_operators: Vec<MissingOperator>,
+    pub missing_slots: Vec<MissingSlot>,
+    /// Combination keys the engine returned `None` for.
+    pub failures: Vec<String>,
+}
+
+/// Snapshot key for one combination.
+pub fn combination_key(
+    operator_id: &str,
+    skill: i32,
+    module: i32,
+    potential: i32,
+    (defense, res): (f64, f64),
+) -> String {
+    format!("{operator_id}_s{skill}_m{module}_p{potential}_{defense:.0}_{res:.0}")
+}
+
+fn is_released(operator: &Operator) -> bool {
+    operator
+        .id
+        .as_deref()
+        .is_some_and(|id| id.starts_with("char_"))
+        && !operator.is_not_obtainable
+        && !matches!(
+            operator.profession,
+            OperatorProfession::Token | OperatorProfession::Trap
+        )
+}
+
+/// Round to 3 decimals so the snapshot file doesn't churn on float noise.
+fn round(v: f64) -> f64 {
+    (v * 1000.0).round() / 1000.0
+}
+
+/// Per-operator output, merged into the report afterwards.
+#[derive(Default)]
+struct OperatorCoverage {
+    missing: Option<MissingOperator>,
+    slots: Option<MissingSlot>,
+    combinations: usize,
+    failures: Vec<String>,
+    values: Vec<(String, SnapshotValue)>,
+}
+
+fn cover_operator(id: &str, operator: &Operator) -> OperatorCoverage {
+    let Some(formula) = get_formula(id) else {
+        return OperatorCoverage {
+            missing: Some(MissingOperator {
+                id: id.to_owned(),
+                name: operator.name.clone(),
+                rarity: operator.rarity.to_star_int(),
+                profession: operator.profession.clone(),
+                has_heal_formula: get_heal_formula(id).is_some(),
+            }),
+            ..Default::default()
+        };
+    };
+
+    let mut out = OperatorCoverage::default();
+
+    let missing_skills: Vec<i32> = (1..=operator.skills.len() as i32)
+        .filter(|s| !formula.available_skills.contains(s))
+        .collect();
+    // Formula modules index the number-sorted advanced modules, so anything
+    // past the formula's count has no formula slot.
+    let mut advanced: Vec<&str> = operator
+        .modules
+        .iter()
+        .filter(|m| m.module.module_type == ModuleType::Advanced)
+        .map(|m| m.module.uni_equip_id.as_str())
+        .collect();
+    advanced.sort_by_key(|id| {
+        id.split('_')
+            .nth(1)
+            .and_then(|n| n.parse::<i32>().ok())
+            .unwrap_or(i32::MAX)
+    });
+    let missing_modules: Vec<String> = advanced
+        .into_iter()
+        .skip(formula.available_modules.len())
+        .map(str::to_owned)
+        .collect();
+    if !missing_skills.is_empty() || !missing_modules.is_empty() {
+        out.slots = Some(MissingSlot {
+            operator_id: id.to_owned(),
+            name: operator.name.clone(),
+            skills: missing_skills,
+            modules: missing_modules,
+        });
+    }
+
+    let modules = std::iter::once(0).chain(formula.available_modules.iter().copied());
+    for module in modules {
+        for &skill in &formula.available_skills {
+            for potential in 1..=6 {
+                let params = OperatorParams {
+                    skill_index: Some(skill),
+                    module_index: Some(module),
+                    potential: Some(potential),
+                    ..Default::default()
+                };
+                for enemy in SNAPSHOT_ENEMIES {
+                    out.combinations += 1;
+                    let key = combination_key(id, skill, module, potential, enemy);
+                    let stats = EnemyStats {
+                        defense: enemy.0,
+                        res: enemy.1,
+                    };
+                    match calculate_dps(operator, params.clone(), &stats) {
+                        Some(r) => out.values.push((
+                            key,
+                            SnapshotValue {
+                                skill_dps: round(r.skill_dps),
+                                average_dps: round(r.average_dps),
+                            },
+                        )),
+                        None => out.failures.push(key),
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Walk every released operator and build the coverage report plus the
+/// golden-value snapshot.
+pub fn build(gd: &GameData) -> (CoverageReport, Snapshot) {
+    let per_operator: Vec<OperatorCoverage> = gd
+        .operators
+        .par_iter()
+        .filter(|(_, op)| is_released(op))
+        .map(|(id, op)| cover_operator(id, op))
+        .collect();
+
+    let mut report = CoverageReport {
+        operators_total: per_operator.len(),
+        operators_covered: 0,
+        combinations: 0,
+        missing_operators: Vec::new(),
+        missing_slots: Vec::new(),
+        failures: Vec::new(),
+    };
+    let mut snapshot = Snapshot::new();
+    for op in per_operator {
+        match op.missing {
+            Some(missing) => report.missing_operators.push(missing),
+            None => report.operators_covered += 1,
+        }
+        report.missing_slots.extend(op.slots);
+        report.combinations += op.combinations;
+        report.failures.extend(op.failures);
+        snapshot.extend(op.values);
+    }
+    report
+        .missing_operators
+        .sort_by(|a, b| b.rarity.cmp(&a.rarity).then_with(|| a.id.cmp(&b.id)));
+    report
+        .missing_slots
+        .sort_by(|a, b| a.operator_id.cmp(&b.operator_id));
+    report.failures.sort();
+
+    (report, snapshot)
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotDiff {
+    /// `(key, old, new)` for values that moved beyond tolerance.
+    pub changed: Vec<(String, SnapshotValue, SnapshotValue)>,
+    /// Keys only in the new snapshot.
+    pub added: Vec<String>,
+    /// Keys only in the old snapshot.
+    pub removed: Vec<String>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+fn drifted(old: f64, new: f64, tolerance: f64) -> bool {
+    let diff = (old - new).abs();
+    diff > tolerance * old.abs().max(1.0)
+}
+
+/// Compare two snapshots. Values within `tolerance` (relative, floored at an
+/// absolute `tolerance` near zero) count as unchanged.
+pub fn diff(old: &Snapshot, new: &Snapshot, tolerance: f64) -> SnapshotDiff {
+    let mut out = SnapshotDiff::default();
+    for (key, old_value) in old {
+        match new.get(key) {
+            None => out.removed.push(key.clone()),
+            Some(new_value) => {
+                if drifted(old_value.skill_dps, new_value.skill_dps, tolerance)
+                    || drifted(old_value.average_dps, new_value.average_dps, tolerance)
+                {
+                    out.changed
+                        .push((key.clone(), old_value.clone(), new_value.clone()));
+                }
+            }
+        }
+    }
+    out.added = new
+        .keys()
+        .filter(|k| !old.contains_key(*k))
+        .cloned()
+        .collect();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(skill_dps: f64) -> SnapshotValue {
+        SnapshotValue {
+            skill_dps,
+            average_dps: skill_dps / 2.0,
+        }
+    }
+
+    #[test]
+    fn diff_ignores_drift_within_tolerance() {
+        let old = Snapshot::from([("a".to_owned(), value(1000.0))]);
+        let new = Snapshot::from([("a".to_owned(), value(1000.5))]);
+        assert!(diff(&old, &new, SNAPSHOT_TOLERANCE).is_empty());
+    }
+
+    #[test]
+    fn diff_reports_changed_added_and_removed() {
+        let old = Snapshot::from([
+            ("kept".to_owned(), value(1000.0)),
+            ("gone".to_owned(), value(10.0)),
+        ]);
+        let new = Snapshot::from([
+            ("kept".to_owned(), value(1100.0)),
+            ("new".to_owned(), value(10.0)),
+        ]);
+        let d = diff(&old, &new, SNAPSHOT_TOLERANCE);
+        assert_eq!(d.changed.len(), 1);
+        assert_eq!(d.changed[0].0, "kept");
+        assert_eq!(d.added, vec!["new"]);
+        assert_eq!(d.removed, vec!["gone"]);
+    }
+}
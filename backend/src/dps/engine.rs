@@ -1,3 +1,4 @@
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::LazyLock;
@@ -9,7 +10,10 @@ use crate::dps::operator_unit::OperatorParams;
 use super::custom::dispatch;
 use super::custom::dispatch_hps;
 use super::formulas::apply_shreds;
+use super::healing::{self, HealCycle, HealScenario, HealScenarioResult};
 use super::operator_unit::{EnemyStats, OperatorUnit};
+use super::squad::{SquadEffect, provided_effects};
+use super::timeline::{SpRecovery, TimelineInputs, TimelineResult, simulate};
 
 const FORMULAS_JSON: &str = include_str!("config/operator_formulas.json");
 const HEAL_FORMULAS_JSON: &str = include_str!("config/heal_formulas.json");
@@ -133,11 +137,12 @@ pub fn supported_healers() -> &'static HashMap<String, OperatorFormula> {
     &HEAL_FORMULAS
 }
 
-pub fn calculate_dps(
+/// Resolve the DPS formula and build the unit for `operator` at `params`.
+/// `None` if the operator has no formula or a requested module can't resolve.
+fn build_dps_unit(
     operator: &Operator,
     params: OperatorParams,
-    enemy: &EnemyStats,
-) -> Option<DpsResult> {
+) -> Option<(OperatorUnit, &'static OperatorFormula)> {
     let op_id = operator.id.as_deref()?;
     let formula = FORMULAS.get(op_id)?;
 
@@ -159,52 +164,162 @@ pub fn calculate_dps(
         return None;
     }
 
-    let shredded = apply_shreds(enemy, &unit.shreds);
+    Some((unit, formula))
+}
+
+/// A built unit ready to be evaluated against any number of enemies.
+struct PreparedUnit {
+    unit: OperatorUnit,
+    skill_formula: &'static SkillFormula,
+    /// External fragile, lifted off the unit: operators should not see it
+    /// (matches Python behavior where buff_fragile=0 during skill_dps), so it's
+    /// applied to the result instead, always last.
+    fragile: f64,
+}
+
+fn prepare_unit(operator: &Operator, params: OperatorParams) -> Option<PreparedUnit> {
+    let (mut unit, formula) = build_dps_unit(operator, params)?;
 
     // Get the skill formula for current skill index
     let skill_key = unit.skill_index.to_string();
     let skill_formula = formula.skills.get(&skill_key)?;
 
-    // Calculate skill DPS
-    // Save and zero out buff_fragile before calling skill_dps - operators should not
-    // see external fragile (matches Python behavior where buff_fragile=0 during skill_dps).
-    // Fragile is applied externally after the call.
-    let external_fragile = unit.buff_fragile;
-    let mut unit = unit;
+    let fragile = unit.buff_fragile;
     unit.buff_fragile = 0.0;
-    let skill_dps = calculate_skill_dps(&unit, skill_formula, &shredded);
 
-    // Apply fragile externally (always last)
-    let skill_dps = skill_dps * (1.0 + external_fragile);
+    Some(PreparedUnit {
+        unit,
+        skill_formula,
+        fragile,
+    })
+}
 
-    // Total damage
-    let total_damage = if unit.skill_duration > 0.0 {
-        skill_dps * unit.skill_duration
-    } else {
-        skill_dps
-    };
+impl PreparedUnit {
+    /// On-skill and off-skill (basic attack) DPS against `enemy`, shreds applied.
+    fn skill_and_off_skill_dps(&self, enemy: &EnemyStats) -> (f64, f64) {
+        let shredded = apply_shreds(enemy, &self.unit.shreds);
+        let skill_dps =
+            calculate_skill_dps(&self.unit, self.skill_formula, &shredded) * (1.0 + self.fragile);
 
-    // Average DPS (cycle including downtime)
-    let average_dps = if unit.skill_duration > 0.0 && unit.skill_cost > 0 {
-        // Calculate off-skill DPS (basic attack)
+        // Off-skill DPS (basic attack). `buff_fragile` is zeroed on the unit, so
+        // this matches the reference's cycle average, which leaves fragile off here.
         let off_skill_dps =
-            unit.normal_attack(&shredded, None, None, None) * (1.0 + unit.buff_fragile);
-        let sp_time = f64::from(unit.skill_cost) / (1.0 + f64::from(unit.sp_boost));
-        let cycle_dmg = skill_dps * unit.skill_duration + off_skill_dps * sp_time;
-        cycle_dmg / (unit.skill_duration + sp_time)
-    } else {
-        skill_dps
-    };
+            self.unit.normal_attack(&shredded, None, None, None) * (1.0 + self.unit.buff_fragile);
+
+        (skill_dps, off_skill_dps)
+    }
 
-    Some(DpsResult {
+    fn dps(&self, enemy: &EnemyStats) -> DpsResult {
+        let unit = &self.unit;
+        let (skill_dps, off_skill_dps) = self.skill_and_off_skill_dps(enemy);
+
+        // Total damage
+        let total_damage = if unit.skill_duration > 0.0 {
+            skill_dps * unit.skill_duration
+        } else {
+            skill_dps
+        };
+
+        // Average DPS (cycle including downtime)
+        let average_dps = if unit.skill_duration > 0.0 && unit.skill_cost > 0 {
+            let sp_time = f64::from(unit.skill_cost) / (1.0 + f64::from(unit.sp_boost));
+            let cycle_dmg = skill_dps * unit.skill_duration + off_skill_dps * sp_time;
+            cycle_dmg / (unit.skill_duration + sp_time)
+        } else {
+            skill_dps
+        };
+
+        DpsResult {
+            skill_dps,
+            total_damage,
+            average_dps,
+        }
+    }
+}
+
+pub fn calculate_dps(
+    operator: &Operator,
+    params: OperatorParams,
+    enemy: &EnemyStats,
+) -> Option<DpsResult> {
+    Some(prepare_unit(operator, params)?.dps(enemy))
+}
+
+/// DPS at each of `points`, building the unit once and evaluating the points
+/// in parallel. Results are in the same order as `points`.
+pub fn calculate_dps_curve(
+    operator: &Operator,
+    params: OperatorParams,
+    points: &[EnemyStats],
+) -> Option<Vec<DpsResult>> {
+    let prepared = prepare_unit(operator, params)?;
+    Some(points.par_iter().map(|p| prepared.dps(p)).collect())
+}
+
+/// Seconds for `dps` to bring down `hp` against `regen` HP/s, or `None` if it
+/// never does.
+pub fn time_to_kill(hp: f64, dps: f64, regen: f64) -> Option<f64> {
+    let net = dps - regen;
+    (net > 0.0).then(|| hp / net)
+}
+
+/// Squad effects `operator` provides to the rest of a squad at `params`.
+/// Works for operators without a DPS formula too, since most supports have none.
+pub fn squad_effects(operator: &Operator, params: OperatorParams) -> Vec<SquadEffect> {
+    let (default_skill, default_potential, available_skills) =
+        match operator.id.as_deref().and_then(|id| FORMULAS.get(id)) {
+            Some(f) => (
+                f.default_skill,
+                f.default_potential,
+                f.available_skills.clone(),
+            ),
+            None => (operator.skills.len() as i32, 1, Vec::new()),
+        };
+    let unit = OperatorUnit::new(
+        OperatorData::new(operator.clone()),
+        params,
+        default_skill,
+        default_potential,
+        0,
+        available_skills,
+        Vec::new(),
+    );
+    provided_effects(&unit)
+}
+
+/// Step the operator's skill cycle across `duration` seconds, charging SP by
+/// the skill's own `SpType` and starting from its initial SP.
+/// `hits_taken_per_second` only matters for defensive-recovery skills.
+pub fn calculate_timeline(
+    operator: &Operator,
+    params: OperatorParams,
+    enemy: &EnemyStats,
+    duration: f64,
+    hits_taken_per_second: f64,
+) -> Option<TimelineResult> {
+    let prepared = prepare_unit(operator, params)?;
+    let (skill_dps, off_skill_dps) = prepared.skill_and_off_skill_dps(enemy);
+    let unit = &prepared.unit;
+
+    let sp_data = unit.skill_sp_data();
+    let inputs = TimelineInputs {
         skill_dps,
-        total_damage,
-        average_dps,
-    })
+        off_skill_dps,
+        skill_duration: unit.skill_duration,
+        skill_cost: unit.skill_cost,
+        init_sp: sp_data.map_or(0, |sp| sp.init_sp),
+        sp_recovery: sp_data.map_or(SpRecovery::Auto, |sp| SpRecovery::from_sp_type(&sp.sp_type)),
+        sp_boost: f64::from(unit.sp_boost),
+        attacks_per_second: (unit.attack_speed / 100.0) / f64::from(unit.attack_interval),
+        hits_taken_per_second: hits_taken_per_second.max(0.0),
+    };
+
+    Some(simulate(&inputs, duration))
 }
 
-/// Returns `None` for operators without a transpiled HPS implementation.
-pub fn calculate_hps(operator: &Operator, params: OperatorParams) -> Option<HpsResult> {
+/// Build the healer's unit at `params`. `None` for operators without a
+/// transpiled HPS implementation or with an unresolvable module.
+fn build_hps_unit(operator: &Operator, params: OperatorParams) -> Option<OperatorUnit> {
     let op_id = operator.id.as_deref()?;
     let formula = HEAL_FORMULAS.get(op_id)?;
 
@@ -224,8 +339,37 @@ pub fn calculate_hps(operator: &Operator, params: OperatorParams) -> Option<HpsR
     }
 
     apply_init_fixups(&mut unit, op_id);
+    Some(unit)
+}
+
+/// Returns `None` for operators without a transpiled HPS implementation.
+pub fn calculate_hps(operator: &Operator, params: OperatorParams) -> Option<HpsResult> {
+    dispatch_hps(&build_hps_unit(operator, params)?)
+}
 
-    dispatch_hps(&unit)
+/// Play the healer against `scenario`: `scenario.injured_allies` becomes the
+/// heal target count, and `target_max_hp` feeds %-max-HP heals.
+pub fn calculate_heal_scenario(
+    operator: &Operator,
+    mut params: OperatorParams,
+    scenario: &HealScenario,
+) -> Option<(HpsResult, HealScenarioResult)> {
+    params.targets = Some(scenario.injured_allies.max(1));
+    let mut unit = build_hps_unit(operator, params)?;
+    if scenario.target_max_hp > 0.0 {
+        unit.target_hp = scenario.target_max_hp;
+    }
+    let hps = dispatch_hps(&unit)?;
+
+    let cycle = HealCycle {
+        skill_hps: hps.skill_hps,
+        base_hps: hps.base_hps,
+        avg_hps: hps.avg_hps,
+        skill_duration: unit.skill_duration,
+        charge_time: f64::from(unit.skill_cost) / (1.0 + f64::from(unit.sp_boost)),
+    };
+    let result = healing::evaluate(&cycle, scenario);
+    Some((hps, result))
 }
 
 /// Per-operator init side effects that the transpiler can't derive from the
@@ -0,0 +1,182 @@
+//! Healing against an incoming-damage scenario.
+//!
+//! Raw HPS says how much a healer can put out, not how much of it lands: heals
+//! past a full HP bar are wasted, and a healer whose average keeps up can still
+//! lose an ally during the SP-charge gap. This module takes the engine's three
+//! HPS figures plus the skill cycle and plays them against N injured allies
+//! each taking a steady stream of damage.
+//!
+//! Healing is assumed to spread evenly across the injured allies.
+
+use serde::{Deserialize, Serialize};
+
+/// How long to play the scenario for before calling it sustainable.
+const HORIZON: f64 = 600.0;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealScenario {
+    /// Allies taking damage at the same time.
+    pub injured_allies: i32,
+    /// Damage per second each injured ally takes (after their DEF/RES).
+    pub incoming_dps: f64,
+    /// Max HP of each injured ally.
+    pub target_max_hp: f64,
+}
+
+/// The healer's output and skill cycle, as the engine resolves them.
+#[derive(Debug, Clone, Copy)]
+pub struct HealCycle {
+    pub skill_hps: f64,
+    pub base_hps: f64,
+    pub avg_hps: f64,
+    /// `<= 0` for skills without an active window.
+    pub skill_duration: f64,
+    /// Seconds to charge the skill.
+    pub charge_time: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealScenarioResult {
+    /// Total damage per second across all injured allies.
+    pub incoming_dps_total: f64,
+    /// Healing that actually restores HP, cycle-averaged.
+    pub effective_hps: f64,
+    /// Healing wasted on full HP, cycle-averaged.
+    pub overheal_hps: f64,
+    /// `overhealHps / avgHps`.
+    pub overheal_ratio: f64,
+    pub skill_effective_hps: f64,
+    pub base_effective_hps: f64,
+    /// Whether no ally goes down within the simulated window.
+    pub sustainable: bool,
+    /// Highest per-ally incoming DPS the cycle-averaged healing keeps up with.
+    pub max_sustained_dps_per_ally: f64,
+    /// Lowest HP any ally reaches, as a fraction of max HP (0 if one falls).
+    pub lowest_hp_fraction: f64,
+    /// Seconds until the first ally falls, if one does.
+    pub time_to_first_down: Option<f64>,
+}
+
+/// Play `cycle` against `scenario`.
+pub fn evaluate(cycle: &HealCycle, scenario: &HealScenario) -> HealScenarioResult {
+    let allies = f64::from(scenario.injured_allies.max(1));
+    let incoming = scenario.incoming_dps.max(0.0) * allies;
+    let effective = |hps: f64| hps.min(incoming);
+
+    let effective_hps = effective(cycle.avg_hps);
+    let overheal_hps = (cycle.avg_hps - effective_hps).max(0.0);
+
+    let (lowest_hp, time_to_first_down) = play(cycle, scenario, allies);
+    let max_hp = scenario.target_max_hp;
+
+    HealScenarioResult {
+        incoming_dps_total: incoming,
+        effective_hps,
+        overheal_hps,
+        overheal_ratio: if cycle.avg_hps > 0.0 {
+            overheal_hps / cycle.avg_hps
+        } else {
+            0.0
+        },
+        skill_effective_hps: effective(cycle.skill_hps),
+        base_effective_hps: effective(cycle.base_hps),
+        sustainable: time_to_first_down.is_none(),
+        max_sustained_dps_per_ally: cycle.avg_hps / allies,
+        lowest_hp_fraction: if max_hp > 0.0 {
+            (lowest_hp / max_hp).clamp(0.0, 1.0)
+        } else {
+            0.0
+        },
+        time_to_first_down,
+    }
+}
+
+/// Step through charge / skill phases from full HP, starting with the charge.
+/// Returns the lowest per-ally HP and when it first hits 0.
+fn play(cycle: &HealCycle, scenario: &HealScenario, allies: f64) -> (f64, Option<f64>) {
+    let max_hp = scenario.target_max_hp.max(0.0);
+    let damage = scenario.incoming_dps.max(0.0);
+
+    let phases: Vec<(f64, f64)> = if cycle.skill_duration > 0.0 && cycle.charge_time > 0.0 {
+        vec![
+            (cycle.charge_time, cycle.base_hps),
+            (cycle.skill_duration, cycle.skill_hps),
+        ]
+    } else {
+        vec![(HORIZON, cycle.avg_hps)]
+    };
+
+    let mut hp = max_hp;
+    let mut lowest = max_hp;
+    let mut t = 0.0;
+    'outer: while t < HORIZON {
+        for &(length, hps) in &phases {
+            let length = length.min(HORIZON - t);
+            // Net HP lost per second by each ally in this phase.
+            let loss = damage - hps / allies;
+            if loss > 0.0 && hp - loss * length <= 0.0 {
+                return (0.0, Some(t + hp / loss));
+            }
+            hp = (hp - loss * length).min(max_hp);
+            lowest = lowest.min(hp);
+            t += length;
+            if t >= HORIZON {
+                break 'outer;
+            }
+        }
+    }
+    (lowest, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cycle() -> HealCycle {
+        HealCycle {
+            skill_hps: 1000.0,
+            base_hps: 200.0,
+            avg_hps: 500.0,
+            skill_duration: 10.0,
+            charge_time: 20.0,
+        }
+    }
+
+    fn scenario(injured_allies: i32, incoming_dps: f64) -> HealScenario {
+        HealScenario {
+            injured_allies,
+            incoming_dps,
+            target_max_hp: 3000.0,
+        }
+    }
+
+    #[test]
+    fn light_pressure_is_mostly_overheal() {
+        let r = evaluate(&cycle(), &scenario(1, 100.0));
+        assert!(r.sustainable);
+        assert!((r.effective_hps - 100.0).abs() < 1e-9);
+        assert!((r.overheal_ratio - 0.8).abs() < 1e-9);
+        assert!((r.lowest_hp_fraction - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn charge_gap_can_drop_an_ally_despite_sustaining_on_average() {
+        // 2 allies x 245 DPS = 490 <= 500 avg, but each charge loses 2900 HP
+        // per ally and each skill only restores 2550, so the second charge
+        // (starting at 2650 HP, t = 30s) runs out after ~18.3s.
+        let r = evaluate(&cycle(), &scenario(2, 245.0));
+        assert!(!r.sustainable);
+        let down = r.time_to_first_down.unwrap();
+        assert!((down - (30.0 + 2650.0 / 145.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn moderate_pressure_dips_but_recovers() {
+        // Charge loses 50 HP/s for 20s (1000 HP), skill heals 400 HP/s back.
+        let r = evaluate(&cycle(), &scenario(1, 250.0));
+        assert!(r.sustainable);
+        assert!((r.lowest_hp_fraction - 2.0 / 3.0).abs() < 1e-9);
+    }
+}
@@ -1,6 +1,10 @@
 pub mod config;
+pub mod coverage;
 pub mod custom;
 pub mod engine;
 pub mod formulas;
+pub mod healing;
 pub mod operator_data;
 pub mod operator_unit;
+pub mod squad;
+pub mod timeline;
@@ -30,6 +30,21 @@ pub fn uniequip_number(module: &OperatorModule) -> i32 {
         .unwrap_or(i32::MAX)
 }
 
+/// Whether an operator's normal attacks deal physical damage, by class and
+/// branch.
+pub fn is_physical(operator: &Operator) -> bool {
+    if operator.profession == OperatorProfession::Caster
+        || operator.profession == OperatorProfession::Medic
+        || operator.profession == OperatorProfession::Supporter
+    {
+        false
+    } else if operator.sub_profession_id == "craftsman" {
+        true
+    } else {
+        operator.sub_profession_id != "artsfghter"
+    }
+}
+
 pub struct OperatorData {
     pub data: Operator,
 
@@ -147,16 +162,7 @@ impl OperatorData {
             .get(1)
             .map_or(0.0, |kf| kf.data.attack_speed);
 
-        let is_physical = if operator.profession == OperatorProfession::Caster
-            || operator.profession == OperatorProfession::Medic
-            || operator.profession == OperatorProfession::Supporter
-        {
-            false
-        } else if operator.sub_profession_id == "craftsman" {
-            true
-        } else {
-            operator.sub_profession_id != "artsfghter"
-        };
+        let is_physical = is_physical(&operator);
 
         let is_ranged = operator.position != OperatorPosition::Melee;
 
@@ -1,6 +1,6 @@
 use std::fmt::Write as _;
 
-use crate::core::gamedata::types::{enemy::Enemy, operator::OperatorModule};
+use crate::core::gamedata::types::{enemy::Enemy, operator::OperatorModule, skill::SkillSpData};
 
 use super::operator_data::OperatorData;
 
@@ -771,6 +771,23 @@ impl OperatorUnit {
         }
     }
 
+    /// SP data for the selected skill at the resolved skill level, or `None`
+    /// when no skill is selected (rarity <= 2 or skill 0).
+    pub fn skill_sp_data(&self) -> Option<&SkillSpData> {
+        if self.skill_index < 1 {
+            return None;
+        }
+        self.data
+            .data
+            .skills
+            .get((self.skill_index - 1) as usize)?
+            .static_data
+            .as_ref()?
+            .levels
+            .get((self.skill_level - 1) as usize)
+            .map(|level| &level.sp_data)
+    }
+
     pub fn normal_attack(
         &self,
         enemy: &EnemyStats,
@@ -861,6 +878,7 @@ pub struct ExtraBuffs {
     pub aspd: f64,
 }
 
+#[derive(Clone)]
 pub struct OperatorParams {
     pub potential: Option<i32>,
     pub promotion: Option<i32>,
@@ -936,7 +954,7 @@ impl Default for OperatorParams {
 /// 1: Flat ATK buff (eg. 102)
 /// 2: ASPD buff (eg. 52)
 /// 3: Fragile debuff as a percentage decimal (eg. 0.3)
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct OperatorBuffs {
     pub atk: Option<f32>,
     pub flat_atk: Option<i32>,
@@ -946,12 +964,13 @@ pub struct OperatorBuffs {
 
 /// 0: ATK buff as a percentage decimal (eg. 0.4)
 /// 1: Flat ATK buff (eg. 102)
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct OperatorBaseBuffs {
     pub atk: Option<f32>,
     pub flat_atk: Option<i32>,
 }
 
+#[derive(Clone)]
 pub struct OperatorConditionals {
     pub trait_damage: Option<bool>,
     pub talent_damage: Option<bool>,
@@ -976,7 +995,7 @@ impl Default for OperatorConditionals {
 /// 1: Flat DEF shred (eg. 102)
 /// 2: RES shred as a percentage decimal (eg. 0.4)
 /// 3: Flat RES shred (eg. 102)
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct OperatorShred {
     pub def: Option<i32>,
     pub def_flat: Option<i32>,
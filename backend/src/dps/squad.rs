@@ -0,0 +1,343 @@
+//! Cross-operator effects for squad DPS.
+//!
+//! Each member's resolved talents and active skill are scanned for blackboard
+//! keys that buff allies (ATK, ASPD, SP recovery) or debuff enemies (fragile,
+//! DEF/RES shred). The blackboard carries no target, so the effect's direction
+//! comes from the description sentence that renders the key: "allies" /
+//! "friendly" / "Operators" means it buffs the squad, "enemies" means it
+//! debuffs the target, and anything else is a self-buff the member's own
+//! formula already accounts for.
+//!
+//! Skill effects are scaled by the provider's skill uptime so a 30s-on / 40s-off
+//! buff contributes its cycle average rather than its peak.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::core::gamedata::types::operator::{Operator, OperatorPhase, TalentCandidate};
+use crate::core::gamedata::types::skill::SkillLevel;
+use crate::dps::operator_unit::{OperatorParams, OperatorShred, OperatorUnit};
+
+/// The largest deployable squad (12 + one support unit).
+pub const MAX_SQUAD_SIZE: usize = 13;
+
+/// `{key}`, `{-key}`, `{key:0%}`, `{attack@atk:0.0}` - description placeholders.
+static RE_PLACEHOLDER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\{-?([A-Za-z0-9_@.\[\]]+)(?::[^}]*)?\}").unwrap());
+
+static RE_ALLY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(all(y|ies)|friendly|(other |all )?operators?|teammates?)\b").unwrap()
+});
+
+static RE_ENEMY: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)\benem(y|ies)\b").unwrap());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SquadEffectKind {
+    /// ATK +x% for allies (decimal).
+    Atk,
+    /// ASPD +x for allies (flat).
+    Aspd,
+    /// SP/s for allies.
+    SpRecovery,
+    /// Enemy takes +x% damage (decimal).
+    Fragile,
+    /// Enemy DEF -x% (percent).
+    DefShred,
+    /// Enemy DEF -x (flat).
+    DefShredFlat,
+    /// Enemy RES -x% (percent).
+    ResShred,
+    /// Enemy RES -x (flat).
+    ResShredFlat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EffectSource {
+    Talent1,
+    Talent2,
+    Skill,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SquadEffect {
+    pub source: EffectSource,
+    pub kind: SquadEffectKind,
+    pub key: String,
+    /// Peak value while the effect is up, in the units of `kind`.
+    pub value: f64,
+    /// Fraction of the time the effect is up (1 for talents).
+    pub uptime: f64,
+}
+
+/// What a member receives from the rest of the squad, uptime-weighted.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceivedEffects {
+    pub atk: f64,
+    pub aspd: f64,
+    pub sp_recovery: f64,
+    pub fragile: f64,
+    pub def_shred: f64,
+    pub def_shred_flat: f64,
+    pub res_shred: f64,
+    pub res_shred_flat: f64,
+}
+
+/// The squad effects `unit` provides at its resolved elite/level/potential and
+/// selected skill.
+pub fn provided_effects(unit: &OperatorUnit) -> Vec<SquadEffect> {
+    let operator = &unit.data.data;
+    let mut effects = Vec::new();
+
+    for (slot, source) in [(0, EffectSource::Talent1), (1, EffectSource::Talent2)] {
+        let Some(candidate) = operator
+            .talents
+            .get(slot)
+            .and_then(|t| unlocked_candidate(&t.candidates, unit))
+        else {
+            continue;
+        };
+        let description = candidate.description.as_deref().unwrap_or("");
+        for bb in &candidate.blackboard {
+            if let Some((kind, value)) = classify(&bb.key, bb.value, description) {
+                effects.push(SquadEffect {
+                    source,
+                    kind,
+                    key: bb.key.clone(),
+                    value,
+                    uptime: 1.0,
+                });
+            }
+        }
+    }
+
+    if let Some(level) = skill_level_data(operator, unit) {
+        let uptime = skill_uptime(unit);
+        for bb in &level.blackboard {
+            if let Some((kind, value)) = classify(&bb.key, bb.value, &level.description) {
+                effects.push(SquadEffect {
+                    source: EffectSource::Skill,
+                    kind,
+                    key: bb.key.clone(),
+                    value,
+                    uptime,
+                });
+            }
+        }
+    }
+
+    effects
+}
+
+/// Fold the effects of every other member into what member `index` receives.
+/// ATK/ASPD/SP and flat shreds add; percentage shreds multiply; fragile takes
+/// the strongest source, since fragile from different operators doesn't stack.
+pub fn received_effects(provided: &[Vec<SquadEffect>], index: usize) -> ReceivedEffects {
+    let mut received = ReceivedEffects::default();
+    let mut def_keep = 1.0;
+    let mut res_keep = 1.0;
+
+    for (i, effects) in provided.iter().enumerate() {
+        if i == index {
+            continue;
+        }
+        for effect in effects {
+            let value = effect.value * effect.uptime;
+            match effect.kind {
+                SquadEffectKind::Atk => received.atk += value,
+                SquadEffectKind::Aspd => received.aspd += value,
+                SquadEffectKind::SpRecovery => received.sp_recovery += value,
+                SquadEffectKind::Fragile => received.fragile = received.fragile.max(value),
+                SquadEffectKind::DefShred => def_keep *= 1.0 - value / 100.0,
+                SquadEffectKind::DefShredFlat => received.def_shred_flat += value,
+                SquadEffectKind::ResShred => res_keep *= 1.0 - value / 100.0,
+                SquadEffectKind::ResShredFlat => received.res_shred_flat += value,
+            }
+        }
+    }
+
+    received.def_shred = (1.0 - def_keep) * 100.0;
+    received.res_shred = (1.0 - res_keep) * 100.0;
+    received
+}
+
+/// Layer received effects on top of the member's own hand-entered buffs.
+pub fn apply_received(params: &mut OperatorParams, received: &ReceivedEffects) {
+    let buffs = &mut params.buffs;
+    buffs.atk = Some(buffs.atk.unwrap_or(0.0) + received.atk as f32);
+    buffs.aspd = Some(buffs.aspd.unwrap_or(0) + received.aspd.round() as i32);
+    buffs.fragile = Some(buffs.fragile.unwrap_or(0.0).max(received.fragile as f32));
+    params.sp_boost = Some(params.sp_boost.unwrap_or(0.0) + received.sp_recovery as f32);
+
+    let shred = params.shred.get_or_insert_with(OperatorShred::default);
+    let own_def_keep = 1.0 - f64::from(shred.def.unwrap_or(0)) / 100.0;
+    let own_res_keep = 1.0 - f64::from(shred.res.unwrap_or(0)) / 100.0;
+    shred.def =
+        Some(((1.0 - own_def_keep * (1.0 - received.def_shred / 100.0)) * 100.0).round() as i32);
+    shred.res =
+        Some(((1.0 - own_res_keep * (1.0 - received.res_shred / 100.0)) * 100.0).round() as i32);
+    shred.def_flat = Some(shred.def_flat.unwrap_or(0) + received.def_shred_flat.round() as i32);
+    shred.res_flat = Some(shred.res_flat.unwrap_or(0) + received.res_shred_flat.round() as i32);
+}
+
+/// Map a blackboard entry to a squad effect, or `None` for self-buffs and keys
+/// that don't feed the DPS model.
+fn classify(key: &str, value: f64, description: &str) -> Option<(SquadEffectKind, f64)> {
+    let target = target_of(key, description)?;
+    let key = key.to_ascii_lowercase();
+    match (target, key.as_str()) {
+        (Target::Ally, "atk") if value > 0.0 => Some((SquadEffectKind::Atk, value)),
+        (Target::Ally, "attack_speed") if value > 0.0 => Some((SquadEffectKind::Aspd, value)),
+        (Target::Ally, "sp_recovery_per_sec") if value > 0.0 => {
+            Some((SquadEffectKind::SpRecovery, value))
+        }
+        (Target::Enemy, "damage_scale" | "damage_scale_enemy") if value > 1.0 => {
+            Some((SquadEffectKind::Fragile, value - 1.0))
+        }
+        (Target::Enemy, "def") if value < 0.0 => Some(if value > -1.0 {
+            (SquadEffectKind::DefShred, -value * 100.0)
+        } else {
+            (SquadEffectKind::DefShredFlat, -value)
+        }),
+        (Target::Enemy, "magic_resistance") if value < 0.0 => Some(if value > -1.0 {
+            (SquadEffectKind::ResShred, -value * 100.0)
+        } else {
+            (SquadEffectKind::ResShredFlat, -value)
+        }),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Ally,
+    Enemy,
+}
+
+/// Who the sentence rendering `{key}` talks about. The nearest target word
+/// before the placeholder wins; failing that, the first one after it.
+fn target_of(key: &str, description: &str) -> Option<Target> {
+    // "this Operator" is the member itself, not the squad.
+    let description = description
+        .replace("this Operator", "self")
+        .replace("This Operator", "Self");
+    for sentence in description.split(['.', ';', '\n']) {
+        let Some(pos) = RE_PLACEHOLDER
+            .captures_iter(sentence)
+            .find(|c| c[1].eq_ignore_ascii_case(key))
+            .and_then(|c| c.get(0))
+            .map(|m| m.start())
+        else {
+            continue;
+        };
+
+        let (before, after) = sentence.split_at(pos);
+        let last = |re: &Regex, s: &str| re.find_iter(s).last().map(|m| m.end());
+        let first = |re: &Regex, s: &str| re.find(s).map(|m| m.start());
+
+        return match (last(&RE_ALLY, before), last(&RE_ENEMY, before)) {
+            (Some(a), Some(e)) => Some(if a > e { Target::Ally } else { Target::Enemy }),
+            (Some(_), None) => Some(Target::Ally),
+            (None, Some(_)) => Some(Target::Enemy),
+            (None, None) => match (first(&RE_ALLY, after), first(&RE_ENEMY, after)) {
+                (Some(a), Some(e)) => Some(if a < e { Target::Ally } else { Target::Enemy }),
+                (Some(_), None) => Some(Target::Ally),
+                (None, Some(_)) => Some(Target::Enemy),
+                (None, None) => None,
+            },
+        };
+    }
+    None
+}
+
+/// The highest talent candidate unlocked at the unit's elite/level/potential.
+/// Module-upgraded candidates aren't considered; they rarely change a support
+/// effect's target, only its value.
+fn unlocked_candidate<'a>(
+    candidates: &'a [TalentCandidate],
+    unit: &OperatorUnit,
+) -> Option<&'a TalentCandidate> {
+    candidates.iter().rev().find(|c| {
+        let phase = phase_number(&c.unlock_condition.phase);
+        (unit.elite > phase || (unit.elite == phase && unit.level >= c.unlock_condition.level))
+            && unit.potential > c.required_potential_rank
+    })
+}
+
+const fn phase_number(phase: &OperatorPhase) -> i32 {
+    match phase {
+        OperatorPhase::Elite0 => 0,
+        OperatorPhase::Elite1 => 1,
+        OperatorPhase::Elite2 => 2,
+    }
+}
+
+fn skill_level_data<'a>(operator: &'a Operator, unit: &OperatorUnit) -> Option<&'a SkillLevel> {
+    if unit.skill_index < 1 {
+        return None;
+    }
+    operator
+        .skills
+        .get((unit.skill_index - 1) as usize)?
+        .static_data
+        .as_ref()?
+        .levels
+        .get((unit.skill_level - 1) as usize)
+}
+
+/// Fraction of an SP cycle the skill is active. Skills without a duration
+/// (instant or toggled) are treated as always up.
+fn skill_uptime(unit: &OperatorUnit) -> f64 {
+    if unit.skill_duration <= 0.0 || unit.skill_cost <= 0 {
+        return 1.0;
+    }
+    let sp_time = f64::from(unit.skill_cost) / (1.0 + f64::from(unit.sp_boost));
+    unit.skill_duration / (unit.skill_duration + sp_time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ally_and_enemy_sentences_are_told_apart() {
+        let desc = "Increases the ATK of all allies within range by {atk:0%}. \
+                    Enemies hit have their DEF reduced by {-def:0%}";
+        assert_eq!(
+            classify("atk", 0.3, desc).map(|(k, _)| k),
+            Some(SquadEffectKind::Atk)
+        );
+        let (kind, value) = classify("def", -0.25, desc).unwrap();
+        assert_eq!(kind, SquadEffectKind::DefShred);
+        assert!((value - 25.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn self_buffs_are_ignored() {
+        assert!(classify("atk", 0.5, "ATK +{atk:0%}, attack interval increases").is_none());
+        assert!(classify("atk", 0.5, "This Operator gains ATK +{atk:0%}").is_none());
+    }
+
+    #[test]
+    fn received_excludes_self_and_multiplies_percent_shreds() {
+        let shred = |value| SquadEffect {
+            source: EffectSource::Talent1,
+            kind: SquadEffectKind::DefShred,
+            key: "def".into(),
+            value,
+            uptime: 1.0,
+        };
+        let provided = vec![vec![shred(50.0)], vec![shred(20.0)], vec![]];
+
+        let received = received_effects(&provided, 2);
+        assert!((received.def_shred - 60.0).abs() < 1e-9);
+
+        let received = received_effects(&provided, 0);
+        assert!((received.def_shred - 20.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,281 @@
+//! Time-stepped skill-cycle simulation.
+//!
+//! `engine::calculate_dps` collapses a skill cycle into a closed-form average,
+//! which hides when the damage actually lands. This module steps through a
+//! fixed window instead, charging SP the way the skill's `SpType` says it
+//! charges (per second, per attack, or per hit taken), firing the skill when
+//! it's ready and emitting one damage sample per second.
+//!
+//! The simulator only needs the two damage rates the engine already resolves
+//! (on-skill and off-skill DPS), so it stays independent of `OperatorUnit`
+//! and can be exercised with plain numbers.
+
+use serde::Serialize;
+
+/// Internal integration steps per second. Fine enough that attack-driven SP
+/// and skill edges land within a tenth of a second of where they would in game.
+const STEPS_PER_SECOND: usize = 10;
+const STEP: f64 = 1.0 / STEPS_PER_SECOND as f64;
+
+/// Slack for comparing accumulated SP / remaining time, which drift by a few
+/// ULPs over hundreds of steps.
+const EPSILON: f64 = 1e-9;
+
+/// Upper bound on the simulated window, in seconds.
+pub const MAX_DURATION: f64 = 600.0;
+
+/// How a skill gains SP, mirroring `SpData.sp_type` in the skill table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SpRecovery {
+    /// `INCREASE_WITH_TIME` - 1 SP per second.
+    Auto,
+    /// `INCREASE_WHEN_ATTACK` - 1 SP per attack.
+    Offensive,
+    /// `INCREASE_WHEN_TAKEN_DAMAGE` - 1 SP per hit taken.
+    Defensive,
+}
+
+impl SpRecovery {
+    pub fn from_sp_type(sp_type: &str) -> Self {
+        match sp_type {
+            "INCREASE_WHEN_ATTACK" => Self::Offensive,
+            "INCREASE_WHEN_TAKEN_DAMAGE" => Self::Defensive,
+            _ => Self::Auto,
+        }
+    }
+}
+
+/// Everything the simulator needs about one operator configuration.
+#[derive(Debug, Clone)]
+pub struct TimelineInputs {
+    /// DPS while the skill is active. For skills without a duration this is
+    /// already the engine's cycle-averaged value.
+    pub skill_dps: f64,
+    /// DPS from basic attacks while the skill is charging.
+    pub off_skill_dps: f64,
+    /// Skill duration in seconds; `<= 0` for instant / ammo / toggle skills.
+    pub skill_duration: f64,
+    pub skill_cost: i32,
+    pub init_sp: i32,
+    pub sp_recovery: SpRecovery,
+    /// Flat SP/s from external sources (e.g. Ptilopsis / Ling buffs). Only
+    /// auto-recovery skills benefit, as in game.
+    pub sp_boost: f64,
+    /// Attacks per second, used for `Offensive` SP gain.
+    pub attacks_per_second: f64,
+    /// Hits taken per second, used for `Defensive` SP gain.
+    pub hits_taken_per_second: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineSample {
+    /// End of the one-second bucket this sample covers.
+    pub time: f64,
+    pub damage: f64,
+    pub cumulative_damage: f64,
+    /// Whether the skill was active at any point in this second.
+    pub skill_active: bool,
+    /// SP at the end of the bucket (0 while the skill is running).
+    pub sp: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineResult {
+    pub duration: f64,
+    pub skill_dps: f64,
+    pub off_skill_dps: f64,
+    pub total_damage: f64,
+    pub average_dps: f64,
+    pub sp_recovery: SpRecovery,
+    pub skill_cost: i32,
+    pub init_sp: i32,
+    /// Times (seconds) at which the skill was activated.
+    pub activations: Vec<f64>,
+    /// Total seconds spent with the skill active.
+    pub uptime: f64,
+    pub samples: Vec<TimelineSample>,
+}
+
+/// Step the skill cycle across `duration` seconds.
+///
+/// Skills without a duration (or without an SP cost) have no distinct on/off
+/// phase: `skill_dps` already averages them, so it's applied flat across the
+/// window with no activations.
+pub fn simulate(inputs: &TimelineInputs, duration: f64) -> TimelineResult {
+    let duration = duration.clamp(1.0, MAX_DURATION);
+    let seconds = duration.ceil() as usize;
+
+    let cycles = inputs.skill_duration > 0.0 && inputs.skill_cost > 0;
+    let cost = f64::from(inputs.skill_cost);
+    let sp_rate = match inputs.sp_recovery {
+        SpRecovery::Auto => 1.0 + inputs.sp_boost,
+        SpRecovery::Offensive => inputs.attacks_per_second,
+        SpRecovery::Defensive => inputs.hits_taken_per_second,
+    };
+
+    let mut sp = f64::from(inputs.init_sp).min(cost);
+    let mut remaining = 0.0_f64;
+    let mut activations = Vec::new();
+    let mut uptime = 0.0;
+    let mut cumulative = 0.0;
+    let mut samples = Vec::with_capacity(seconds);
+
+    for second in 0..seconds {
+        let bucket_end = ((second + 1) as f64).min(duration);
+        let mut damage = 0.0;
+        let mut active_in_bucket = false;
+
+        for step in 0..STEPS_PER_SECOND {
+            let t = second as f64 + step as f64 * STEP;
+            if t >= duration {
+                break;
+            }
+            let dt = STEP.min(duration - t);
+
+            if !cycles {
+                damage += inputs.skill_dps * dt;
+                continue;
+            }
+
+            if remaining <= EPSILON && sp + EPSILON >= cost {
+                activations.push(round_time(t));
+                remaining = inputs.skill_duration;
+                sp = 0.0;
+            }
+
+            if remaining > EPSILON {
+                // The skill may end partway through a step; the tail of the
+                // step falls back to basic attacks and starts charging again.
+                let on = dt.min(remaining);
+                let off = dt - on;
+                damage += inputs.skill_dps * on + inputs.off_skill_dps * off;
+                sp += sp_rate * off;
+                remaining -= on;
+                uptime += on;
+                active_in_bucket = true;
+            } else {
+                damage += inputs.off_skill_dps * dt;
+                sp += sp_rate * dt;
+            }
+            sp = sp.min(cost);
+        }
+
+        cumulative += damage;
+        samples.push(TimelineSample {
+            time: bucket_end,
+            damage,
+            cumulative_damage: cumulative,
+            skill_active: active_in_bucket,
+            sp: if remaining > EPSILON { 0.0 } else { sp },
+        });
+    }
+
+    TimelineResult {
+        duration,
+        skill_dps: inputs.skill_dps,
+        off_skill_dps: inputs.off_skill_dps,
+        total_damage: cumulative,
+        average_dps: cumulative / duration,
+        sp_recovery: inputs.sp_recovery,
+        skill_cost: inputs.skill_cost,
+        init_sp: inputs.init_sp,
+        activations,
+        uptime: round_time(uptime),
+        samples,
+    }
+}
+
+/// Snap accumulated float time back onto the step grid.
+fn round_time(t: f64) -> f64 {
+    let steps = STEPS_PER_SECOND as f64;
+    (t * steps).round() / steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs(sp_recovery: SpRecovery) -> TimelineInputs {
+        TimelineInputs {
+            skill_dps: 1000.0,
+            off_skill_dps: 100.0,
+            skill_duration: 10.0,
+            skill_cost: 20,
+            init_sp: 10,
+            sp_recovery,
+            sp_boost: 0.0,
+            attacks_per_second: 0.5,
+            hits_taken_per_second: 0.0,
+        }
+    }
+
+    #[test]
+    fn auto_skill_fires_after_remaining_sp_and_cycles() {
+        let result = simulate(&inputs(SpRecovery::Auto), 60.0);
+
+        // 10 init SP + 10s charge -> first cast at 10s, then every 30s.
+        assert_eq!(result.activations, vec![10.0, 40.0]);
+        assert!((result.uptime - 20.0).abs() < 1e-6);
+        assert_eq!(result.samples.len(), 60);
+
+        let expected = 20.0 * 1000.0 + 40.0 * 100.0;
+        assert!((result.total_damage - expected).abs() < 1e-6);
+        assert!((result.average_dps - expected / 60.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn offensive_skill_charges_per_attack() {
+        let result = simulate(&inputs(SpRecovery::Offensive), 60.0);
+
+        // 0.5 SP/s from attacks: first cast after 20s of charging.
+        assert_eq!(result.activations.first().copied(), Some(20.0));
+    }
+
+    #[test]
+    fn sp_boost_only_speeds_up_auto_recovery() {
+        let mut boosted = inputs(SpRecovery::Offensive);
+        boosted.sp_boost = 0.5;
+        let result = simulate(&boosted, 60.0);
+        assert_eq!(result.activations.first().copied(), Some(20.0));
+
+        boosted.sp_recovery = SpRecovery::Auto;
+        let result = simulate(&boosted, 60.0);
+        // 1.5 SP/s: the missing 10 SP take 6.67s.
+        assert!((result.activations[0] - 6.7).abs() < 0.11);
+    }
+
+    #[test]
+    fn sp_never_exceeds_cost() {
+        let mut fast = inputs(SpRecovery::Auto);
+        fast.sp_boost = 50.0;
+        fast.skill_duration = 0.25;
+        let result = simulate(&fast, 30.0);
+        assert!(result.samples.iter().all(|s| s.sp <= 20.0));
+    }
+
+    #[test]
+    fn defensive_skill_never_fires_without_incoming_hits() {
+        let result = simulate(&inputs(SpRecovery::Defensive), 60.0);
+
+        assert!(result.activations.is_empty());
+        assert!((result.total_damage - 6000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn durationless_skill_is_flat() {
+        let mut flat = inputs(SpRecovery::Auto);
+        flat.skill_duration = -1.0;
+        let result = simulate(&flat, 30.0);
+
+        assert!(result.activations.is_empty());
+        assert!(
+            result
+                .samples
+                .iter()
+                .all(|s| (s.damage - 1000.0).abs() < 1e-6)
+        );
+    }
+}
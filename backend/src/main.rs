@@ -1,4 +1,4 @@
-use arc_swap::ArcSwap;
+use arc_swap::{ArcSwap, ArcSwapOption};
 use backend::app::server;
 use backend::core::hypergryph::{config, loaders};
 use backend::core::{
@@ -8,10 +8,14 @@ use backend::core::{
 use backend::{
     app::{
         cache::store::CacheStore,
-        state::{AppConfig, AppState, ServerData, derive_assets_dir, derive_game_data_dir},
+        state::{
+            AppConfig, AppState, ServerData, derive_assets_dir, derive_game_data_dir,
+            next_load_generation,
+        },
     },
     core::{
         gamedata::assets::AssetIndex,
+        gamedata_search::SearchIndex,
         hypergryph::{config::GlobalConfig, constants::Server},
     },
 };
@@ -19,6 +23,7 @@ use dotenv::dotenv;
 use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
 use tracing::{info, warn};
 
 #[cfg(not(target_env = "msvc"))]
@@ -56,6 +61,7 @@ async fn main() {
         )
         .unwrap_or_else(|e| panic!("failed to load game data for {}: {e}", srv.as_str()));
         let asset_index = AssetIndex::build(Path::new(&assets_dir));
+        let search_index = SearchIndex::build(&game_data);
         info!(
             server = srv.as_str(),
             operators = game_data.operators.len(),
@@ -65,7 +71,10 @@ async fn main() {
             srv,
             Arc::new(ServerData {
                 game_data: ArcSwap::from_pointee(game_data),
+                generation: AtomicU64::new(next_load_generation()),
+                previous_game_data: ArcSwapOption::empty(),
                 asset_index: ArcSwap::from_pointee(asset_index),
+                search_index: ArcSwap::from_pointee(search_index),
                 game_data_dir,
                 assets_dir,
             }),
@@ -0,0 +1,40 @@
+//! Diffs the DPS engine against the golden snapshot written by `dps-coverage`.
+
+use backend::dps::coverage::{self, SNAPSHOT_TOLERANCE, Snapshot};
+use std::path::Path;
+
+mod common;
+
+#[test]
+#[ignore = "needs tests/fixtures/dps_snapshot.json: generate it with \
+            `cargo run --release --bin dps-coverage`, commit it, and drop this attribute"]
+fn dps_matches_golden_snapshot() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/dps_snapshot.json");
+    let data = std::fs::read_to_string(&path).unwrap_or_else(|e| {
+        panic!(
+            "{}: {e} - run `cargo run --release --bin dps-coverage` to create it",
+            path.display()
+        )
+    });
+    let expected: Snapshot = serde_json::from_str(&data).expect("Invalid dps_snapshot.json");
+
+    let game_data = common::load_game_data();
+    let (report, actual) = coverage::build(game_data);
+    let diff = coverage::diff(&expected, &actual, SNAPSHOT_TOLERANCE);
+
+    for (key, old, new) in diff.changed.iter().take(20) {
+        eprintln!(
+            "{key}: skill {} -> {}, avg {} -> {}",
+            old.skill_dps, new.skill_dps, old.average_dps, new.average_dps
+        );
+    }
+    assert!(
+        diff.is_empty(),
+        "DPS snapshot drift: {} changed, {} added, {} removed ({} engine failures). \
+         Re-run `dps-coverage` if the change is intended.",
+        diff.changed.len(),
+        diff.added.len(),
+        diff.removed.len(),
+        report.failures.len(),
+    );
+}